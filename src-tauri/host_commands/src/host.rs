@@ -1,21 +1,61 @@
 use std::{
-    borrow::Cow,
     fs::OpenOptions,
     path::PathBuf,
     sync::{
-        atomic::{AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
         Arc, Mutex,
     },
     time::UNIX_EPOCH,
 };
 
 use crate::{
-    DataId, FileInfo, FileSlot, FileStatus, FirefoxProfileInfo, FoundSessionFile, OutputFormat,
-    PathId, TabGroup,
+    CombinedUrlsSummary, DataId, DeltaExportSummary, DomainFilters, FileInfo, FileSlot, FileStatus, FirefoxProfileInfo,
+    FoundSessionFile, GenerateOptions, LoadProgress, OutputFormat, OutputOptions, PathId, ProfileExport, SavePathStatus, SaveSummary,
+    SavedLinks, SavedUiState, SessionStats, StepTimings, TabCounts, TabEntry, TabGroup, TabInfo, TreeSource,
 };
-use firefox_session_data::session_store::FirefoxSessionStore;
+use compression::Compression;
+use closed_tabs::ParsedSession;
 use tauri_commands::const_cfg;
 
+mod closed_tabs;
+mod compression;
+mod config;
+mod containers;
+mod custom_formats;
+mod encoding;
+mod export_history;
+mod extensions;
+mod file_associations;
+mod generate;
+mod html_window_filter;
+mod invalid_trees;
+mod json_repair;
+mod markdown_toc;
+#[cfg(not(target_family = "wasm"))]
+mod profiles_ini;
+mod saved_ui_state;
+#[cfg(not(target_family = "wasm"))]
+mod session_bundle;
+mod tab_walk;
+#[cfg(test)]
+mod test_session;
+mod typst;
+
+/// Decompress `data` without copying it. Firefox's LZ4 format is a single
+/// block that must be available in full while the output is written, so peak
+/// memory is the compressed plus the decompressed size.
+fn decompress(
+    data: &[u8],
+    compressed_with: Compression,
+    max_bytes: u64,
+) -> Result<Vec<u8>, String> {
+    match compressed_with {
+        Compression::Gzip => compression::decompress_gzip(data, max_bytes),
+        Compression::Zstd => compression::decompress_zstd(data, max_bytes),
+        Compression::MozLz4 => compression::decompress_moz_lz4(data),
+    }
+}
+
 /// A version of [`tokio::task::spawn_blocking`] that works for the WebAssembly
 /// target where we don't have access to threads, in that case we simply block
 /// the runtime (i.e. the event loop).
@@ -31,6 +71,68 @@ where
     }
 }
 
+/// Wait for `fut` to complete but give up after `timeout_secs` seconds and
+/// call `on_timeout`, which should tell the work to stop. The work then gets
+/// [`TIMEOUT_CLEANUP_GRACE`] to stop and clean up after itself. There are no
+/// threads on the WebAssembly target so the work can't be interrupted and the
+/// timeout is ignored there.
+pub async fn with_timeout<F, T>(
+    fut: F,
+    timeout_secs: Option<u64>,
+    on_timeout: impl FnOnce(),
+) -> Result<T, String>
+where
+    F: std::future::Future<Output = Result<T, String>>,
+{
+    const_cfg!(if cfg!(target_family = "wasm") {
+        let _ = (timeout_secs, on_timeout);
+        fut.await
+    } else {
+        let Some(secs) = timeout_secs else {
+            return fut.await;
+        };
+        let mut fut = std::pin::pin!(fut);
+        match tokio::time::timeout(std::time::Duration::from_secs(secs), &mut fut).await {
+            Ok(result) => result,
+            Err(_) => {
+                on_timeout();
+                let _ = tokio::time::timeout(TIMEOUT_CLEANUP_GRACE, fut).await;
+                Err(format!("generation timed out after {secs} seconds"))
+            }
+        }
+    })
+}
+
+/// Progress of [`load_data`](super::FileManagementCommands::load_data), shared
+/// with the thread that reads the file.
+#[derive(Debug, Default)]
+pub struct LoadCounter {
+    bytes_read: AtomicU64,
+    total_bytes: AtomicU64,
+}
+impl LoadCounter {
+    fn start(&self, total_bytes: u64) {
+        self.bytes_read.store(0, Ordering::Relaxed);
+        self.total_bytes.store(total_bytes, Ordering::Relaxed);
+    }
+    fn set_read(&self, bytes_read: u64) {
+        self.bytes_read.store(bytes_read, Ordering::Relaxed);
+        // The file might have grown since reading started:
+        self.total_bytes.fetch_max(bytes_read, Ordering::Relaxed);
+    }
+    fn finish(&self, bytes_read: u64) {
+        // The file might also have shrunk:
+        self.total_bytes.store(bytes_read, Ordering::Relaxed);
+        self.bytes_read.store(bytes_read, Ordering::Relaxed);
+    }
+    fn get(&self) -> LoadProgress {
+        LoadProgress {
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            total_bytes: self.total_bytes.load(Ordering::Relaxed),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct FileState {
     pub path_id: PathId,
@@ -38,9 +140,36 @@ pub struct FileState {
     pub file_path: Option<PathBuf>,
     pub is_compressed: bool,
     pub data: Option<Arc<[u8]>>,
-    pub session: Option<Arc<FirefoxSessionStore>>,
+    pub session: Option<Arc<ParsedSession>>,
+    pub timings: StepTimings,
+    /// Hash of the file's data as it was loaded, used to identify the file
+    /// when comparing with earlier exports.
+    pub content_hash: Option<u64>,
+    /// How much of the file has been read by `load_data`.
+    pub load_progress: Arc<LoadCounter>,
+    /// Size of the uncompressed data, remembered when it is freed after
+    /// parsing.
+    pub uncompressed_bytes: Option<u64>,
+    /// Number of windows and tabs in the parsed session.
+    pub window_count: Option<u32>,
+    pub tab_count: Option<u32>,
+    /// Describes what was lost if only part of damaged data could be parsed.
+    pub parse_warning: Option<String>,
+    /// The data was taken by `decompress_data` and will be replaced by the
+    /// decompressed data once it is done.
+    pub decompressing: bool,
 }
 impl FileState {
+    /// The loaded data, or an error that explains why it isn't available.
+    fn loaded_data(&self) -> Result<&Arc<[u8]>, &'static str> {
+        self.data.as_ref().ok_or(if self.decompressing {
+            "the data is still being decompressed"
+        } else {
+            "file data not loaded"
+        })
+    }
+
+
     pub fn to_info(&self) -> FileInfo {
         FileInfo {
             file_path: self
@@ -51,7 +180,7 @@ impl FileState {
             data_id: self.data_id,
             status: if self.session.is_some() {
                 FileStatus::Parsed
-            } else if self.data.is_some() {
+            } else if self.data.is_some() || self.decompressing {
                 if self.is_compressed {
                     FileStatus::Compressed
                 } else {
@@ -62,6 +191,15 @@ impl FileState {
             } else {
                 FileStatus::Empty
             },
+            uncompressed_bytes: self.uncompressed_bytes.or_else(|| {
+                self.data
+                    .as_ref()
+                    .filter(|_| !self.is_compressed)
+                    .map(|data| data.len() as u64)
+            }),
+            window_count: self.window_count,
+            tab_count: self.tab_count,
+            parse_warning: self.parse_warning.clone(),
         }
     }
 }
@@ -74,6 +212,14 @@ impl Default for FileState {
             is_compressed: true,
             data: None,
             session: None,
+            timings: Default::default(),
+            content_hash: None,
+            load_progress: Default::default(),
+            uncompressed_bytes: None,
+            window_count: None,
+            tab_count: None,
+            parse_warning: None,
+            decompressing: false,
         }
     }
 }
@@ -82,15 +228,58 @@ pub struct UiState {
     pub current_file: FileState,
     pub new_file: FileState,
     pub save_path: Option<PathBuf>,
+    /// Measure how long each step of processing a file takes.
+    pub record_timings: bool,
+    /// Data that would be larger than this once decompressed isn't
+    /// decompressed, so that a damaged or malicious file can't use up all
+    /// memory. Remembered between restarts.
+    pub max_decompressed_bytes: u64,
+    /// The data and options that were used by the latest successful call to
+    /// [`FileManagementCommands::save_links`], used by
+    /// [`FileManagementCommands::save_links_again`].
+    pub last_export: Option<(DataId, GenerateOptions)>,
+    /// Files that were recently selected or loaded, the most recent first.
+    /// Remembered between restarts.
+    pub recent_paths: Vec<PathBuf>,
+    /// Add loaded files to [`UiState::recent_paths`]. Only the state of the
+    /// UI does this, so that exports that use separate state don't overwrite
+    /// the recent files.
+    pub track_recent_paths: bool,
+    /// Previews returned by [`FileManagementCommands::to_text_links`].
+    pub preview_cache: PreviewCache,
+    /// Saves that are in progress, so that they can be cancelled by
+    /// [`cancel_saves`] when the program is closed.
+    #[cfg(not(target_family = "wasm"))]
+    pub active_saves: Vec<ActiveSave>,
     #[cfg(target_family = "wasm")]
     pub handle_saved_data: Box<dyn FnMut(Vec<u8>, &'static str) -> Result<(), String> + Send + 'static>,
+    /// Take a stream that was opened for the save so that saved data is
+    /// written to it instead of being handed to `handle_saved_data`. Returns
+    /// `None` if no stream was opened, then `handle_saved_data` is used
+    /// instead.
+    #[cfg(target_family = "wasm")]
+    pub open_saved_stream: Box<dyn FnMut(&'static str) -> Option<SavedStream> + Send + 'static>,
 }
+
+/// Writes saved data to a stream, see [`UiState::open_saved_stream`]. The
+/// returned future completes once all data was written and the stream was
+/// closed.
+#[cfg(target_family = "wasm")]
+pub type SavedStream = Box<
+    dyn FnOnce(
+        Vec<u8>,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<(), String>>>>,
+>;
 impl std::fmt::Debug for UiState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("UiState")
             .field("current_file", &self.current_file)
             .field("new_file", &self.new_file)
             .field("save_path", &self.save_path)
+            .field("record_timings", &self.record_timings)
+            .field("max_decompressed_bytes", &self.max_decompressed_bytes)
+            .field("last_export", &self.last_export)
+            .field("recent_paths", &self.recent_paths)
             .finish()
     }
 }
@@ -99,17 +288,88 @@ impl Default for UiState {
         Self {
             current_file: Default::default(),
             new_file: Default::default(),
-            // TODO: more robust finding of downloads folder.
-            save_path: std::env::var("USERPROFILE")
-                .map(|home| home + r"\Downloads\firefox-links")
-                .map(Into::into)
-                .ok(),
+            save_path: None,
+            record_timings: false,
+            max_decompressed_bytes: DEFAULT_MAX_DECOMPRESSED_BYTES,
+            last_export: None,
+            preview_cache: PreviewCache::default(),
+            #[cfg(not(target_family = "wasm"))]
+            active_saves: Vec::new(),
+            recent_paths: Vec::new(),
+            track_recent_paths: false,
             #[cfg(target_family = "wasm")]
             handle_saved_data: Box::new(|_, _| Ok(())),
+            #[cfg(target_family = "wasm")]
+            open_saved_stream: Box::new(|_| None),
         }
     }
 }
 impl UiState {
+    /// The state of the program when it starts, with the settings and recent
+    /// files that were remembered from earlier runs.
+    pub fn load() -> Self {
+        let saved_settings = read_settings();
+        Self {
+            save_path: saved_settings
+                .as_ref()
+                .and_then(|settings| settings.get("save_path")?.as_str())
+                .map(PathBuf::from)
+                .or_else(|| {
+                    const_cfg!(if cfg!(target_family = "wasm") {
+                        None
+                    } else {
+                        dirs::download_dir().map(|downloads| downloads.join("firefox-links"))
+                    })
+                }),
+            max_decompressed_bytes: saved_max_decompressed_bytes(saved_settings.as_ref()),
+            recent_paths: config::read_json(RECENT_FILES_FILE)
+                .unwrap_or_else(|_e| {
+                    #[cfg(debug_assertions)]
+                    {
+                        eprintln!("Failed to read recent files: {_e}");
+                    }
+                    None
+                })
+                .map(|files| config::string_list(&files, "paths"))
+                .unwrap_or_default()
+                .into_iter()
+                .map(PathBuf::from)
+                .collect(),
+            track_recent_paths: true,
+            ..Self::default()
+        }
+    }
+    /// Move a file to the front of the recent files, adding it if needed.
+    pub fn remember_recent_path(&mut self, path: PathBuf) {
+        if !self.track_recent_paths {
+            return;
+        }
+        self.recent_paths.retain(|recent| *recent != path);
+        self.recent_paths.insert(0, path);
+        self.recent_paths.truncate(MAX_RECENT_PATHS);
+        if let Err(_e) = self.save_recent_paths() {
+            #[cfg(debug_assertions)]
+            {
+                eprintln!("Failed to save recent files: {_e}");
+            }
+        }
+    }
+    /// Settings that are stored in this state between restarts, written to
+    /// [`SETTINGS_FILE`] by [`write_settings`].
+    fn settings(&self) -> serde_json::Value {
+        serde_json::json!({
+            "save_path": self.save_path.as_ref().map(|path| path.to_string_lossy()),
+            "max_decompressed_bytes": self.max_decompressed_bytes,
+        })
+    }
+    fn save_recent_paths(&self) -> Result<(), String> {
+        let paths = self
+            .recent_paths
+            .iter()
+            .map(|path| path.to_string_lossy())
+            .collect::<Vec<_>>();
+        config::write_json(RECENT_FILES_FILE, &serde_json::json!({ "paths": paths }))
+    }
     pub fn get_file_mut(&mut self, slot: FileSlot) -> &mut FileState {
         match slot {
             FileSlot::New => &mut self.new_file,
@@ -136,6 +396,464 @@ impl UiState {
     }
 }
 
+/// Sessionstore files that Firefox writes inside a profile folder, the most
+/// frequently written first.
+const SESSION_FILE_CANDIDATES: [&str; 4] = [
+    "sessionstore.jsonlz4",
+    "sessionstore-backups/recovery.jsonlz4",
+    "sessionstore-backups/recovery.baklz4",
+    "sessionstore-backups/previous.jsonlz4",
+];
+
+/// Magic bytes at the start of Firefox's LZ4 compressed files.
+const MOZ_LZ4_MAGIC: &[u8] = b"mozLz40\0";
+
+/// Error message for files that were clearly not written by Firefox.
+const NOT_SESSION_FILE: &str = "this doesn't look like a Firefox sessionstore file";
+
+/// Check if file data is compressed. The header is trusted over the file's
+/// extension since decompressed copies of sessionstore files are sometimes
+/// saved with a `.jsonlz4` extension and the other way around.
+fn is_compressed_data(data: &[u8], path: &std::path::Path) -> bool {
+    if Compression::detect(data).is_some() {
+        return true;
+    }
+    let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+    let looks_like_json = data.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'{');
+    // Other data with an lz4 extension fails with a clear error when it is
+    // decompressed:
+    !looks_like_json
+        && path
+            .extension()
+            .and_then(|ext| ext.to_str().map(|v| v.ends_with("lz4")))
+            .unwrap_or(false)
+}
+
+/// Check if JSON data that couldn't be parsed as a session is at least an
+/// object with the top-level keys of a sessionstore file. Returns `None` if
+/// the data isn't valid JSON, since then it might be a damaged sessionstore
+/// file.
+fn has_session_keys(data: &[u8]) -> Option<bool> {
+    let first = data.iter().find(|b| !b.is_ascii_whitespace());
+    if first != Some(&b'{') {
+        return Some(false);
+    }
+    let object = serde_json::from_slice::<serde_json::Map<String, serde_json::Value>>(data).ok()?;
+    Some(object.contains_key("windows") || object.contains_key("_closedWindows"))
+}
+
+/// Uncompressed data that should be parsed and whether the time it takes
+/// should be recorded.
+fn data_to_parse(state: &Mutex<UiState>, id: DataId) -> Result<(Arc<[u8]>, bool), String> {
+    let mut guard = state.lock().unwrap();
+    let record_timings = guard.record_timings;
+    let host_data = guard
+        .get_file_for_data_id(id)
+        .ok_or("file id has expired")?;
+
+    let data = host_data.loaded_data()?.clone();
+
+    if host_data.is_compressed {
+        return Err("can't parse compressed data".to_string());
+    }
+    Ok((data, record_timings))
+}
+
+/// The file that the data was loaded from, used to find files in the same
+/// Firefox profile.
+fn session_file_path(state: &Mutex<UiState>, id: DataId) -> Option<PathBuf> {
+    state
+        .lock()
+        .unwrap()
+        .get_file_for_data_id(id)?
+        .file_path
+        .clone()
+}
+
+/// Number of open and closed windows and the tabs in them.
+fn count_windows_and_tabs(session: &ParsedSession) -> (u32, u32) {
+    let groups = tab_walk::selected_groups(session, &GenerateOptions::default());
+    let tab_count = groups
+        .iter()
+        .map(|(_, _, group)| group.tabs().len() as u32)
+        .sum::<u32>();
+    (groups.len() as u32, tab_count)
+}
+
+/// Describe where JSON data couldn't be parsed, for example
+/// `at line 1 column 12: expected value near "{"windows": >>>]}"`.
+fn describe_json_error(data: &[u8], error: &serde_json::Error) -> String {
+    /// Bytes of context to show before and after the error.
+    const CONTEXT: usize = 40;
+
+    let (line, column) = (error.line(), error.column());
+    // The error's message ends with its location, which is written first:
+    let message = error.to_string();
+    let message = message
+        .strip_suffix(&format!(" at line {line} column {column}"))
+        .unwrap_or(&message);
+
+    // Lines and columns are 1-based and columns count bytes:
+    let line_start = if line <= 1 {
+        Some(0)
+    } else {
+        data.iter()
+            .enumerate()
+            .filter(|(_, &b)| b == b'\n')
+            .nth(line - 2)
+            .map(|(ix, _)| ix + 1)
+    };
+    let Some(offset) = line_start.map(|start| (start + column.saturating_sub(1)).min(data.len()))
+    else {
+        return format!("at line {line} column {column}: {message}");
+    };
+    let printable = |bytes: &[u8]| {
+        String::from_utf8_lossy(bytes)
+            .chars()
+            .map(|c| if c.is_control() { ' ' } else { c })
+            .collect::<String>()
+    };
+    let before = printable(&data[offset.saturating_sub(CONTEXT)..offset]);
+    let after = printable(&data[offset..(offset + CONTEXT).min(data.len())]);
+    format!("at line {line} column {column}: {message} near \"{before}>>>{after}\"")
+}
+
+/// The profile folder that a sessionstore file is stored in, assuming it is
+/// one of the [`SESSION_FILE_CANDIDATES`].
+fn profile_dir(session_file: &std::path::Path) -> Option<&std::path::Path> {
+    let parent = session_file.parent()?;
+    if parent.file_name().is_some_and(|name| name == "sessionstore-backups") {
+        parent.parent()
+    } else {
+        Some(parent)
+    }
+}
+
+/// Suggested name for saved links, for example
+/// `firefox-tabs-default-release-2024-05-01`. The profile name is only included
+/// if the sessionstore file was loaded from a Firefox profile. There is no
+/// extension since the output format can be changed after the file is picked.
+#[cfg(not(target_family = "wasm"))]
+fn default_save_file_name(session_file: Option<&std::path::Path>) -> String {
+    let date = chrono::DateTime::<chrono::Utc>::from(std::time::SystemTime::now())
+        .format("%Y-%m-%d")
+        .to_string();
+    let profile = session_file
+        .and_then(profile_dir)
+        .filter(|profile| profile.join("prefs.js").exists())
+        .and_then(|profile| profile.file_name()?.to_str())
+        // Profile folders are named like "<random>.<profile name>":
+        .map(|name| name.split_once('.').map_or(name, |(_, name)| name))
+        .map(|name| {
+            name.chars()
+                .map(|c| if c.is_alphanumeric() || c == '_' { c } else { '-' })
+                .collect::<String>()
+        })
+        .filter(|name| !name.is_empty());
+    match profile {
+        Some(profile) => format!("firefox-tabs-{profile}-{date}"),
+        None => format!("firefox-tabs-{date}"),
+    }
+}
+
+/// The folder where Firefox stores `profiles.ini` on the current platform.
+#[cfg(not(target_family = "wasm"))]
+fn firefox_data_dir() -> Option<PathBuf> {
+    if cfg!(windows) {
+        // %APPDATA%:
+        Some(dirs::data_dir()?.join(r"Mozilla\Firefox"))
+    } else if cfg!(target_os = "macos") {
+        // ~/Library/Application Support:
+        Some(dirs::data_dir()?.join("Firefox"))
+    } else {
+        Some(dirs::home_dir()?.join(".mozilla/firefox"))
+    }
+}
+
+/// The folder where Firefox stores its profiles on the current platform.
+#[cfg(not(target_family = "wasm"))]
+fn firefox_profiles_dir() -> Option<PathBuf> {
+    let data_dir = firefox_data_dir()?;
+    if cfg!(any(windows, target_os = "macos")) {
+        Some(data_dir.join("Profiles"))
+    } else {
+        Some(data_dir)
+    }
+}
+
+/// Find profiles by looking for folders with sessionstore files or
+/// preferences inside [`firefox_profiles_dir`].
+#[cfg(not(target_family = "wasm"))]
+fn scan_firefox_profiles_dir() -> Vec<(PathBuf, Option<std::time::SystemTime>)> {
+    let Some(entries) = firefox_profiles_dir().and_then(|dir| std::fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let is_profile = path.join("prefs.js").exists()
+                || SESSION_FILE_CANDIDATES
+                    .iter()
+                    .any(|suffix| path.join(suffix).exists());
+            if !is_profile {
+                return None;
+            }
+            let modified_at = path.metadata().and_then(|meta| meta.modified()).ok();
+            Some((path, modified_at))
+        })
+        .collect()
+}
+
+/// Name of the config file that stores the domain allowlist and blocklist.
+const DOMAIN_FILTERS_FILE: &str = "domain-filters.json";
+
+/// Name of the config file that stores [`UiState::recent_paths`].
+const RECENT_FILES_FILE: &str = "recent-files.json";
+
+/// Name of the config file that stores settings from [`UiState`], currently
+/// the save path and the decompression size limit.
+const SETTINGS_FILE: &str = "settings.json";
+
+/// Default for [`UiState::max_decompressed_bytes`]. Even sessions with
+/// thousands of tabs are usually much smaller.
+const DEFAULT_MAX_DECOMPRESSED_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// How long [`cancel_saves`] keeps removing the files of saves that haven't
+/// stopped yet.
+#[cfg(not(target_family = "wasm"))]
+const CANCEL_SAVES_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// How long work that timed out gets to stop and remove its partial output in
+/// [`with_timeout`].
+#[cfg(not(target_family = "wasm"))]
+const TIMEOUT_CLEANUP_GRACE: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// How long the save path must stay the same before it is written to
+/// [`SETTINGS_FILE`].
+#[cfg(not(target_family = "wasm"))]
+const SAVE_PATH_DEBOUNCE: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Incremented every time the settings change so that a delayed write of the
+/// save path doesn't overwrite newer settings.
+static SETTINGS_EDITS: AtomicU64 = AtomicU64::new(0);
+
+/// Read the settings that [`write_settings`] saved.
+fn read_settings() -> Option<serde_json::Value> {
+    config::read_json(SETTINGS_FILE).unwrap_or_else(|_e| {
+        #[cfg(debug_assertions)]
+        {
+            eprintln!("Failed to read settings: {_e}");
+        }
+        None
+    })
+}
+
+/// The remembered [`UiState::max_decompressed_bytes`] from the settings.
+fn saved_max_decompressed_bytes(settings: Option<&serde_json::Value>) -> u64 {
+    settings
+        .and_then(|settings| settings.get("max_decompressed_bytes")?.as_u64())
+        .unwrap_or(DEFAULT_MAX_DECOMPRESSED_BYTES)
+}
+
+/// Write settings from [`UiState::settings`] to [`SETTINGS_FILE`]. Call
+/// without holding the lock of the state.
+fn write_settings(settings: &serde_json::Value) -> Result<(), String> {
+    config::write_json(SETTINGS_FILE, settings)
+}
+
+/// How many files are kept in [`UiState::recent_paths`].
+const MAX_RECENT_PATHS: usize = 10;
+
+/// Name of the config file that stores the UI state between restarts.
+const UI_STATE_FILE: &str = "ui-state.json";
+
+/// How long the UI state must stay the same before it is written to
+/// [`UI_STATE_FILE`].
+#[cfg(not(target_family = "wasm"))]
+const UI_STATE_DEBOUNCE: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Incremented every time the UI state is saved so that only the latest state
+/// is written to [`UI_STATE_FILE`].
+static UI_STATE_EDITS: AtomicU64 = AtomicU64::new(0);
+
+/// Milliseconds since `start` if timings are being recorded.
+fn elapsed_ms(record_timings: bool, start: web_time::Instant) -> Option<u64> {
+    record_timings.then(|| start.elapsed().as_millis() as u64)
+}
+
+/// Create the file that output should be written to. Adds `file_ext` to the
+/// path if it doesn't have an extension.
+#[cfg(not(target_family = "wasm"))]
+fn create_output_file(
+    save_path: &mut PathBuf,
+    file_ext: &str,
+    output_options: &OutputOptions,
+) -> Result<std::fs::File, String> {
+    if save_path.extension().is_none() {
+        save_path.set_extension(file_ext);
+    }
+
+    if let Some(folder) = save_path.parent() {
+        if output_options.create_folder {
+            std::fs::create_dir_all(folder).map_err(|e| {
+                format!("failed to create folder at \"{}\": {e}", folder.display())
+            })?;
+        }
+    }
+
+    OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .create(true)
+        .create_new(!output_options.overwrite)
+        .open(&*save_path)
+        .map_err(|e| {
+            format!(
+                "failed to create new file at \"{}\": {e}",
+                save_path.display()
+            )
+        })
+}
+
+/// Recently generated previews so that selecting the same windows again
+/// doesn't need to walk the whole session again. The options include the
+/// selected windows.
+#[derive(Debug, Default)]
+pub struct PreviewCache {
+    /// The most recently used preview is last.
+    entries: Vec<(DataId, GenerateOptions, String)>,
+}
+impl PreviewCache {
+    /// Previews of large sessions can be big, so only keep a few.
+    const CAPACITY: usize = 8;
+
+    fn get(&mut self, id: DataId, generate_options: &GenerateOptions) -> Option<String> {
+        let ix = self
+            .entries
+            .iter()
+            .position(|(data_id, options, _)| *data_id == id && options == generate_options)?;
+        let entry = self.entries.remove(ix);
+        let links = entry.2.clone();
+        self.entries.push(entry);
+        Some(links)
+    }
+
+    fn insert(&mut self, id: DataId, generate_options: GenerateOptions, links: String) {
+        self.entries
+            .retain(|(data_id, options, _)| !(*data_id == id && *options == generate_options));
+        if self.entries.len() >= Self::CAPACITY {
+            self.entries.remove(0);
+        }
+        self.entries.push((id, generate_options, links));
+    }
+
+    /// Forget previews of all data except the specified data.
+    fn retain_data(&mut self, id: DataId) {
+        self.entries.retain(|(data_id, ..)| *data_id == id);
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// A save that is in progress, see [`UiState::active_saves`].
+#[cfg(not(target_family = "wasm"))]
+#[derive(Debug)]
+pub struct ActiveSave {
+    /// Only the save itself holds a strong reference, so the save has
+    /// finished once this can't be upgraded.
+    cancelled: std::sync::Weak<AtomicBool>,
+    /// Files that the save writes, including the restore bundle, that didn't
+    /// exist before the save started. Files that are overwritten aren't
+    /// included since they can't be restored.
+    created_files: Vec<PathBuf>,
+}
+
+/// Cancel all saves that are in progress and remove the files they created.
+/// Should be called before the program exits.
+///
+/// Doesn't block: a background thread removes the files, and keeps trying for
+/// a little while since files might be created or still be open until their
+/// save notices that it was cancelled.
+#[cfg(not(target_family = "wasm"))]
+pub fn cancel_saves(state: &Mutex<UiState>) {
+    let mut saves = std::mem::take(&mut state.lock().unwrap().active_saves);
+    for save in &saves {
+        if let Some(cancelled) = save.cancelled.upgrade() {
+            cancelled.store(true, Ordering::Release);
+        }
+    }
+    if saves.is_empty() {
+        return;
+    }
+
+    std::thread::spawn(move || {
+        let deadline = std::time::Instant::now() + CANCEL_SAVES_TIMEOUT;
+        loop {
+            // Check before removing so that the files are removed once more
+            // after the save stopped:
+            saves.retain(|save| {
+                let running = save.cancelled.strong_count() > 0;
+                for path in &save.created_files {
+                    let _ = std::fs::remove_file(path);
+                }
+                running
+            });
+            if saves.is_empty() || std::time::Instant::now() >= deadline {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+    });
+}
+
+/// Write a HTML document and save the exported windows to a sessionstore file
+/// next to it. The document links to the sessionstore file so that the tabs
+/// can be restored.
+#[cfg(not(target_family = "wasm"))]
+fn write_links_with_restore_bundle(
+    session: &ParsedSession,
+    generate_options: &GenerateOptions,
+    export_id: Option<&str>,
+    source_path: Option<&std::path::Path>,
+    max_bytes: u64,
+    save_path: &std::path::Path,
+    output_options: &OutputOptions,
+    stop: &generate::StopFlags,
+    mut output: impl std::io::Write,
+) -> Result<(), String> {
+    let source_path = source_path
+        .ok_or("can't save a restore bundle for sessionstore data that wasn't loaded from a file")?;
+    let mut session_json = session_bundle::read_session_json(source_path, max_bytes)?;
+    session_bundle::retain_windows(
+        &mut session_json,
+        &tab_walk::selected_windows(session, generate_options),
+    );
+
+    let mut bundle_path = save_path.with_extension("jsonlz4");
+    if stop.is_stopped() {
+        return Err("saving was stopped".to_string());
+    }
+    let bundle_file = create_output_file(&mut bundle_path, "jsonlz4", output_options)?;
+    session_bundle::write_jsonlz4(&session_json, stop.writer(bundle_file))?;
+    let bundle_name = bundle_path.file_name().unwrap_or_default().to_string_lossy();
+
+    let mut html = Vec::new();
+    generate::write_links(
+        session,
+        generate_options,
+        output_options.format,
+        export_id,
+        generate::PdfPageOptions::new(output_options)?,
+        stop,
+        &mut html,
+    )?;
+    output
+        .write_all(&session_bundle::insert_restore_link(&html, &bundle_name))
+        .map_err(|e| format!("failed to write document: {e}"))
+}
+
 impl PathId {
     pub fn new() -> PathId {
         static NEXT_ID: AtomicU64 = AtomicU64::new(1);
@@ -149,6 +867,341 @@ impl DataId {
     }
 }
 
+/// The most recently modified session file of a profile that exists. Reads
+/// the metadata of the files so call it on a blocking thread.
+fn newest_session_file(session_files: &[FoundSessionFile]) -> Option<String> {
+    session_files
+        .iter()
+        .filter_map(|file| {
+            let modified = std::fs::metadata(&file.file_path).ok()?.modified().ok()?;
+            Some((modified, &file.file_path))
+        })
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, file_path)| file_path.clone())
+}
+
+/// The save path for the `number`th window when each window is saved to its
+/// own file, for example `links-window-1.pdf` for `links.pdf`.
+fn window_save_path(save_path: &std::path::Path, number: usize) -> PathBuf {
+    let stem = save_path.file_stem().unwrap_or_default().to_string_lossy();
+    let mut file_name = format!("{stem}-window-{number}");
+    if let Some(ext) = save_path.extension() {
+        file_name.push('.');
+        file_name.push_str(&ext.to_string_lossy());
+    }
+    save_path.with_file_name(file_name)
+}
+
+/// A file name without extension for the export of a Firefox profile. Characters
+/// that aren't allowed in file names are replaced and profiles with the same
+/// name get a number so that they aren't saved to the same file.
+fn profile_file_stem(
+    profile_name: &str,
+    used_stems: &mut std::collections::HashSet<String>,
+) -> String {
+    let stem = profile_name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>();
+    // Windows doesn't allow names that end with a dot or a space:
+    let stem = stem.trim().trim_end_matches('.');
+    let stem = if stem.is_empty() { "profile" } else { stem };
+
+    // File names are case insensitive on Windows and macOS:
+    let mut unique = stem.to_owned();
+    let mut number = 1;
+    while !used_stems.insert(unique.to_lowercase()) {
+        number += 1;
+        unique = format!("{stem} ({number})");
+    }
+    unique
+}
+
+/// A file that [`write_links_file`] wrote.
+#[derive(Debug)]
+struct WrittenFile {
+    /// Empty on the WebAssembly target where the document is handed to the
+    /// UI.
+    path: PathBuf,
+    /// The file didn't exist before it was written.
+    created_new: bool,
+    /// Number of characters that were replaced since they can't be
+    /// represented in the text encoding.
+    replaced: usize,
+}
+
+/// Generate a document with links and write it to `save_path`, used by
+/// [`FileManagementCommands::save_links`](crate::FileManagementCommands::save_links).
+/// On the WebAssembly target the document is handed to `open_saved_stream` or
+/// `handle_saved_data` of the [`UiState`] instead.
+#[allow(clippy::too_many_arguments)]
+async fn write_links_file(
+    _state: &Mutex<UiState>,
+    session: Arc<ParsedSession>,
+    generate_options: GenerateOptions,
+    output_options: OutputOptions,
+    mut save_path: PathBuf,
+    _source_path: Option<PathBuf>,
+    pdf_page: generate::PdfPageOptions,
+    export_id: Option<String>,
+) -> Result<WrittenFile, String> {
+    // Set if the generation timed out or was cancelled, writes to the output
+    // will fail after that and any partial output is discarded:
+    let timed_out = Arc::new(AtomicBool::new(false));
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let generation_timeout_secs = output_options.generation_timeout_secs;
+
+    let restore_bundle = cfg!(not(target_family = "wasm"))
+        && output_options.restore_bundle
+        && output_options.format == OutputFormat::HTML;
+
+    #[cfg(not(target_family = "wasm"))]
+    let max_bytes;
+    #[cfg(not(target_family = "wasm"))]
+    let (created_new, bundle_created_new) = {
+        if save_path.extension().is_none() {
+            save_path.set_extension(generate::file_extension(output_options.format));
+        }
+        let (created_new, bundle_created_new) = spawn_blocking({
+            let save_path = save_path.clone();
+            move || {
+                (
+                    !save_path.exists(),
+                    restore_bundle && !save_path.with_extension("jsonlz4").exists(),
+                )
+            }
+        })
+        .await;
+        let mut guard = _state.lock().unwrap();
+        max_bytes = guard.max_decompressed_bytes;
+        guard
+            .active_saves
+            .retain(|save| save.cancelled.strong_count() > 0);
+        guard.active_saves.push(ActiveSave {
+            cancelled: Arc::downgrade(&cancelled),
+            created_files: [
+                Some(save_path.clone()).filter(|_| created_new),
+                Some(save_path.with_extension("jsonlz4")).filter(|_| bundle_created_new),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+        });
+        (created_new, bundle_created_new)
+    };
+    #[cfg(target_family = "wasm")]
+    let created_new = false;
+    let written_path = save_path.clone();
+
+    let generation = spawn_blocking({
+        let timed_out = timed_out.clone();
+        move || -> Result<_, String> {
+            let file_ext = generate::file_extension(output_options.format);
+            let stop = generate::StopFlags(vec![timed_out.clone(), cancelled.clone()]);
+
+            let mut file = {
+                #[cfg(target_family = "wasm")]
+                {
+                    Vec::new()
+                }
+                #[cfg(not(target_family = "wasm"))]
+                {
+                    stop.writer(create_output_file(&mut save_path, file_ext, &output_options)?)
+                }
+            };
+
+            let written = if restore_bundle {
+                // Only HTML, which is always UTF-8:
+                const_cfg!(if cfg!(target_family = "wasm") {
+                    Ok(0)
+                } else {
+                    write_links_with_restore_bundle(
+                        &session,
+                        &generate_options,
+                        export_id.as_deref(),
+                        _source_path.as_deref(),
+                        max_bytes,
+                        &save_path,
+                        &output_options,
+                        &stop,
+                        &mut file,
+                    )
+                    .map(|()| 0)
+                })
+            } else {
+                generate::write_encoded_links(
+                    &session,
+                    &generate_options,
+                    output_options.format,
+                    export_id.as_deref(),
+                    pdf_page,
+                    output_options.text_encoding,
+                    &stop,
+                    &mut file,
+                )
+            };
+
+            let was_timed_out = timed_out.load(Ordering::Acquire);
+            let stopped = was_timed_out || cancelled.load(Ordering::Acquire);
+            // Don't leave partial output behind:
+            #[cfg(not(target_family = "wasm"))]
+            if stopped || written.is_err() {
+                drop(file);
+                if was_timed_out || created_new {
+                    let _ = std::fs::remove_file(&save_path);
+                }
+                if restore_bundle && (was_timed_out || bundle_created_new) {
+                    let _ = std::fs::remove_file(save_path.with_extension("jsonlz4"));
+                }
+            }
+            if stopped {
+                return Err(if was_timed_out {
+                    "generation timed out".to_string()
+                } else {
+                    "saving was cancelled".to_string()
+                });
+            }
+            let replaced = written?;
+
+            #[cfg(target_family = "wasm")]
+            {
+                Ok((replaced, file, file_ext))
+            }
+            #[cfg(not(target_family = "wasm"))]
+            {
+                Ok(replaced)
+            }
+        }
+    });
+    let generated = with_timeout(generation, generation_timeout_secs, || {
+        timed_out.store(true, Ordering::Release);
+    })
+    .await;
+    // The work can be stuck outside of the writes, for example in a PDF
+    // backend, so don't wait for it to remove the partial output:
+    #[cfg(not(target_family = "wasm"))]
+    if generated.is_err() && timed_out.load(Ordering::Acquire) {
+        let path = written_path.clone();
+        spawn_blocking(move || {
+            let _ = std::fs::remove_file(&path);
+            if restore_bundle {
+                let _ = std::fs::remove_file(path.with_extension("jsonlz4"));
+            }
+        })
+        .await;
+    }
+    let generated = generated?;
+
+    #[cfg(target_family = "wasm")]
+    let replaced = {
+        let (replaced, data, file_ext) = generated;
+        let stream = (_state.lock().unwrap().open_saved_stream)(file_ext);
+        match stream {
+            // Generation is synchronous on this target so it can't wait for
+            // the stream, instead the finished document is written to it:
+            Some(write) => write(data).await?,
+            None => (_state.lock().unwrap().handle_saved_data)(data, file_ext)?,
+        }
+        replaced
+    };
+    #[cfg(not(target_family = "wasm"))]
+    let replaced = generated;
+
+    Ok(WrittenFile {
+        path: written_path,
+        created_new,
+        replaced,
+    })
+}
+
+/// Remove the files that a save created before it failed. Returns the files
+/// that were kept since they existed before and were overwritten.
+async fn remove_created_files(written: Vec<WrittenFile>) -> Vec<PathBuf> {
+    spawn_blocking(move || {
+        written
+            .into_iter()
+            .filter(|file| !file.path.as_os_str().is_empty())
+            .filter_map(|file| {
+                if file.created_new && std::fs::remove_file(&file.path).is_ok() {
+                    None
+                } else {
+                    Some(file.path)
+                }
+            })
+            .collect()
+    })
+    .await
+}
+
+/// Open what [`save_links`](super::FileManagementCommands::save_links) saved
+/// with the default program: the file itself or the folder that contains the
+/// files if there are several of them. Failures are returned to be shown
+/// with the status of the save since the save itself succeeded.
+#[cfg(not(target_family = "wasm"))]
+fn reveal_saved_output(saved_path: &std::path::Path, file_count: u32) -> Result<(), String> {
+    let target = if file_count == 1 {
+        saved_path
+    } else {
+        saved_path
+            .parent()
+            .ok_or("failed to find the folder of the saved files")?
+    };
+    opener::open(target).map_err(|e| format!("failed to open \"{}\": {e}", target.display()))
+}
+
+/// A path that was typed or pasted as the file to load. Surrounding quotes
+/// (added by "Copy as path" on Windows) are removed and `file://` URLs are
+/// converted to paths.
+fn normalize_input_path(text: &str) -> PathBuf {
+    let text = text.trim();
+    let text = text
+        .strip_prefix('"')
+        .and_then(|text| text.strip_suffix('"'))
+        .unwrap_or(text);
+    let Some(url_path) = text
+        .get(.."file://".len())
+        .filter(|scheme| scheme.eq_ignore_ascii_case("file://"))
+        .map(|scheme| &text[scheme.len()..])
+    else {
+        return PathBuf::from(text);
+    };
+    let url_path = url_path.strip_prefix("localhost").unwrap_or(url_path);
+
+    // Decode escaped characters like `%20`:
+    let mut bytes = Vec::with_capacity(url_path.len());
+    let mut rest = url_path.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let escaped = (byte == b'%')
+            .then(|| tail.get(..2))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        if let Some(escaped) = escaped {
+            bytes.push(escaped);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    let path = String::from_utf8_lossy(&bytes).into_owned();
+
+    if cfg!(windows) {
+        match path.strip_prefix('/') {
+            // Drive letter like `file:///C:/Users`:
+            Some(local) if local.as_bytes().get(1) == Some(&b':') => PathBuf::from(local),
+            Some(_) => PathBuf::from(path),
+            // Network share like `file://server/share`:
+            None => PathBuf::from(format!(r"\\{}", path.replace('/', "\\"))),
+        }
+    } else {
+        PathBuf::from(path)
+    }
+}
+
 #[derive(Debug, Clone, Copy, Default)]
 pub struct HostCommands;
 
@@ -156,25 +1209,97 @@ pub struct HostCommands;
 #[cfg_attr(not(target_family = "wasm"), async_trait::async_trait)]
 impl super::StatelessCommands for HostCommands {
     async fn format_descriptions(&self) -> Vec<(OutputFormat, String)> {
-        use firefox_session_data::to_links::ttl_formats::FormatInfo;
         OutputFormat::all()
             .iter()
-            .map(|&f| (f, FormatInfo::from(f).to_string()))
+            .map(|&f| {
+                let description = match f.format_info() {
+                    Some(info) => info.to_string(),
+                    None => f.custom_description().unwrap_or_default().to_owned(),
+                };
+                (f, description)
+            })
             .collect()
     }
+    async fn load_domain_filters(&self) -> Result<DomainFilters, String> {
+        let Some(filters) = config::read_json(DOMAIN_FILTERS_FILE)? else {
+            return Ok(DomainFilters::default());
+        };
+        Ok(DomainFilters {
+            allowlist: config::string_list(&filters, "allowlist"),
+            blocklist: config::string_list(&filters, "blocklist"),
+        })
+    }
+    async fn save_domain_filters(&self, filters: DomainFilters) -> Result<(), String> {
+        config::write_json(
+            DOMAIN_FILTERS_FILE,
+            &serde_json::json!({
+                "allowlist": filters.allowlist,
+                "blocklist": filters.blocklist,
+            }),
+        )
+    }
+    async fn save_session_state(&self, state: SavedUiState) -> Result<(), String> {
+        let edit = UI_STATE_EDITS.fetch_add(1, Ordering::SeqCst) + 1;
+
+        // The state changes while the user types, so write it once it stops
+        // changing:
+        #[cfg(not(target_family = "wasm"))]
+        tokio::time::sleep(UI_STATE_DEBOUNCE).await;
+        if UI_STATE_EDITS.load(Ordering::SeqCst) != edit {
+            return Ok(());
+        }
+        let value = saved_ui_state::to_json(&state);
+        spawn_blocking(move || config::write_json(UI_STATE_FILE, &value)).await
+    }
+    async fn restore_session_state(&self) -> Result<Option<SavedUiState>, String> {
+        Ok(config::read_json(UI_STATE_FILE)?.map(|value| saved_ui_state::from_json(&value)))
+    }
+    async fn register_file_associations(&self) -> Result<(), String> {
+        file_associations::register()
+    }
     async fn find_firefox_profiles(&self) -> Result<Vec<FirefoxProfileInfo>, String> {
-        let finder = ::firefox_session_data::find::FirefoxProfileFinder::new()
-            .map_err(|e| format!("{e}"))?;
-        let profiles = finder.all_profiles().map_err(|e| format!("{e}"))?;
-        Ok(profiles
+        let found = ::firefox_session_data::find::FirefoxProfileFinder::new()
+            .map_err(|e| format!("{e}"))
+            .and_then(|finder| finder.all_profiles().map_err(|e| format!("{e}")))
+            .map(|profiles| {
+                profiles
+                    .iter()
+                    .map(|(path, time)| (path.to_path_buf(), time.as_ref().ok().copied()))
+                    .collect::<Vec<_>>()
+            });
+
+        // The finder might not know where profiles are stored on every
+        // platform, so also look in the usual folder:
+        #[cfg(not(target_family = "wasm"))]
+        let found = {
+            let scanned = scan_firefox_profiles_dir();
+            match found {
+                Err(_) if !scanned.is_empty() => Ok(scanned),
+                Err(e) => Err(e),
+                Ok(mut profiles) => {
+                    for (path, time) in scanned {
+                        if !profiles.iter().any(|(known, _)| *known == path) {
+                            profiles.push((path, time));
+                        }
+                    }
+                    Ok(profiles)
+                }
+            }
+        };
+        let profiles = found?;
+
+        let default_profiles = const_cfg!(if cfg!(target_family = "wasm") {
+            Vec::<PathBuf>::new()
+        } else {
+            firefox_data_dir()
+                .map(|dir| profiles_ini::default_profiles(&dir))
+                .unwrap_or_default()
+        });
+
+        let mut profiles = profiles
             .iter()
             .filter_map(|(path, time)| {
-                let potential = [
-                    "sessionstore.jsonlz4",
-                    "sessionstore-backups/recovery.jsonlz4",
-                    "sessionstore-backups/recovery.baklz4",
-                    "sessionstore-backups/previous.jsonlz4",
-                ];
+                let potential = SESSION_FILE_CANDIDATES;
                 let mut session_files: Vec<_> = potential
                     .into_iter()
                     .filter_map(|suffix| {
@@ -209,12 +1334,23 @@ impl super::StatelessCommands for HostCommands {
                     file_path: path.to_str()?.to_owned(),
                     modified_at: time
                         .as_ref()
-                        .ok()
                         .map(|time| time.duration_since(UNIX_EPOCH).unwrap().as_secs()),
+                    is_default: default_profiles.iter().any(|default| default == path),
                     session_files,
                 })
             })
-            .collect())
+            .collect::<Vec<_>>();
+        // Default profiles first, then the most recently used:
+        profiles.sort_by_key(|profile| {
+            (
+                !profile.is_default,
+                std::cmp::Reverse(profile.modified_at),
+            )
+        });
+        Ok(profiles)
+    }
+    async fn typst_available_fonts(&self) -> Vec<String> {
+        spawn_blocking(typst::available_fonts).await
     }
 }
 
@@ -258,7 +1394,6 @@ impl super::FilePromptCommands for HostCommands {
         )) {
             unimplemented!("the file_open command isn't implemented for this target");
         } else {
-            use std::{env, path::PathBuf};
             #[cfg(feature = "tauri-export")]
             use tauri_plugin_dialog::DialogExt;
 
@@ -270,12 +1405,11 @@ impl super::FilePromptCommands for HostCommands {
             } else {
                 rfd::AsyncFileDialog::new().set_parent(&**cx)
             })
-            .add_filter("Firefox session file", &["js", "baklz4", "jsonlz4"])
+            .add_filter("Firefox session file", &["js", "json", "baklz4", "jsonlz4", "gz", "zst"])
             .add_filter("All files", &["*"])
             .set_title("Open Firefox Sessionstore File");
-            if let Some(data) = env::var_os("APPDATA") {
-                let data = PathBuf::from(data);
-                builder = builder.set_directory(data.join("Mozilla\\Firefox\\Profiles"));
+            if let Some(profiles) = firefox_profiles_dir() {
+                builder = builder.set_directory(profiles);
             }
 
             let file_path = const_cfg!(if cfg!(feature = "tauri-export") {
@@ -290,6 +1424,7 @@ impl super::FilePromptCommands for HostCommands {
             });
 
             let mut guard = state.lock().unwrap();
+            guard.remember_recent_path(file_path.clone());
             let file_info = guard.get_file_mut(slot);
             *file_info = Default::default();
             file_info.path_id = PathId::new();
@@ -320,7 +1455,10 @@ impl super::FilePromptCommands for HostCommands {
                 rfd::AsyncFileDialog::new().set_parent(&**cx)
             })
             // .add_filter("All files", &["*"])
-            .set_title("Save Links from Firefox Tabs");
+            .set_title("Save Links from Firefox Tabs")
+            .set_file_name(default_save_file_name(
+                state.lock().unwrap().current_file.file_path.as_deref(),
+            ));
 
             let path = const_cfg!(if cfg!(feature = "tauri-export") {
                 builder.save_file(move |file_path| {
@@ -346,6 +1484,10 @@ impl super::FileManagementCommands for HostCommands {
     async fn get_info_for_slot(&self, state: Self::State<'_>, slot: FileSlot) -> FileInfo {
         state.lock().unwrap().get_file_mut(slot).to_info()
     }
+    async fn all_file_infos(&self, state: Self::State<'_>) -> Vec<FileInfo> {
+        let guard = state.lock().unwrap();
+        vec![guard.new_file.to_info(), guard.current_file.to_info()]
+    }
     async fn get_info_for_path_id(&self, state: Self::State<'_>, id: PathId) -> Option<FileInfo> {
         Some(state.lock().unwrap().get_file_for_path_id(id)?.to_info())
     }
@@ -362,20 +1504,86 @@ impl super::FileManagementCommands for HostCommands {
         let file_info = guard.get_file_mut(slot);
         *file_info = Default::default();
         file_info.path_id = PathId::new();
-        file_info.file_path = Some(file_path.into());
+        file_info.file_path = Some(normalize_input_path(&file_path));
         file_info.path_id
     }
     async fn set_save_path(&self, state: Self::State<'_>, file_path: String) {
-        let mut guard = state.lock().unwrap();
-        guard.save_path = Some(file_path.into());
-    }
-    async fn get_save_path(&self, state: Self::State<'_>) -> Option<String> {
-        let guard = state.lock().unwrap();
-        guard
-            .save_path
-            .clone()
-            .map(|p| p.to_string_lossy().into_owned())
-    }
+        let (edit, settings) = {
+            let mut guard = state.lock().unwrap();
+            guard.save_path = Some(file_path.into());
+            (SETTINGS_EDITS.fetch_add(1, Ordering::SeqCst) + 1, guard.settings())
+        };
+
+        // The path changes while the user types, so write it once it stops
+        // changing without making the caller wait:
+        let write = async move {
+            #[cfg(not(target_family = "wasm"))]
+            tokio::time::sleep(SAVE_PATH_DEBOUNCE).await;
+
+            // Only write the latest settings:
+            if SETTINGS_EDITS.load(Ordering::SeqCst) != edit {
+                return;
+            }
+            if let Err(_e) = spawn_blocking(move || write_settings(&settings)).await {
+                #[cfg(debug_assertions)]
+                {
+                    eprintln!("Failed to save settings: {_e}");
+                }
+            }
+        };
+        const_cfg!(if cfg!(target_family = "wasm") {
+            write.await;
+        } else {
+            tokio::spawn(write);
+        });
+    }
+    async fn get_save_path(&self, state: Self::State<'_>) -> Option<String> {
+        let guard = state.lock().unwrap();
+        guard
+            .save_path
+            .clone()
+            .map(|p| p.to_string_lossy().into_owned())
+    }
+    async fn validate_save_path(
+        &self,
+        _state: Self::State<'_>,
+        file_path: String,
+        format: OutputFormat,
+    ) -> SavePathStatus {
+        const_cfg!(if cfg!(target_family = "wasm") {
+            let _ = (file_path, format);
+            SavePathStatus {
+                folder_exists: true,
+                ..Default::default()
+            }
+        } else {
+            let file_ext = generate::file_extension(format);
+            let mut path = PathBuf::from(file_path);
+            let expected_extension = match path.extension() {
+                None => {
+                    path.set_extension(file_ext);
+                    None
+                }
+                Some(ext) if ext.eq_ignore_ascii_case(file_ext) => None,
+                Some(_) => Some(file_ext.to_owned()),
+            };
+            // The file system can be slow, for example for network drives:
+            spawn_blocking(move || {
+                let folder_exists = match path.parent() {
+                    // Relative to the current directory:
+                    Some(folder) if folder.as_os_str().is_empty() => true,
+                    Some(folder) => folder.is_dir(),
+                    None => false,
+                };
+                SavePathStatus {
+                    folder_exists,
+                    file_exists: path.is_file(),
+                    expected_extension,
+                }
+            })
+            .await
+        })
+    }
 
     async fn forget_data(&self, state: Self::State<'_>, id: DataId) {
         let mut guard = state.lock().unwrap();
@@ -403,9 +1611,22 @@ impl super::FileManagementCommands for HostCommands {
             eprintln!("Forget path with {id:?}");
         }
     }
+    async fn forget_all(&self, state: Self::State<'_>) {
+        let mut guard = state.lock().unwrap();
+        guard.new_file = Default::default();
+        guard.current_file = Default::default();
+        guard.preview_cache.clear();
+        guard.last_export = None;
+        #[cfg(debug_assertions)]
+        {
+            eprintln!("Forget all data");
+        }
+    }
     async fn commit_new_file(&self, state: Self::State<'_>) {
         let mut guard = state.lock().unwrap();
         guard.current_file = std::mem::take(&mut guard.new_file);
+        let data_id = guard.current_file.data_id;
+        guard.preview_cache.retain_data(data_id);
         // Leave path but give it a new id to not cause confusion:
         guard.new_file.path_id = PathId::new();
         guard.new_file.file_path = guard.current_file.file_path.clone();
@@ -435,10 +1656,7 @@ impl super::FileManagementCommands for HostCommands {
                 .clone()
         };
 
-        let is_compressed = path
-            .extension()
-            .and_then(|ext| ext.to_str().map(|v| v.ends_with("lz4")))
-            .unwrap_or(false);
+        let is_compressed = is_compressed_data(&data, &path);
 
         let file_info = guard
             .get_file_for_path_id(id)
@@ -447,46 +1665,77 @@ impl super::FileManagementCommands for HostCommands {
         *file_info = FileState {
             file_path: file_info.file_path.clone(),
             is_compressed,
+            content_hash: Some(export_history::content_hash(&data)),
             data: Some(data.into()),
             data_id: DataId::new(),
             path_id: id,
             session: None,
+            timings: Default::default(),
+            load_progress: Default::default(),
+            uncompressed_bytes: None,
+            window_count: None,
+            tab_count: None,
+            parse_warning: None,
+            decompressing: false,
         };
         Ok(file_info.data_id)
     }
     async fn load_data(&self, state: Self::State<'_>, id: PathId) -> Result<DataId, String> {
-        use std::{
-            fs::File,
-            io::{BufReader, Read},
-        };
-        let path = {
+        use std::{fs::File, io::Read};
+
+        /// Update the progress after reading this many bytes.
+        const CHUNK_SIZE: usize = 1024 * 1024;
+
+        let (path, record_timings, progress) = {
             let mut guard = state.lock().unwrap();
+            let record_timings = guard.record_timings;
             let file_info = guard
                 .get_file_for_path_id(id)
                 .ok_or("path id has expired")?;
 
-            file_info
+            let path = file_info
                 .file_path
                 .as_ref()
                 .ok_or("file hasn't been selected yet")?
-                .clone()
+                .clone();
+            (path, record_timings, file_info.load_progress.clone())
         };
 
+        let start = web_time::Instant::now();
         let (is_compressed, data) = spawn_blocking(move || -> Result<_, String> {
-            let file = File::open(&path)
+            if path.as_os_str().is_empty() {
+                return Err("no file path was entered".to_owned());
+            } else if path.is_dir() {
+                return Err(format!("{} is a folder, not a file", path.display()));
+            } else if !path.exists() {
+                return Err(format!("there is no file at {}", path.display()));
+            }
+            let mut file = File::open(&path)
                 .map_err(|e| format!("failed to open file at {}: {e}", path.display()))?;
 
-            let mut buffer = BufReader::new(file);
-            let mut data = Vec::new();
-
-            buffer
-                .read_to_end(&mut data)
-                .map_err(|e| format!("failed to read file data from {}: {e}", path.display()))?;
+            let total_bytes = file.metadata().map(|meta| meta.len()).unwrap_or(0);
+            progress.start(total_bytes);
+
+            let mut data = Vec::with_capacity(usize::try_from(total_bytes).unwrap_or(0));
+            let mut chunk = vec![0; CHUNK_SIZE];
+            loop {
+                let read = match file.read(&mut chunk) {
+                    Ok(0) => break,
+                    Ok(read) => read,
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                    Err(e) => {
+                        return Err(format!(
+                            "failed to read file data from {}: {e}",
+                            path.display()
+                        ))
+                    }
+                };
+                data.extend_from_slice(&chunk[..read]);
+                progress.set_read(data.len() as u64);
+            }
+            progress.finish(data.len() as u64);
 
-            let is_compressed = path
-                .extension()
-                .and_then(|ext| ext.to_str().map(|v| v.ends_with("lz4")))
-                .unwrap_or(false);
+            let is_compressed = is_compressed_data(&data, &path);
 
             Ok((is_compressed, data))
         })
@@ -500,72 +1749,271 @@ impl super::FileManagementCommands for HostCommands {
         *file_info = FileState {
             file_path: file_info.file_path.clone(),
             is_compressed,
+            content_hash: Some(export_history::content_hash(&data)),
             data: Some(data.into()),
             data_id: DataId::new(),
             path_id: id,
             session: None,
+            timings: StepTimings {
+                load_ms: elapsed_ms(record_timings, start),
+                ..Default::default()
+            },
+            load_progress: file_info.load_progress.clone(),
+            uncompressed_bytes: None,
+            window_count: None,
+            tab_count: None,
+            parse_warning: None,
+            decompressing: false,
         };
-        Ok(file_info.data_id)
+        let data_id = file_info.data_id;
+        if let Some(path) = file_info.file_path.clone() {
+            guard.remember_recent_path(path);
+        }
+        Ok(data_id)
     }
 
-    async fn decompress_data(&self, state: Self::State<'_>, id: DataId) -> Result<(), String> {
-        use {either::Either, std::io::Empty};
+    async fn get_recent_paths(&self, state: Self::State<'_>) -> Vec<String> {
+        let guard = state.lock().unwrap();
+        guard
+            .recent_paths
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect()
+    }
+    async fn clear_recent_paths(&self, state: Self::State<'_>) -> Result<(), String> {
+        let mut guard = state.lock().unwrap();
+        guard.recent_paths.clear();
+        guard.save_recent_paths()
+    }
 
-        let data = {
+    async fn load_progress(&self, state: Self::State<'_>, id: PathId) -> Option<LoadProgress> {
+        let mut guard = state.lock().unwrap();
+        Some(guard.get_file_for_path_id(id)?.load_progress.get())
+    }
+
+    async fn decompress_data(&self, state: Self::State<'_>, id: DataId) -> Result<(), String> {
+        let (data, compressed_with, record_timings, max_bytes) = {
             let mut guard = state.lock().unwrap();
+            let record_timings = guard.record_timings;
+            let max_bytes = guard.max_decompressed_bytes;
             let host_data = guard
                 .get_file_for_data_id(id)
                 .ok_or("file id has expired")?;
 
-            let data = host_data.data.clone().ok_or("file data not loaded")?;
+            let data = host_data.loaded_data()?;
 
             if !host_data.is_compressed {
-                return Err("the data was already uncompressed".to_string());
+                // Nothing to do, for example a plain JSON file:
+                return Ok(());
             }
-            data
+            let compressed_with = Compression::detect(data).ok_or_else(|| {
+                format!("{NOT_SESSION_FILE} (missing mozLz4, gzip or zstd header)")
+            })?;
+            if compressed_with == Compression::MozLz4 {
+                compression::check_moz_lz4_size(data, max_bytes)?;
+            }
+            // Take the data so that it is freed before the decompressed data is
+            // stored:
+            let data = host_data.data.take().ok_or("file data not loaded")?;
+            host_data.decompressing = true;
+            (data, compressed_with, record_timings, max_bytes)
         };
-        let decompressed = spawn_blocking(move || {
-            std::panic::catch_unwind(|| {
-                firefox_session_data::io_utils::decompress_lz4_data(Either::<_, Empty>::Left(
-                    Vec::<u8>::from(&*data).into(),
-                ))
-                .map(|reader| -> Vec<u8> { reader.into() })
-                .map_err(|e| format!("failed to decompress data: {e}"))
-            })
-            .unwrap_or_else(|_| Err("decompression of sessionstore data panicked".to_string()))
+        let start = web_time::Instant::now();
+        let result = spawn_blocking(move || {
+            let result = std::panic::catch_unwind(|| decompress(&data, compressed_with, max_bytes))
+                .unwrap_or_else(|_| Err("decompression of sessionstore data panicked".to_string()));
+            // On success the compressed data is freed here, before the
+            // decompressed data is stored:
+            result.map_err(|e| (e, data))
         })
-        .await?;
+        .await;
 
         let mut guard = state.lock().unwrap();
         let host_data = guard
             .get_file_for_data_id(id)
             .ok_or("file id expired while decompressing")?;
+        host_data.decompressing = false;
+        let decompressed = match result {
+            Ok(decompressed) => decompressed,
+            Err((e, data)) => {
+                host_data.data = Some(data);
+                return Err(e);
+            }
+        };
         host_data.data = Some(decompressed.into());
         host_data.is_compressed = false;
+        host_data.timings.decompress_ms = elapsed_ms(record_timings, start);
         Ok(())
     }
 
     async fn parse_session_data(&self, state: Self::State<'_>, id: DataId) -> Result<(), String> {
-        use firefox_session_data::session_store::FirefoxSessionStore;
-        use std::sync::Arc;
+        let (data, record_timings) = data_to_parse(state, id)?;
+        let session_file = session_file_path(state, id);
+
+        let uncompressed_bytes = data.len() as u64;
+        let start = web_time::Instant::now();
+        let (session, window_count, tab_count, warning) = spawn_blocking(move || {
+            let mut was_repaired = false;
+            let mut session = match ParsedSession::parse(&data) {
+                Ok(session) => session,
+                Err(error) => {
+                    // Only attempt repairs if the data couldn't be parsed as is:
+                    json_repair::repair(&data)
+                        .and_then(|repaired| {
+                            // Closed tabs and containers are read from the
+                            // repaired data as well:
+                            let session = ParsedSession::parse(&repaired).ok()?;
+                            was_repaired = true;
+                            Some(session)
+                        })
+                        .ok_or_else(|| match has_session_keys(&data) {
+                            Some(false) => format!(
+                                "{NOT_SESSION_FILE} (expected JSON with \"windows\" and \"_closedWindows\" fields)"
+                            ),
+                            _ => format!(
+                                "failed to parse sessionstore JSON data {}",
+                                describe_json_error(&data, &error)
+                            ),
+                        })?
+                }
+            };
+            if let Some(session_file) = &session_file {
+                session.read_container_names(session_file);
+            }
+            let (window_count, tab_count) = count_windows_and_tabs(&session);
+            let tree_warning = session.tree_warning();
+            let warning = match (was_repaired, tree_warning) {
+                (true, Some(tree_warning)) => Some(format!(
+                    "the data was malformed and has been repaired, {tree_warning}"
+                )),
+                (true, None) => Some("the data was malformed and has been repaired".to_owned()),
+                (false, tree_warning) => tree_warning,
+            };
+            Ok::<_, String>((session, window_count, tab_count, warning))
+        })
+        .await?;
+
+        let mut guard = state.lock().unwrap();
+        let host_data = guard
+            .get_file_for_data_id(id)
+            .ok_or("file id expired while parsing JSON")?;
+        host_data.parse_warning = warning;
+        host_data.session = Some(Arc::new(session));
+        host_data.data = None; // <- Free memory
+        host_data.uncompressed_bytes = Some(uncompressed_bytes);
+        host_data.window_count = Some(window_count);
+        host_data.tab_count = Some(tab_count);
+        host_data.timings.parse_ms = elapsed_ms(record_timings, start);
 
-        let data = {
+        Ok(())
+    }
+
+    async fn get_raw_json(&self, state: Self::State<'_>, id: DataId) -> Result<String, String> {
+        let to_text = |data: Vec<u8>| {
+            String::from_utf8(data).map_err(|e| format!("the JSON data isn't valid UTF-8: {e}"))
+        };
+        let (source, _max_bytes) = {
             let mut guard = state.lock().unwrap();
+            let max_bytes = guard.max_decompressed_bytes;
             let host_data = guard
                 .get_file_for_data_id(id)
                 .ok_or("file id has expired")?;
-
-            let data = host_data.data.clone().ok_or("file data not loaded")?;
-
-            if host_data.is_compressed {
-                return Err("can't parse compressed data".to_string());
+            if let Some(data) = &host_data.data {
+                if host_data.is_compressed {
+                    return Err("must decompress data before reading its JSON".to_owned());
+                }
+                return to_text(data.to_vec());
+            }
+            if host_data.session.is_none() {
+                return Err("file data not loaded".to_owned());
             }
-            data
+            (host_data.file_path.clone().zip(host_data.content_hash), max_bytes)
         };
 
-        let session = spawn_blocking(move || {
-            serde_json::from_slice::<FirefoxSessionStore>(&data)
-                .map_err(|e| format!("failed to parse sessionstore JSON data: {e}"))
+        // The data was freed when it was parsed, so read it again:
+        const_cfg!(if cfg!(target_family = "wasm") {
+            let _ = source;
+            Err("the JSON data was freed after it was parsed".to_owned())
+        } else {
+            let (path, content_hash) = source.ok_or(
+                "the JSON data was freed after it was parsed and wasn't loaded from a file",
+            )?;
+            spawn_blocking(move || {
+                let data = std::fs::read(&path).map_err(|e| {
+                    format!("failed to read sessionstore file at \"{}\": {e}", path.display())
+                })?;
+                if export_history::content_hash(&data) != content_hash {
+                    return Err(
+                        "the file has changed since it was loaded, reload it to read its JSON"
+                            .to_owned(),
+                    );
+                }
+                to_text(session_bundle::decompress_session_data(data, _max_bytes)?)
+            })
+            .await
+        })
+    }
+
+    async fn parse_session_data_lenient(
+        &self,
+        state: Self::State<'_>,
+        id: DataId,
+    ) -> Result<(), String> {
+        use serde_json::Value;
+
+        let (data, record_timings) = data_to_parse(state, id)?;
+        let session_file = session_file_path(state, id);
+
+        let uncompressed_bytes = data.len() as u64;
+        let start = web_time::Instant::now();
+        let (session, window_count, tab_count, warning) = spawn_blocking(move || {
+            let repaired = json_repair::repair(&data);
+            let data = repaired.as_deref().unwrap_or(&data);
+            let closed = json_repair::close_truncated(data)
+                .ok_or("the data isn't cut off so no windows could be recovered")?;
+            let mut value = serde_json::from_slice::<Value>(&closed.data)
+                .map_err(|e| format!("failed to recover windows from damaged data: {e}"))?;
+            drop(closed.data);
+            let root = value
+                .as_object_mut()
+                .ok_or("failed to recover windows from damaged data: it isn't a JSON object")?;
+            // The data might have been cut off before some top level fields:
+            json_repair::fill_missing_fields(root);
+
+            // Each window is checked on its own so that only the window that
+            // was cut off and windows that can't be read are removed:
+            let skipped = json_repair::keep_readable_windows(
+                &mut value,
+                closed.cut_item_of.as_deref(),
+                ParsedSession::is_readable,
+            );
+            let mut session = ParsedSession::from_value(&value)
+                .map_err(|e| format!("failed to recover any windows from damaged data: {e}"))?;
+            session.copy_invalid_trees(|| Some(value));
+            if let Some(session_file) = &session_file {
+                session.read_container_names(session_file);
+            }
+            let (window_count, tab_count) = count_windows_and_tabs(&session);
+            if window_count == 0 {
+                return Err("the data was cut off before any window was complete".to_string());
+            }
+            let mut warning = format!(
+                "the data was cut off, {} with {} were recovered",
+                tab_walk::count_label(window_count as usize, "window"),
+                tab_walk::count_label(tab_count as usize, "tab"),
+            );
+            if let Some(is_closed) = skipped.cut_off {
+                let kind = if is_closed { "closed" } else { "open" };
+                warning.push_str(&format!(", the {kind} window that was cut off was skipped"));
+            }
+            if skipped.unreadable > 0 {
+                let windows = tab_walk::count_label(skipped.unreadable, "window");
+                warning.push_str(&format!(", skipped {windows} that couldn't be read"));
+            }
+            if let Some(tree_warning) = session.tree_warning() {
+                warning.push_str(&format!(", {tree_warning}"));
+            }
+            Ok::<_, String>((session, window_count, tab_count, warning))
         })
         .await?;
 
@@ -575,6 +2023,11 @@ impl super::FileManagementCommands for HostCommands {
             .ok_or("file id expired while parsing JSON")?;
         host_data.session = Some(Arc::new(session));
         host_data.data = None; // <- Free memory
+        host_data.uncompressed_bytes = Some(uncompressed_bytes);
+        host_data.window_count = Some(window_count);
+        host_data.tab_count = Some(tab_count);
+        host_data.parse_warning = Some(warning);
+        host_data.timings.parse_ms = elapsed_ms(record_timings, start);
 
         Ok(())
     }
@@ -584,9 +2037,8 @@ impl super::FileManagementCommands for HostCommands {
         state: Self::State<'_>,
         id: DataId,
         sort_groups: bool,
+        duplicate_names: crate::DuplicateNameStrategy,
     ) -> Result<crate::AllTabGroups, String> {
-        use firefox_session_data::session_store::session_info::get_groups_from_session;
-
         let session = state
             .lock()
             .unwrap()
@@ -596,21 +2048,26 @@ impl super::FileManagementCommands for HostCommands {
             .clone()
             .ok_or("must deserialize JSON sessionstore data before tab groups can be inspected")?;
 
-        Ok(spawn_blocking(move || crate::AllTabGroups {
-            open: get_groups_from_session(&session, true, false, sort_groups)
-                .enumerate()
-                .map(|(ix, group)| TabGroup {
-                    index: ix as _,
-                    name: group.name().to_owned(),
-                })
-                .collect::<Vec<_>>(),
-            closed: get_groups_from_session(&session, false, true, sort_groups)
-                .enumerate()
-                .map(|(ix, group)| TabGroup {
-                    index: ix as _,
+        Ok(spawn_blocking(move || {
+            let generate_options = GenerateOptions {
+                sort_groups,
+                duplicate_names,
+                ..Default::default()
+            };
+            let mut groups = crate::AllTabGroups::default();
+            for (is_closed, index, group) in tab_walk::selected_groups(&session, &generate_options) {
+                let group = TabGroup {
+                    index,
                     name: group.name().to_owned(),
-                })
-                .collect::<Vec<_>>(),
+                    tab_count: group.tabs().len() as u32,
+                };
+                if is_closed {
+                    groups.closed.push(group);
+                } else {
+                    groups.open.push(group);
+                }
+            }
+            groups
         })
         .await)
     }
@@ -621,88 +2078,44 @@ impl super::FileManagementCommands for HostCommands {
         id: DataId,
         generate_options: crate::GenerateOptions,
     ) -> Result<String, String> {
-        use firefox_session_data::{
-            pdf_converter::html_to_pdf::WriteBuilderSimple,
-            session_store::{
-                session_info::{get_groups_from_session, TreeDataSource},
-                to_links::{LinkFormat, ToLinksOptions},
-            },
-            to_links::TabsToLinksOutput,
+        let (session, record_timings) = {
+            let mut guard = state.lock().unwrap();
+            let record_timings = guard.record_timings;
+            let session = guard
+                .get_file_for_data_id(id)
+                .ok_or("file id has expired")?
+                .session
+                .clone()
+                .ok_or("must deserialize JSON sessionstore data before converting tabs to links")?;
+            if let Some(links) = guard.preview_cache.get(id, &generate_options) {
+                return Ok(links);
+            }
+            (session, record_timings)
         };
 
-        let session = state
-            .lock()
-            .unwrap()
-            .get_file_for_data_id(id)
-            .ok_or("file id has expired")?
-            .session
-            .clone()
-            .ok_or("must deserialize JSON sessionstore data before converting tabs to links")?;
-
-        spawn_blocking(move || {
-            let mut output: Vec<u8> = Vec::new();
-
-            let open_groups =
-                get_groups_from_session(&session, true, false, generate_options.sort_groups)
-                    .enumerate()
-                    .filter(|(ix, _)| {
-                        if let Some(indexes) = &generate_options.open_group_indexes {
-                            indexes.contains(&(*ix as u32))
-                        } else {
-                            true
-                        }
-                    })
-                    .map(|(_, g)| g);
-
-            let closed_groups =
-                get_groups_from_session(&session, false, true, generate_options.sort_groups)
-                    .enumerate()
-                    .filter(|(ix, _)| {
-                        if let Some(indexes) = &generate_options.closed_group_indexes {
-                            indexes.contains(&(*ix as u32))
-                        } else {
-                            true
-                        }
-                    })
-                    .map(|(_, g)| g);
-
-            firefox_session_data::tabs_to_links(
-                &open_groups.chain(closed_groups).collect::<Vec<_>>(),
-                TabsToLinksOutput {
-                    format: LinkFormat::TXT,
-                    as_pdf: None,
-                    conversion_options: ToLinksOptions {
-                        format: LinkFormat::TXT,
-                        page_breaks_after_group: false,
-                        skip_page_break_after_last_group: true,
-                        table_of_contents: generate_options.table_of_content,
-                        indent_all_links: true,
-                        custom_page_break: "".into(),
-                        // If there is any data from Sidebery then TST data
-                        // won't be used and so on:
-                        tree_sources: Cow::Borrowed(&[
-                            TreeDataSource::Sidebery,
-                            TreeDataSource::TstWebExtension,
-                            TreeDataSource::TstLegacy,
-                        ]),
-                    },
-                },
-                WriteBuilderSimple(&mut output),
-            )
-            .map_err(|e| e.to_string())?;
-
-            /// UTF 8 Byte Order Mark. Write to the beginning of a text file to indicate the text encoding of the data.
-            const UTF_8_BOM: &[u8] = b"\xEF\xBB\xBF";
-
-            let output = if output.starts_with(UTF_8_BOM) {
-                &output[UTF_8_BOM.len()..]
-            } else {
-                output.as_slice()
-            };
-
-            Ok(String::from_utf8_lossy(output).into_owned())
+        let start = web_time::Instant::now();
+        let links = spawn_blocking({
+            let generate_options = generate_options.clone();
+            move || {
+                generate::preview_text(
+                    &session,
+                    &generate_options,
+                    &tab_walk::tree_sources(&generate_options),
+                )
+            }
         })
-        .await
+        .await;
+
+        let mut guard = state.lock().unwrap();
+        if let Some(file) = guard.get_file_for_data_id(id) {
+            file.timings.generate_ms = elapsed_ms(record_timings, start);
+        }
+        if let Ok(links) = &links {
+            guard
+                .preview_cache
+                .insert(id, generate_options, links.clone());
+        }
+        links
     }
 
     async fn save_links(
@@ -711,17 +2124,9 @@ impl super::FileManagementCommands for HostCommands {
         id: DataId,
         generate_options: crate::GenerateOptions,
         output_options: crate::OutputOptions,
-    ) -> Result<(), String> {
-        use firefox_session_data::{
-            pdf_converter::html_to_pdf::WriteBuilderSimple,
-            session_store::{
-                session_info::{get_groups_from_session, TreeDataSource},
-                to_links::{LinkFormat, ToLinksOptions},
-            },
-            to_links::{ttl_formats::FormatInfo, TabsToLinksOutput},
-        };
-
-        let (mut save_path, session) = {
+    ) -> Result<SavedLinks, String> {
+        let last_export = (id, generate_options.clone());
+        let (save_path, session, source_path) = {
             let mut guard = state.lock().unwrap();
             let save_path = if cfg!(target_family = "wasm") {
                 Default::default()
@@ -735,126 +2140,174 @@ impl super::FileManagementCommands for HostCommands {
                 .session
                 .clone()
                 .ok_or("must deserialize JSON sessionstore data before converting tabs to links")?;
-            (save_path, session)
+            (save_path, session, file.file_path.clone())
         };
 
-        let _data = spawn_blocking(move || -> Result<_, String> {
-            let (format, as_pdf) = FormatInfo::from(output_options.format)
-                .as_format()
-                .to_link_format();
-
-            let file_ext = if as_pdf.is_some() {
-                "pdf"
-            } else {
-                match format {
-                    LinkFormat::TXT => "txt",
-                    LinkFormat::RTF { .. } => "rtf",
-                    LinkFormat::HTML => "html",
-                    LinkFormat::Markdown => "md",
-                    LinkFormat::Typst => "typ",
-                }
-            };
+        let pdf_page = generate::PdfPageOptions::new(&output_options)?;
+        let export_id = output_options
+            .include_export_id
+            .then(generate::new_export_id);
+        #[cfg(debug_assertions)]
+        if let Some(export_id) = &export_id {
+            eprintln!("Saving links with export ID {export_id}");
+        }
 
-            let mut file = {
-                #[cfg(target_family = "wasm")]
-                {
-                    Vec::new()
+        #[cfg(not(target_family = "wasm"))]
+        let reveal = output_options.reveal_after_save;
+
+        let written = if output_options.split_per_window {
+            let windows = spawn_blocking({
+                let session = session.clone();
+                let generate_options = generate_options.clone();
+                move || {
+                    // Recently closed tabs have the same index as their window
+                    // and are saved in the same file as it:
+                    let mut seen = std::collections::HashSet::new();
+                    tab_walk::selected_groups(&session, &generate_options)
+                        .into_iter()
+                        .map(|(is_closed, index, _)| (is_closed, index))
+                        .filter(|&window| seen.insert(window))
+                        .collect::<Vec<_>>()
                 }
-                #[cfg(not(target_family = "wasm"))]
-                {
-                    if save_path.extension().is_none() {
-                        save_path.set_extension(file_ext);
-                    }
-
-                    if let Some(folder) = save_path.parent() {
-                        if output_options.create_folder {
-                            std::fs::create_dir_all(folder).map_err(|e| {
-                                format!("failed to create folder at \"{}\": {e}", folder.display())
-                            })?;
+            })
+            .await;
+            if windows.is_empty() {
+                return Err("no windows to save".to_string());
+            }
+            let mut written = Vec::with_capacity(windows.len());
+            for (ix, &(is_closed, index)) in windows.iter().enumerate() {
+                let (open, closed) = if is_closed {
+                    (vec![], vec![index])
+                } else {
+                    (vec![index], vec![])
+                };
+                let window_options = GenerateOptions {
+                    open_group_indexes: Some(open),
+                    closed_group_indexes: Some(closed),
+                    ..generate_options.clone()
+                };
+                let result = write_links_file(
+                    state,
+                    session.clone(),
+                    window_options,
+                    output_options.clone(),
+                    window_save_path(&save_path, ix + 1),
+                    source_path.clone(),
+                    pdf_page,
+                    export_id.clone(),
+                )
+                .await;
+                match result {
+                    Ok(file) => written.push(file),
+                    Err(e) => {
+                        // Only keep files from this save that replaced
+                        // existing files, they can't be restored:
+                        let saved_count = written.len();
+                        let kept = remove_created_files(written).await;
+                        let mut message =
+                            format!("failed to save window {} of {}: {e}", ix + 1, windows.len());
+                        if !kept.is_empty() {
+                            let paths = kept
+                                .iter()
+                                .map(|path| format!("\"{}\"", path.display()))
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            message.push_str(&format!(", overwrote {paths} before the failure"));
+                        } else if saved_count > 0 {
+                            message.push_str(", removed the windows that were already saved");
                         }
+                        return Err(message);
                     }
-
-                    OpenOptions::new()
-                        .write(true)
-                        .truncate(true)
-                        .create(true)
-                        .create_new(!output_options.overwrite)
-                        .open(&save_path)
-                        .map_err(|e| {
-                            format!(
-                                "failed to create new file at \"{}\": {e}",
-                                save_path.display()
-                            )
-                        })?
                 }
-            };
-
-            let open_groups =
-                get_groups_from_session(&session, true, false, generate_options.sort_groups)
-                    .enumerate()
-                    .filter(|(ix, _)| {
-                        if let Some(indexes) = &generate_options.open_group_indexes {
-                            indexes.contains(&(*ix as u32))
-                        } else {
-                            true
-                        }
-                    })
-                    .map(|(_, g)| g);
-
-            let closed_groups =
-                get_groups_from_session(&session, false, true, generate_options.sort_groups)
-                    .enumerate()
-                    .filter(|(ix, _)| {
-                        if let Some(indexes) = &generate_options.closed_group_indexes {
-                            indexes.contains(&(*ix as u32))
-                        } else {
-                            true
-                        }
-                    })
-                    .map(|(_, g)| g);
-
-            let page_breaks = !matches!(format, LinkFormat::TXT);
-            let mut tree_sources = Vec::with_capacity(3);
-            if generate_options.sidebery_trees {
-                // Prefer first found source, so if there is any data from
-                // Sidebery then TST data won't be used and so on.
-                tree_sources.push(TreeDataSource::Sidebery);
-            }
-            if generate_options.tree_style_tab_trees {
-                tree_sources.extend_from_slice(&[
-                    TreeDataSource::TstWebExtension,
-                    TreeDataSource::TstLegacy,
-                ]);
-            }
-
-            firefox_session_data::tabs_to_links(
-                &open_groups.chain(closed_groups).collect::<Vec<_>>(),
-                TabsToLinksOutput {
-                    format,
-                    as_pdf,
-                    conversion_options: ToLinksOptions {
-                        format,
-                        // No page break character for text files so fallback to
-                        // several new lines:
-                        page_breaks_after_group: page_breaks,
-                        skip_page_break_after_last_group: page_breaks && (format.is_html() || format.is_typst()),
-                        table_of_contents: generate_options.table_of_content,
-                        indent_all_links: true,
-                        custom_page_break: "".into(),
-                        tree_sources: Cow::Owned(tree_sources),
-                    },
-                },
-                WriteBuilderSimple(&mut file),
+            }
+            written
+        } else {
+            let written = write_links_file(
+                state,
+                session,
+                generate_options,
+                output_options,
+                save_path,
+                source_path,
+                pdf_page,
+                export_id,
             )
-            .map_err(|e| e.to_string())?;
+            .await?;
+            vec![written]
+        };
+        let file_count = written.len() as u32;
+
+        state.lock().unwrap().last_export = Some(last_export);
+        let reveal_error = const_cfg!(if cfg!(target_family = "wasm") {
+            None
+        } else {
+            written
+                .first()
+                .filter(|_| reveal)
+                .and_then(|file| reveal_saved_output(&file.path, file_count).err())
+        });
+        Ok(SavedLinks {
+            file_count,
+            paths: written
+                .iter()
+                .filter(|file| !file.path.as_os_str().is_empty())
+                .map(|file| file.path.to_string_lossy().into_owned())
+                .collect(),
+            replaced_characters: written.iter().map(|file| file.replaced as u64).sum(),
+            reveal_error,
+        })
+    }
+
+    async fn save_text(
+        &self,
+        state: Self::State<'_>,
+        text: String,
+        output_options: OutputOptions,
+    ) -> Result<SavedLinks, String> {
+        let file_ext = generate::file_extension(OutputFormat::TEXT);
+        let (mut save_path, renamed) = if cfg!(target_family = "wasm") {
+            (PathBuf::new(), false)
+        } else {
+            let mut save_path = state
+                .lock()
+                .unwrap()
+                .save_path
+                .clone()
+                .ok_or("no save path selected")?;
+            let renamed = save_path
+                .extension()
+                .is_some_and(|ext| !ext.eq_ignore_ascii_case(file_ext));
+            // Don't write text to a file named like another format:
+            save_path.set_extension(file_ext);
+            (save_path, renamed)
+        };
+        #[cfg(not(target_family = "wasm"))]
+        let reveal = output_options.reveal_after_save;
 
+        let (replaced, _data, save_path) = spawn_blocking(move || -> Result<_, String> {
+            let (data, replaced) =
+                encoding::transcode(text.as_bytes(), output_options.text_encoding);
             #[cfg(target_family = "wasm")]
             {
-                Ok((file, file_ext))
+                let _ = (&mut save_path, renamed, &output_options);
+                Ok((replaced, data, save_path))
             }
             #[cfg(not(target_family = "wasm"))]
             {
-                Ok(())
+                use std::io::Write;
+
+                // The selected path was validated, not the text file next to
+                // it, so never replace that one:
+                if renamed && save_path.exists() {
+                    return Err(format!(
+                        "the preview is saved as text but \"{}\" already exists",
+                        save_path.display()
+                    ));
+                }
+                create_output_file(&mut save_path, file_ext, &output_options)?
+                    .write_all(&data)
+                    .map_err(|e| format!("failed to write text: {e}"))?;
+                Ok((replaced, (), save_path))
             }
         })
         .await?;
@@ -862,9 +2315,964 @@ impl super::FileManagementCommands for HostCommands {
         #[cfg(target_family = "wasm")]
         {
             let mut guard = state.lock().unwrap();
-            (guard.handle_saved_data)(_data.0, _data.1)?;
+            (guard.handle_saved_data)(_data, file_ext)?;
         }
+        let reveal_error = const_cfg!(if cfg!(target_family = "wasm") {
+            None
+        } else {
+            reveal
+                .then(|| reveal_saved_output(&save_path, 1).err())
+                .flatten()
+        });
+        Ok(SavedLinks {
+            file_count: 1,
+            paths: if save_path.as_os_str().is_empty() {
+                Vec::new()
+            } else {
+                vec![save_path.to_string_lossy().into_owned()]
+            },
+            replaced_characters: replaced as u64,
+            reveal_error,
+        })
+    }
 
-        Ok(())
+    async fn save_links_again(
+        &self,
+        state: Self::State<'_>,
+        output_options: OutputOptions,
+    ) -> Result<SavedLinks, String> {
+        let (id, generate_options) = state
+            .lock()
+            .unwrap()
+            .last_export
+            .clone()
+            .ok_or("nothing has been saved yet")?;
+        self.save_links(state, id, generate_options, output_options)
+            .await
+    }
+
+    async fn load_and_export(
+        &self,
+        state: Self::State<'_>,
+        file_path: String,
+        generate_options: GenerateOptions,
+        output_options: OutputOptions,
+        save_path: String,
+    ) -> Result<SaveSummary, String> {
+        if cfg!(target_family = "wasm") {
+            return Err("can't load files from a path inside a web page".to_string());
+        }
+
+        // Use separate state so that the files shown in the UI aren't affected,
+        // it also doesn't track recent files:
+        let export_state = {
+            let guard = state.lock().unwrap();
+            Mutex::new(UiState {
+                save_path: Some(save_path.into()),
+                record_timings: guard.record_timings,
+                max_decompressed_bytes: guard.max_decompressed_bytes,
+                ..Default::default()
+            })
+        };
+        let export_state = &export_state;
+
+        let path_id = self
+            .set_open_path(export_state, FileSlot::New, file_path.clone())
+            .await;
+        let data_id = self
+            .load_data(export_state, path_id)
+            .await
+            .map_err(|e| format!("failed to load \"{file_path}\": {e}"))?;
+        let is_compressed = export_state.lock().unwrap().new_file.is_compressed;
+        if is_compressed {
+            self.decompress_data(export_state, data_id)
+                .await
+                .map_err(|e| format!("failed to decompress \"{file_path}\": {e}"))?;
+        }
+        self.parse_session_data(export_state, data_id)
+            .await
+            .map_err(|e| format!("failed to parse \"{file_path}\": {e}"))?;
+        self.save_links(export_state, data_id, generate_options.clone(), output_options)
+            .await
+            .map_err(|e| format!("failed to save links from \"{file_path}\": {e}"))?;
+
+        let guard = export_state.lock().unwrap();
+        let windows = guard
+            .new_file
+            .session
+            .as_ref()
+            .map(|session| tab_walk::selected_windows(session, &generate_options))
+            .unwrap_or_default();
+        Ok(SaveSummary {
+            window_count: windows.len() as u64,
+            tab_count: windows.iter().map(|window| window.tabs.len() as u64).sum(),
+            timings: guard.new_file.timings.clone(),
+        })
+    }
+
+    async fn export_all_profiles(
+        &self,
+        state: Self::State<'_>,
+        generate_options: GenerateOptions,
+        output_options: OutputOptions,
+        target_folder: String,
+    ) -> Result<Vec<ProfileExport>, String> {
+        if cfg!(target_family = "wasm") {
+            return Err("can't save files to a folder inside a web page".to_string());
+        }
+
+        let profiles = self.find_firefox_profiles().await?;
+        let target_folder = PathBuf::from(target_folder);
+        let file_ext = generate::file_extension(output_options.format);
+        // Don't open a window for every saved file:
+        let output_options = OutputOptions {
+            reveal_after_save: false,
+            ..output_options
+        };
+
+        let mut used_stems = std::collections::HashSet::new();
+        let mut exports = Vec::with_capacity(profiles.len());
+        for profile in profiles {
+            let session_files = profile.session_files.clone();
+            let session_file = spawn_blocking(move || newest_session_file(&session_files)).await;
+            let result = match &session_file {
+                Some(file_path) => {
+                    let stem = profile_file_stem(&profile.name, &mut used_stems);
+                    let save_path = target_folder.join(format!("{stem}.{file_ext}"));
+                    self.load_and_export(
+                        state,
+                        file_path.clone(),
+                        generate_options.clone(),
+                        output_options.clone(),
+                        save_path.to_string_lossy().into_owned(),
+                    )
+                    .await
+                }
+                None => Err("no session file was found in the profile".to_string()),
+            };
+            let (summary, error) = match result {
+                Ok(summary) => (Some(summary), None),
+                Err(e) => (None, Some(e)),
+            };
+            exports.push(ProfileExport {
+                profile_name: profile.name,
+                session_file,
+                summary,
+                error,
+            });
+        }
+        Ok(exports)
+    }
+
+    async fn compare_tree_sources(
+        &self,
+        state: Self::State<'_>,
+        id: DataId,
+        is_closed: bool,
+        group_index: u32,
+    ) -> Result<Vec<(TreeSource, String)>, String> {
+        let session = state
+            .lock()
+            .unwrap()
+            .get_file_for_data_id(id)
+            .ok_or("file id has expired")?
+            .session
+            .clone()
+            .ok_or("must deserialize JSON sessionstore data before converting tabs to links")?;
+
+        spawn_blocking(move || {
+            let generate_options = GenerateOptions {
+                open_group_indexes: Some(if is_closed { vec![] } else { vec![group_index] }),
+                closed_group_indexes: Some(if is_closed { vec![group_index] } else { vec![] }),
+                table_of_content: false,
+                ..Default::default()
+            };
+            TreeSource::all()
+                .iter()
+                .map(|&source| {
+                    let links = generate::preview_text(
+                        &session,
+                        &generate_options,
+                        tab_walk::tree_data_sources(source),
+                    )?;
+                    Ok((source, links))
+                })
+                .collect()
+        })
+        .await
+    }
+
+    async fn get_tabs_for_group(
+        &self,
+        state: Self::State<'_>,
+        id: DataId,
+        is_closed: bool,
+        group_index: u32,
+    ) -> Result<Vec<TabInfo>, String> {
+        let session = state
+            .lock()
+            .unwrap()
+            .get_file_for_data_id(id)
+            .ok_or("file id has expired")?
+            .session
+            .clone()
+            .ok_or("must deserialize JSON sessionstore data before tabs can be listed")?;
+
+        spawn_blocking(move || {
+            let generate_options = GenerateOptions {
+                open_group_indexes: Some(if is_closed { vec![] } else { vec![group_index] }),
+                closed_group_indexes: Some(if is_closed { vec![group_index] } else { vec![] }),
+                ..Default::default()
+            };
+            let window = tab_walk::selected_windows(&session, &generate_options)
+                .pop()
+                .ok_or_else(|| format!("there is no window with index {group_index}"))?;
+            Ok(window
+                .tabs
+                .into_iter()
+                .map(|tab| TabInfo {
+                    title: tab.title,
+                    host: tab_walk::web_host(&tab.url),
+                    url: tab.url,
+                    depth: tab.depth,
+                })
+                .collect())
+        })
+        .await
+    }
+
+    async fn oldest_tabs(
+        &self,
+        state: Self::State<'_>,
+        id: DataId,
+        limit: u32,
+    ) -> Result<Vec<TabEntry>, String> {
+        let session = state
+            .lock()
+            .unwrap()
+            .get_file_for_data_id(id)
+            .ok_or("file id has expired")?
+            .session
+            .clone()
+            .ok_or("must deserialize JSON sessionstore data before finding old tabs")?;
+
+        spawn_blocking(move || {
+            let mut tabs = tab_walk::selected_windows(&session, &GenerateOptions::default())
+                .into_iter()
+                .flat_map(|window| {
+                    window.tabs.into_iter().map(move |tab| TabEntry {
+                        window_index: window.index,
+                        is_closed: window.is_closed,
+                        window_name: window.name.clone(),
+                        title: tab.display_title().to_owned(),
+                        url: tab.url,
+                        last_accessed: tab.last_accessed,
+                    })
+                })
+                .collect::<Vec<_>>();
+            // Tabs without a timestamp are sorted last:
+            tabs.sort_by_key(|tab| (tab.last_accessed.is_none(), tab.last_accessed));
+            tabs.truncate(limit as usize);
+            Ok(tabs)
+        })
+        .await
+    }
+
+    async fn count_tabs(
+        &self,
+        state: Self::State<'_>,
+        id: DataId,
+        generate_options: GenerateOptions,
+    ) -> Result<TabCounts, String> {
+        let session = state
+            .lock()
+            .unwrap()
+            .get_file_for_data_id(id)
+            .ok_or("file id has expired")?
+            .session
+            .clone()
+            .ok_or("must deserialize JSON sessionstore data before counting tabs")?;
+
+        spawn_blocking(move || {
+            let mut counts = TabCounts::default();
+            for window in tab_walk::selected_windows(&session, &generate_options) {
+                let tabs = window.tabs.len() as u32;
+                if window.is_closed {
+                    counts.closed_windows += 1;
+                    counts.closed_tabs += tabs;
+                } else {
+                    counts.open_windows += 1;
+                    counts.open_tabs += tabs;
+                }
+            }
+            Ok(counts)
+        })
+        .await
+    }
+
+    async fn get_session_stats(
+        &self,
+        state: Self::State<'_>,
+        id: DataId,
+    ) -> Result<SessionStats, String> {
+        use std::collections::HashSet;
+
+        let session = state
+            .lock()
+            .unwrap()
+            .get_file_for_data_id(id)
+            .ok_or("file id has expired")?
+            .session
+            .clone()
+            .ok_or("must deserialize JSON sessionstore data before collecting statistics")?;
+
+        spawn_blocking(move || {
+            let mut stats = SessionStats::default();
+            let mut domains = HashSet::new();
+            for (is_closed, _, group) in
+                tab_walk::selected_groups(&session, &GenerateOptions::default())
+            {
+                if is_closed {
+                    stats.closed_windows += 1;
+                } else {
+                    stats.open_windows += 1;
+                }
+                for tab in group.tabs() {
+                    stats.total_tabs += 1;
+                    if tab.pinned() {
+                        stats.pinned_tabs += 1;
+                    }
+                    if tab.url().starts_with("about:reader?") {
+                        stats.reader_mode_tabs += 1;
+                    }
+                    if let Some(host) = tab_walk::web_host(tab.url()) {
+                        domains.insert(host);
+                    }
+                }
+            }
+            stats.unique_domains = domains.len() as u32;
+            Ok(stats)
+        })
+        .await
+    }
+
+    async fn referenced_extensions(
+        &self,
+        state: Self::State<'_>,
+        id: DataId,
+    ) -> Result<Vec<String>, String> {
+        let (session, file_path) = {
+            let mut guard = state.lock().unwrap();
+            let file = guard.get_file_for_data_id(id).ok_or("file id has expired")?;
+            let session = file
+                .session
+                .clone()
+                .ok_or("must deserialize JSON sessionstore data before finding extensions")?;
+            (session, file.file_path.clone())
+        };
+
+        spawn_blocking(move || {
+            let mut uuids = Vec::<String>::new();
+            for window in tab_walk::selected_windows(&session, &GenerateOptions::default()) {
+                for tab in &window.tabs {
+                    if let Some(uuid) = extensions::extension_uuid(&tab.url) {
+                        if !uuids.iter().any(|known| known == uuid) {
+                            uuids.push(uuid.to_owned());
+                        }
+                    }
+                }
+            }
+            // Can't read the profile folder inside a web page:
+            let file_path = file_path.filter(|_| cfg!(not(target_family = "wasm")));
+            Ok(extensions::describe(uuids, file_path.as_deref()))
+        })
+        .await
+    }
+
+    async fn newer_session_file_warning(
+        &self,
+        state: Self::State<'_>,
+        id: DataId,
+    ) -> Result<Option<String>, String> {
+        let file_path = state
+            .lock()
+            .unwrap()
+            .get_file_for_data_id(id)
+            .ok_or("file id has expired")?
+            .file_path
+            .clone();
+        // Can't inspect other files inside a web page:
+        let Some(file_path) = file_path.filter(|_| cfg!(not(target_family = "wasm"))) else {
+            return Ok(None);
+        };
+
+        spawn_blocking(move || {
+            let modified_at = |path: &std::path::Path| path.metadata().and_then(|m| m.modified()).ok();
+            let Some(loaded_at) = modified_at(&file_path) else {
+                return Ok(None);
+            };
+            let Some(profile) = profile_dir(&file_path) else {
+                return Ok(None);
+            };
+            let newest = SESSION_FILE_CANDIDATES
+                .into_iter()
+                .map(|suffix| profile.join(suffix))
+                .filter(|path| *path != file_path)
+                .filter_map(|path| Some((modified_at(&path)?, path)))
+                .filter(|(time, _)| *time > loaded_at)
+                .max_by_key(|(time, _)| *time);
+
+            Ok(newest.map(|(_, path)| {
+                format!(
+                    "\"{}\" in the same profile is newer than the loaded file, the exported tabs might be outdated",
+                    path.strip_prefix(profile).unwrap_or(&path).display()
+                )
+            }))
+        })
+        .await
+    }
+
+    async fn set_record_timings(&self, state: Self::State<'_>, enabled: bool) {
+        state.lock().unwrap().record_timings = enabled;
+    }
+
+    async fn get_max_decompressed_size(&self, state: Self::State<'_>) -> u64 {
+        state.lock().unwrap().max_decompressed_bytes
+    }
+    async fn set_max_decompressed_size(
+        &self,
+        state: Self::State<'_>,
+        max_bytes: u64,
+    ) -> Result<(), String> {
+        if max_bytes == 0 {
+            return Err("the decompression size limit must be larger than zero".to_string());
+        }
+        let settings = {
+            let mut guard = state.lock().unwrap();
+            guard.max_decompressed_bytes = max_bytes;
+            SETTINGS_EDITS.fetch_add(1, Ordering::SeqCst);
+            guard.settings()
+        };
+        spawn_blocking(move || write_settings(&settings)).await
+    }
+
+    async fn get_timings(&self, state: Self::State<'_>, id: DataId) -> Option<StepTimings> {
+        state
+            .lock()
+            .unwrap()
+            .get_file_for_data_id(id)
+            .map(|file| file.timings.clone())
+    }
+
+    async fn generate_output(
+        &self,
+        state: Self::State<'_>,
+        id: DataId,
+        generate_options: GenerateOptions,
+        format: OutputFormat,
+    ) -> Result<Vec<u8>, String> {
+        let session = state
+            .lock()
+            .unwrap()
+            .get_file_for_data_id(id)
+            .ok_or("file id has expired")?
+            .session
+            .clone()
+            .ok_or("must deserialize JSON sessionstore data before converting tabs to links")?;
+
+        spawn_blocking(move || {
+            let mut output = Vec::new();
+            generate::write_links(
+                &session,
+                &generate_options,
+                format,
+                None,
+                generate::PdfPageOptions::default(),
+                &generate::StopFlags::default(),
+                &mut output,
+            )?;
+            Ok(output)
+        })
+        .await
+    }
+
+    async fn render_pdf_preview(
+        &self,
+        state: Self::State<'_>,
+        id: DataId,
+        generate_options: GenerateOptions,
+        output_options: OutputOptions,
+    ) -> Result<Vec<u8>, String> {
+        if output_options.format != OutputFormat::PDF_TYPST {
+            return Err("previews are only supported for PDFs created with Typst".to_string());
+        }
+        let session = state
+            .lock()
+            .unwrap()
+            .get_file_for_data_id(id)
+            .ok_or("file id has expired")?
+            .session
+            .clone()
+            .ok_or("must deserialize JSON sessionstore data before converting tabs to links")?;
+
+        spawn_blocking(move || {
+            // Render the Typst source of the PDF, with the same page options:
+            let mut source = Vec::new();
+            generate::write_links(
+                &session,
+                &generate_options,
+                OutputFormat::TYPST,
+                None,
+                generate::PdfPageOptions::new(&output_options)?,
+                &generate::StopFlags::default(),
+                &mut source,
+            )?;
+            const_cfg!(if cfg!(target_family = "wasm") {
+                let _ = source;
+                Err("can't render PDF previews inside a web page".to_string())
+            } else {
+                typst::render_first_page(&String::from_utf8_lossy(&source))
+            })
+        })
+        .await
+    }
+
+    async fn generate_data_url(
+        &self,
+        state: Self::State<'_>,
+        id: DataId,
+        generate_options: GenerateOptions,
+        output_options: OutputOptions,
+    ) -> Result<String, String> {
+        use base64::Engine;
+
+        /// Firefox refuses to open longer `data:` URLs.
+        const MAX_DATA_URL_LEN: usize = 32 * 1024 * 1024;
+
+        let session = state
+            .lock()
+            .unwrap()
+            .get_file_for_data_id(id)
+            .ok_or("file id has expired")?
+            .session
+            .clone()
+            .ok_or("must deserialize JSON sessionstore data before converting tabs to links")?;
+
+        spawn_blocking(move || {
+            let mut output = Vec::new();
+            generate::write_encoded_links(
+                &session,
+                &generate_options,
+                output_options.format,
+                None,
+                generate::PdfPageOptions::new(&output_options)?,
+                output_options.text_encoding,
+                &generate::StopFlags::default(),
+                &mut output,
+            )?;
+
+            let prefix = format!(
+                "data:{};base64,",
+                generate::mime_type(output_options.format, output_options.text_encoding)
+            );
+            let url_len = prefix.len() + base64::encoded_len(output.len(), true).unwrap_or(usize::MAX);
+            if url_len > MAX_DATA_URL_LEN {
+                return Err(format!(
+                    "the generated document is too large to open as a data: URL ({} MiB, limit is {} MiB), save it to a file instead",
+                    url_len / (1024 * 1024),
+                    MAX_DATA_URL_LEN / (1024 * 1024)
+                ));
+            }
+            Ok(prefix + &base64::engine::general_purpose::STANDARD.encode(&output))
+        })
+        .await
+    }
+
+    async fn typst_font_warning(
+        &self,
+        state: Self::State<'_>,
+        id: DataId,
+        generate_options: GenerateOptions,
+    ) -> Result<Option<String>, String> {
+        let Some(font) = generate_options.typst_font.clone() else {
+            return Ok(None);
+        };
+        let session = state
+            .lock()
+            .unwrap()
+            .get_file_for_data_id(id)
+            .ok_or("file id has expired")?
+            .session
+            .clone()
+            .ok_or("must deserialize JSON sessionstore data before checking fonts")?;
+
+        Ok(spawn_blocking(move || {
+            let mut text = String::new();
+            for window in tab_walk::selected_windows(&session, &generate_options) {
+                text.push_str(&window.name);
+                for tab in &window.tabs {
+                    text.push_str(tab.display_title());
+                }
+            }
+            let missing = typst::missing_chars(&font, &text);
+            if missing.is_empty() {
+                None
+            } else {
+                const MAX_SHOWN: usize = 20;
+                let shown = missing.iter().take(MAX_SHOWN).collect::<String>();
+                Some(format!(
+                    "the font \"{font}\" can't display {} characters used by tabs: {shown}{}",
+                    missing.len(),
+                    if missing.len() > MAX_SHOWN { "..." } else { "" }
+                ))
+            }
+        })
+        .await)
+    }
+
+    async fn export_combined_urls(
+        &self,
+        state: Self::State<'_>,
+        output_options: OutputOptions,
+    ) -> Result<CombinedUrlsSummary, String> {
+        use std::{collections::HashSet, io::Write};
+
+        let (mut save_path, sessions) = {
+            let guard = state.lock().unwrap();
+            let save_path = if cfg!(target_family = "wasm") {
+                Default::default()
+            } else {
+                guard.save_path.clone().ok_or("no save path selected")?
+            };
+            let sessions = [&guard.current_file, &guard.new_file]
+                .into_iter()
+                .filter_map(|file| file.session.clone())
+                .collect::<Vec<_>>();
+            (save_path, sessions)
+        };
+        if sessions.is_empty() {
+            return Err("no parsed sessionstore data is loaded".to_string());
+        }
+
+        let (summary, _data) = spawn_blocking(move || -> Result<_, String> {
+            // Include all windows, ignoring any selection:
+            let generate_options = GenerateOptions::default();
+
+            let mut seen = HashSet::new();
+            let mut urls = Vec::new();
+            let mut duplicates_removed = 0;
+            for session in &sessions {
+                for window in tab_walk::selected_windows(session, &generate_options) {
+                    for tab in window.tabs {
+                        if seen.insert(tab.url.clone()) {
+                            urls.push(tab.url);
+                        } else {
+                            duplicates_removed += 1;
+                        }
+                    }
+                }
+            }
+
+            let mut file = {
+                #[cfg(target_family = "wasm")]
+                {
+                    let _ = (&mut save_path, &output_options);
+                    Vec::new()
+                }
+                #[cfg(not(target_family = "wasm"))]
+                {
+                    create_output_file(&mut save_path, "txt", &output_options)?
+                }
+            };
+            for url in &urls {
+                writeln!(file, "{url}").map_err(|e| format!("failed to write URLs: {e}"))?;
+            }
+
+            let summary = CombinedUrlsSummary {
+                unique_urls: urls.len() as u64,
+                duplicates_removed,
+            };
+
+            #[cfg(target_family = "wasm")]
+            {
+                Ok((summary, (file, "txt")))
+            }
+            #[cfg(not(target_family = "wasm"))]
+            {
+                Ok((summary, ()))
+            }
+        })
+        .await?;
+
+        #[cfg(target_family = "wasm")]
+        {
+            let mut guard = state.lock().unwrap();
+            (guard.handle_saved_data)(_data.0, _data.1)?;
+        }
+
+        Ok(summary)
+    }
+
+    async fn export_new_since_last(
+        &self,
+        state: Self::State<'_>,
+        id: DataId,
+        generate_options: GenerateOptions,
+        output_options: OutputOptions,
+    ) -> Result<DeltaExportSummary, String> {
+        use std::{collections::HashSet, io::Write};
+
+        let (mut save_path, session, content_hash) = {
+            let mut guard = state.lock().unwrap();
+            let save_path = if cfg!(target_family = "wasm") {
+                Default::default()
+            } else {
+                guard.save_path.clone().ok_or("no save path selected")?
+            };
+            let file_info = guard.get_file_for_data_id(id).ok_or("data id expired")?;
+            let session = file_info
+                .session
+                .clone()
+                .ok_or("no parsed sessionstore data")?;
+            let content_hash = file_info
+                .content_hash
+                .ok_or("the file's content wasn't hashed when it was loaded")?;
+            (save_path, session, content_hash)
+        };
+
+        let (summary, _data) = spawn_blocking(move || -> Result<_, String> {
+            let previous = export_history::previous_urls(content_hash)?;
+            let first_export = previous.is_none();
+            let previous = previous.unwrap_or_default();
+
+            let mut exported = HashSet::new();
+            let mut total_tabs = 0;
+            let mut new_tabs = 0;
+            let mut text = String::new();
+            for window in tab_walk::selected_windows(&session, &generate_options) {
+                let mut window_text = String::new();
+                for tab in &window.tabs {
+                    total_tabs += 1;
+                    exported.insert(tab.url.clone());
+                    if previous.contains(&tab.url) {
+                        continue;
+                    }
+                    new_tabs += 1;
+                    window_text.push_str(&format!("{}\n{}\n", tab.display_title(), tab.url));
+                }
+                if !window_text.is_empty() {
+                    if !text.is_empty() {
+                        text.push('\n');
+                    }
+                    text.push_str(&format!("{}\n\n{window_text}", window.name));
+                }
+            }
+
+            let mut file = {
+                #[cfg(target_family = "wasm")]
+                {
+                    let _ = (&mut save_path, &output_options);
+                    Vec::new()
+                }
+                #[cfg(not(target_family = "wasm"))]
+                {
+                    create_output_file(&mut save_path, "txt", &output_options)?
+                }
+            };
+            file.write_all(text.as_bytes())
+                .map_err(|e| format!("failed to write new tabs: {e}"))?;
+
+            // Only remember the URLs once they have been saved:
+            export_history::store_urls(content_hash, &exported)?;
+
+            let summary = DeltaExportSummary {
+                new_tabs,
+                total_tabs,
+                first_export,
+            };
+
+            #[cfg(target_family = "wasm")]
+            {
+                Ok((summary, (file, "txt")))
+            }
+            #[cfg(not(target_family = "wasm"))]
+            {
+                Ok((summary, ()))
+            }
+        })
+        .await?;
+
+        #[cfg(target_family = "wasm")]
+        {
+            let mut guard = state.lock().unwrap();
+            (guard.handle_saved_data)(_data.0, _data.1)?;
+        }
+
+        Ok(summary)
+    }
+}
+
+/// Convert a sessionstore file to a document with links without showing any
+/// UI, for use in scripts. Goes through the same steps as the GUI (load,
+/// decompress, parse and save) so the output is identical to saving with the
+/// same options, except that the files aren't added to the recent files.
+///
+/// `output` is used as the save path, so a file extension is added if it
+/// doesn't have one. Must be called inside a Tokio runtime.
+#[cfg(not(target_family = "wasm"))]
+pub async fn convert_file(
+    input: &std::path::Path,
+    output: &std::path::Path,
+    generate_options: GenerateOptions,
+    output_options: OutputOptions,
+) -> Result<(), String> {
+    use crate::FileManagementCommands;
+
+    let path_id = PathId::new();
+    let state = Mutex::new(UiState {
+        current_file: FileState {
+            path_id,
+            file_path: Some(input.to_owned()),
+            ..Default::default()
+        },
+        save_path: Some(output.to_owned()),
+        max_decompressed_bytes: saved_max_decompressed_bytes(read_settings().as_ref()),
+        ..UiState::default()
+    });
+
+    let data = spawn_blocking({
+        let input = input.to_owned();
+        move || {
+            std::fs::read(&input)
+                .map_err(|e| format!("failed to read file data from {}: {e}", input.display()))
+        }
+    })
+    .await?;
+    // Unlike load_data this doesn't remember the file as recently opened:
+    let id = HostCommands.set_data(&state, path_id, data).await?;
+
+    let info = HostCommands
+        .get_info_for_data_id(&state, id)
+        .await
+        .ok_or("file id has expired")?;
+    if matches!(info.status, FileStatus::Compressed) {
+        HostCommands.decompress_data(&state, id).await?;
+    }
+    HostCommands.parse_session_data(&state, id).await?;
+    HostCommands
+        .save_links(&state, id, generate_options, output_options)
+        .await?;
+    Ok(())
+}
+
+#[cfg(all(test, not(target_family = "wasm")))]
+mod tests {
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        cell::Cell,
+    };
+
+    use super::*;
+
+    thread_local! {
+        /// Bytes allocated by the current thread minus the bytes it freed.
+        static ALLOCATED: Cell<isize> = const { Cell::new(0) };
+        /// Highest value of `ALLOCATED` since it was last reset.
+        static PEAK_ALLOCATED: Cell<isize> = const { Cell::new(0) };
+    }
+
+    /// Counts the bytes allocated by each thread so that tests can check how
+    /// much memory is used at most.
+    struct CountingAllocator;
+    impl CountingAllocator {
+        fn track(change: isize) {
+            let allocated = ALLOCATED.get() + change;
+            ALLOCATED.set(allocated);
+            PEAK_ALLOCATED.set(PEAK_ALLOCATED.get().max(allocated));
+        }
+
+        /// The most memory that the current thread had allocated at once while
+        /// running `f`, relative to when it started.
+        fn peak_while<R>(f: impl FnOnce() -> R) -> (R, usize) {
+            let start = ALLOCATED.get();
+            PEAK_ALLOCATED.set(start);
+            let result = f();
+            (result, (PEAK_ALLOCATED.get() - start) as usize)
+        }
+    }
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc(layout);
+            if !ptr.is_null() {
+                Self::track(layout.size() as isize);
+            }
+            ptr
+        }
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout);
+            Self::track(-(layout.size() as isize));
+        }
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let new_ptr = System.realloc(ptr, layout, new_size);
+            if !new_ptr.is_null() {
+                Self::track(new_size as isize - layout.size() as isize);
+            }
+            new_ptr
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    fn block_on<F: std::future::Future>(fut: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(fut)
+    }
+
+    #[test]
+    fn decompresses_large_moz_lz4_data() {
+        let tab = serde_json::json!({
+            "entries": [{ "url": "https://example.com/", "title": "Example" }],
+        });
+        let session = serde_json::json!({ "windows": [{ "tabs": vec![tab; 400_000] }] });
+        let mut compressed = Vec::new();
+        session_bundle::write_jsonlz4(&session, &mut compressed).unwrap();
+        assert!(compressed.len() < 1 << 20);
+
+        let (decompressed, peak) = CountingAllocator::peak_while(|| {
+            decompress(&compressed, Compression::MozLz4, u64::MAX).unwrap()
+        });
+        assert!(decompressed.len() > 16 << 20);
+        // Only the output is allocated, there is no copy of the compressed
+        // data and the output isn't grown:
+        assert!(
+            peak <= decompressed.len() + (64 << 10),
+            "allocated {peak} bytes for {} bytes of output",
+            decompressed.len()
+        );
+        assert_eq!(serde_json::from_slice::<serde_json::Value>(&decompressed).unwrap(), session);
+    }
+
+    #[test]
+    fn failed_decompression_keeps_data() {
+        let mut data = MOZ_LZ4_MAGIC.to_vec();
+        // A decompressed size of 100 bytes followed by a truncated block:
+        data.extend_from_slice(&[100, 0, 0, 0, 0xff, 0xff, 0xff]);
+
+        let state = Mutex::new(UiState::default());
+        let info = block_on(async {
+            let path_id = HostCommands
+                .set_open_path(&state, FileSlot::New, "sessionstore.jsonlz4".to_string())
+                .await;
+            let id = HostCommands.set_data(&state, path_id, data.clone()).await.unwrap();
+            HostCommands.decompress_data(&state, id).await.unwrap_err();
+            HostCommands.get_info_for_data_id(&state, id).await.unwrap()
+        });
+        assert!(matches!(info.status, FileStatus::Compressed));
+        let guard = state.lock().unwrap();
+        assert_eq!(guard.new_file.data.as_deref(), Some(&*data));
+        assert!(!guard.new_file.decompressing);
     }
 }
@@ -0,0 +1,346 @@
+//! Recently closed tabs of open windows. Firefox stores these per window in
+//! `_closedTabs` which isn't part of the tab groups that the session library
+//! provides, so they are read into a separate session where the tabs of each
+//! window are the tabs that were recently closed in an open window.
+//!
+//! [`ParsedSession`] also keeps the containers of tabs from the same JSON
+//! data, see [`super::containers`], and copies of windows with invalid trees,
+//! see [`super::invalid_trees`].
+
+use std::{collections::HashMap, fmt, ops::Deref, path::Path};
+
+use firefox_session_data::session_store::{
+    session_info::{get_groups_from_session, TabGroup as SessionTabGroup},
+    FirefoxSessionStore,
+};
+use serde::{
+    de::{MapAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use serde_json::{Map, Value};
+
+use super::{
+    containers::{TabContainer, TabContainers, CONTAINER_MARKER},
+    invalid_trees::FlatWindows,
+};
+
+/// Data that has recently closed tabs contains this, the list is written as
+/// `[]` if a window has no closed tabs.
+const CLOSED_TABS_MARKER: &[u8] = br#""_closedTabs":[{"#;
+
+/// A parsed sessionstore file together with the recently closed tabs of its
+/// open windows and the containers of its tabs. Derefs to the parsed session.
+#[derive(Debug)]
+pub struct ParsedSession {
+    session: FirefoxSessionStore,
+    closed_tabs: Option<ClosedTabs>,
+    containers: Option<TabContainers>,
+    /// Number of windows with an invalid tree of tabs.
+    invalid_trees: usize,
+    flat_windows: Option<FlatWindows>,
+}
+
+#[derive(Debug)]
+struct ClosedTabs {
+    /// Each window's tabs are the recently closed tabs of an open window.
+    session: FirefoxSessionStore,
+    /// Index of the open window that the window at the same index in
+    /// `session` belongs to.
+    window_indexes: Vec<usize>,
+}
+
+/// The parts of sessionstore JSON data that the session library doesn't
+/// provide. The tabs of windows are skipped when deserialized except for
+/// their containers and the recently closed tabs of open windows.
+#[derive(Debug, Default)]
+struct SessionExtras {
+    /// Top level fields other than the windows, such as `"session"`, which
+    /// are small and needed to parse a session.
+    fields: Map<String, Value>,
+    windows: Vec<WindowExtras>,
+    closed_windows: Vec<ClosedWindowExtras>,
+}
+impl<'de> Deserialize<'de> for SessionExtras {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ExtrasVisitor;
+        impl<'de> Visitor<'de> for ExtrasVisitor {
+            type Value = SessionExtras;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("sessionstore data")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut extras = SessionExtras::default();
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "windows" => extras.windows = map.next_value()?,
+                        "_closedWindows" => extras.closed_windows = map.next_value()?,
+                        _ => {
+                            let value = map.next_value()?;
+                            extras.fields.insert(key, value);
+                        }
+                    }
+                }
+                Ok(extras)
+            }
+        }
+        deserializer.deserialize_map(ExtrasVisitor)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct WindowExtras {
+    #[serde(default)]
+    tabs: Vec<TabContainer>,
+    #[serde(default, rename = "_closedTabs")]
+    closed_tabs: Vec<ClosedTab>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ClosedWindowExtras {
+    #[serde(default)]
+    tabs: Vec<TabContainer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClosedTab {
+    /// The tab as it was stored before it was closed.
+    state: Option<Value>,
+}
+
+impl ParsedSession {
+    /// Parse sessionstore JSON `data` together with its recently closed tabs
+    /// and the containers of its tabs.
+    pub fn parse(data: &[u8]) -> serde_json::Result<Self> {
+        let mut session = Self::new(serde_json::from_slice::<FirefoxSessionStore>(data)?);
+        // Only read the data again if there are closed tabs or container
+        // tabs:
+        let contains = |marker: &[u8]| data.windows(marker.len()).any(|w| w == marker);
+        if contains(CLOSED_TABS_MARKER) || contains(CONTAINER_MARKER) {
+            if let Ok(extras) = serde_json::from_slice::<SessionExtras>(data) {
+                session.set_extras(extras);
+            }
+        }
+        // Only parsed again if the data is corrupt:
+        session.copy_invalid_trees(|| serde_json::from_slice::<Value>(data).ok());
+        Ok(session)
+    }
+
+    fn new(session: FirefoxSessionStore) -> Self {
+        Self {
+            session,
+            closed_tabs: None,
+            containers: None,
+            invalid_trees: 0,
+            flat_windows: None,
+        }
+    }
+
+    /// Like [`ParsedSession::parse`] but for data that has already been
+    /// parsed as JSON. Call [`ParsedSession::copy_invalid_trees`] afterwards.
+    pub fn from_value(value: &Value) -> serde_json::Result<Self> {
+        let mut session = Self::new(FirefoxSessionStore::deserialize(value)?);
+        if let Ok(extras) = SessionExtras::deserialize(value) {
+            session.set_extras(extras);
+        }
+        Ok(session)
+    }
+
+    /// Check if sessionstore JSON `value` can be parsed as a session.
+    pub fn is_readable(value: &Value) -> bool {
+        FirefoxSessionStore::deserialize(value).is_ok()
+    }
+
+    fn set_extras(&mut self, extras: SessionExtras) {
+        let SessionExtras {
+            fields,
+            windows,
+            closed_windows,
+        } = extras;
+        let mut open_tabs = Vec::with_capacity(windows.len());
+        let mut closed_tabs = Vec::with_capacity(windows.len());
+        for window in windows {
+            open_tabs.push(window.tabs);
+            closed_tabs.push(window.closed_tabs);
+        }
+        let closed_window_tabs = closed_windows
+            .into_iter()
+            .map(|window| window.tabs)
+            .collect::<Vec<_>>();
+        self.containers = TabContainers::new(&open_tabs, &closed_window_tabs);
+        self.closed_tabs = ClosedTabs::new(fields, closed_tabs);
+    }
+
+    /// Find windows with invalid trees and copy them without their tree data
+    /// from the JSON `value` that the session was parsed from.
+    pub fn copy_invalid_trees(&mut self, value: impl FnOnce() -> Option<Value>) {
+        let windows = FlatWindows::invalid_windows(&self.session);
+        self.invalid_trees = windows.len();
+        if windows.is_empty() {
+            return;
+        }
+        self.flat_windows = value().and_then(|value| FlatWindows::new(&value, windows));
+    }
+
+    /// Some windows have invalid trees, see [`ParsedSession::tree_warning`].
+    pub fn has_invalid_trees(&self) -> bool {
+        self.invalid_trees > 0
+    }
+
+    /// Describes windows with invalid trees, they are written as flat lists.
+    pub fn tree_warning(&self) -> Option<String> {
+        match self.invalid_trees {
+            0 => None,
+            1 => Some(
+                "the tree of tabs in 1 window is invalid (a tab is its own ancestor), \
+                it is written as a flat list"
+                    .to_owned(),
+            ),
+            count => Some(format!(
+                "the trees of tabs in {count} windows are invalid (a tab is its own ancestor), \
+                they are written as flat lists"
+            )),
+        }
+    }
+
+    /// A copy of a window without tree data if its tree is invalid, see
+    /// [`FlatWindows::window`].
+    pub fn flat_window_of(&self, is_closed: bool, index: usize) -> Option<SessionTabGroup<'_>> {
+        self.flat_windows.as_ref()?.window(is_closed, index)
+    }
+
+    /// Read the names of the containers that tabs are in from the profile of
+    /// the sessionstore file that the data was loaded from.
+    pub fn read_container_names(&mut self, session_file: &Path) {
+        if let Some(containers) = &mut self.containers {
+            containers.read_names(session_file);
+        }
+    }
+
+    /// Some tabs are in containers.
+    pub fn has_containers(&self) -> bool {
+        self.containers.is_some()
+    }
+
+    /// The container name of each tab in the open or closed window at
+    /// `window_index` in the sessionstore data, see
+    /// [`TabContainers::names_of`].
+    pub fn container_names_of(
+        &self,
+        is_closed: bool,
+        window_index: usize,
+    ) -> Option<Vec<Option<String>>> {
+        self.containers.as_ref()?.names_of(is_closed, window_index)
+    }
+
+    /// The recently closed tabs of each open window as a tab group, keyed by
+    /// the index of the window in the sessionstore data. Windows where no
+    /// tabs were closed aren't included.
+    pub fn closed_tabs_by_window(&self) -> HashMap<usize, SessionTabGroup<'_>> {
+        let Some(closed_tabs) = &self.closed_tabs else {
+            return HashMap::new();
+        };
+        closed_tabs
+            .window_indexes
+            .iter()
+            .copied()
+            .zip(get_groups_from_session(&closed_tabs.session, true, false, false))
+            .collect()
+    }
+}
+impl Deref for ParsedSession {
+    type Target = FirefoxSessionStore;
+
+    fn deref(&self) -> &Self::Target {
+        &self.session
+    }
+}
+
+impl ClosedTabs {
+    /// `fields` are the top level fields of the sessionstore data other than
+    /// its windows and `closed_tabs` are the recently closed tabs of each
+    /// open window. `None` if no tabs were closed.
+    fn new(mut fields: Map<String, Value>, closed_tabs: Vec<Vec<ClosedTab>>) -> Option<Self> {
+        let mut window_indexes = Vec::new();
+        let mut closed_windows = Vec::new();
+        for (ix, closed) in closed_tabs.into_iter().enumerate() {
+            let tabs = closed
+                .into_iter()
+                .filter_map(|closed| closed.state)
+                .map(|mut tab| {
+                    // Closed tabs are listed without a tree since their tree
+                    // data refers to other tabs:
+                    if let Some(tab) = tab.as_object_mut() {
+                        tab.remove("extData");
+                    }
+                    tab
+                })
+                .collect::<Vec<_>>();
+            if tabs.is_empty() {
+                continue;
+            }
+            let mut closed_window = Map::new();
+            closed_window.insert("tabs".to_owned(), Value::Array(tabs));
+            closed_window.insert("_closedTabs".to_owned(), Value::Array(Vec::new()));
+            closed_window.insert("selected".to_owned(), Value::from(1));
+            window_indexes.push(ix);
+            closed_windows.push(Value::Object(closed_window));
+        }
+        if closed_windows.is_empty() {
+            return None;
+        }
+        fields.insert("windows".to_owned(), Value::Array(closed_windows));
+        fields.insert("_closedWindows".to_owned(), Value::Array(Vec::new()));
+
+        let closed_session = serde_json::from_value::<FirefoxSessionStore>(Value::Object(fields))
+            .ok()?;
+
+        Some(Self {
+            session: closed_session,
+            window_indexes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        tab_walk,
+        test_session::{parse_session, tab, window},
+    };
+    use crate::GenerateOptions;
+
+    #[test]
+    fn closed_tabs_follow_their_window() {
+        let session = parse_session(
+            vec![
+                window(vec![tab("https://a.example/")], vec![tab("https://b.example/")]),
+                window(vec![tab("https://c.example/")], vec![]),
+                window(vec![tab("https://d.example/")], vec![tab("https://e.example/")]),
+            ],
+            vec![window(vec![tab("https://f.example/")], vec![])],
+        );
+        let generate_options = GenerateOptions {
+            include_closed_tabs: true,
+            ..Default::default()
+        };
+        let groups = tab_walk::selected_groups(&session, &generate_options)
+            .into_iter()
+            .map(|(is_closed, ix, group)| {
+                let urls = group.tabs().iter().map(|tab| tab.url().to_owned()).collect();
+                (is_closed, ix, urls)
+            })
+            .collect::<Vec<(bool, u32, Vec<String>)>>();
+        let expected = [
+            (false, 0, "https://a.example/"),
+            (false, 0, "https://b.example/"),
+            (false, 1, "https://c.example/"),
+            (false, 2, "https://d.example/"),
+            (false, 2, "https://e.example/"),
+            (true, 0, "https://f.example/"),
+        ]
+        .map(|(is_closed, ix, url)| (is_closed, ix, vec![url.to_owned()]));
+        assert_eq!(groups, expected);
+    }
+}
@@ -0,0 +1,100 @@
+//! Detect how sessionstore data is compressed. Firefox only uses its own LZ4
+//! based format, but archived sessions are sometimes compressed with gzip or
+//! zstd instead.
+
+use std::io::Read;
+
+use super::{MOZ_LZ4_MAGIC, NOT_SESSION_FILE};
+
+/// Magic bytes at the start of gzip files.
+const GZIP_MAGIC: &[u8] = b"\x1f\x8b";
+/// Magic bytes at the start of zstd frames.
+const ZSTD_MAGIC: &[u8] = b"\x28\xb5\x2f\xfd";
+
+/// A compression format that sessionstore data can be stored with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    MozLz4,
+    Gzip,
+    Zstd,
+}
+impl Compression {
+    /// Find the compression format from the magic bytes at the start of the
+    /// data.
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(MOZ_LZ4_MAGIC) {
+            Some(Self::MozLz4)
+        } else if data.starts_with(GZIP_MAGIC) {
+            Some(Self::Gzip)
+        } else if data.starts_with(ZSTD_MAGIC) {
+            Some(Self::Zstd)
+        } else {
+            None
+        }
+    }
+}
+
+/// Error for data that is larger than `max_bytes` once decompressed.
+fn too_large_error(max_bytes: u64) -> String {
+    const MIB: u64 = 1024 * 1024;
+    format!(
+        "the decompressed data is larger than the limit of {} MiB, increase the limit if the file really is a sessionstore file",
+        max_bytes.div_ceil(MIB)
+    )
+}
+
+/// Check the decompressed size that is stored in the header of Firefox's LZ4
+/// format before any memory is allocated for it.
+pub fn check_moz_lz4_size(data: &[u8], max_bytes: u64) -> Result<(), String> {
+    // The block starts with the decompressed size as a little-endian u32:
+    let Some(size) = data
+        .strip_prefix(MOZ_LZ4_MAGIC)
+        .and_then(|block| block.first_chunk::<4>())
+        .map(|size| u64::from(u32::from_le_bytes(*size)))
+    else {
+        // Fails with a better error when it is decompressed:
+        return Ok(());
+    };
+    if size > max_bytes {
+        return Err(too_large_error(max_bytes));
+    }
+    Ok(())
+}
+
+/// Decompress data in Firefox's LZ4 format directly from a borrowed buffer.
+/// The output is allocated once with the size stored in the header, so check
+/// that size with [`check_moz_lz4_size`] first.
+pub fn decompress_moz_lz4(data: &[u8]) -> Result<Vec<u8>, String> {
+    let block = data
+        .strip_prefix(MOZ_LZ4_MAGIC)
+        .ok_or_else(|| format!("{NOT_SESSION_FILE} (missing mozLz4 header)"))?;
+    // The block starts with the decompressed size as a little-endian u32:
+    lz4_flex::block::decompress_size_prepended(block)
+        .map_err(|e| format!("failed to decompress data: {e}"))
+}
+
+/// Read all decompressed data, but stop with an error once more than
+/// `max_bytes` have been read.
+fn read_limited(decoder: impl Read, max_bytes: u64, format: &str) -> Result<Vec<u8>, String> {
+    let mut output = Vec::new();
+    decoder
+        .take(max_bytes.saturating_add(1))
+        .read_to_end(&mut output)
+        .map_err(|e| format!("failed to decompress {format} data: {e}"))?;
+    if output.len() as u64 > max_bytes {
+        return Err(too_large_error(max_bytes));
+    }
+    Ok(output)
+}
+
+/// Decompress gzip data, including files with several concatenated members.
+pub fn decompress_gzip(data: &[u8], max_bytes: u64) -> Result<Vec<u8>, String> {
+    read_limited(flate2::read::MultiGzDecoder::new(data), max_bytes, "gzip")
+}
+
+/// Decompress a zstd frame.
+pub fn decompress_zstd(mut data: &[u8], max_bytes: u64) -> Result<Vec<u8>, String> {
+    let decoder = ruzstd::decoding::StreamingDecoder::new(&mut data)
+        .map_err(|e| format!("failed to decompress zstd data: {e}"))?;
+    read_limited(decoder, max_bytes, "zstd")
+}
@@ -0,0 +1,124 @@
+//! Store data as JSON files in the user's config directory so that it is
+//! remembered when the program is restarted. Inside a web page the data is
+//! only kept in memory until the page is reloaded.
+
+use serde_json::Value;
+
+#[cfg(not(target_family = "wasm"))]
+mod storage {
+    use std::{path::PathBuf, sync::Mutex};
+
+    use serde_json::Value;
+
+    /// Held while a file is written so that writes don't use the same
+    /// temporary file at the same time.
+    static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+    /// The directory where settings for this program should be stored.
+    fn config_dir() -> Option<PathBuf> {
+        let base = if cfg!(windows) {
+            std::env::var_os("APPDATA").map(PathBuf::from)
+        } else if cfg!(target_os = "macos") {
+            std::env::var_os("HOME")
+                .map(|home| PathBuf::from(home).join("Library/Application Support"))
+        } else {
+            std::env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        };
+        base.map(|dir| dir.join("firefox-session-ui"))
+    }
+
+    fn config_path(file_name: &str) -> Result<PathBuf, String> {
+        config_dir()
+            .map(|dir| dir.join(file_name))
+            .ok_or_else(|| "failed to find the config directory".to_string())
+    }
+
+    pub fn read(file_name: &str) -> Result<Option<Value>, String> {
+        let path = config_path(file_name)?;
+        let data = match std::fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(format!(
+                    "failed to read config file at \"{}\": {e}",
+                    path.display()
+                ))
+            }
+        };
+        serde_json::from_slice(&data)
+            .map(Some)
+            .map_err(|e| format!("failed to parse config file at \"{}\": {e}", path.display()))
+    }
+
+    pub fn write(file_name: &str, value: &Value) -> Result<(), String> {
+        let path = config_path(file_name)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| {
+                format!("failed to create config directory at \"{}\": {e}", dir.display())
+            })?;
+        }
+        let data = serde_json::to_vec_pretty(value)
+            .map_err(|e| format!("failed to serialize config file: {e}"))?;
+
+        // Replace the file in one step so that it is never left half written:
+        let _guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let temp_path = path.with_file_name(format!("{file_name}.tmp"));
+        std::fs::write(&temp_path, data).map_err(|e| {
+            format!("failed to write config file at \"{}\": {e}", temp_path.display())
+        })?;
+        std::fs::rename(&temp_path, &path).map_err(|e| {
+            let _ = std::fs::remove_file(&temp_path);
+            format!("failed to replace config file at \"{}\": {e}", path.display())
+        })
+    }
+}
+
+#[cfg(target_family = "wasm")]
+mod storage {
+    use std::{collections::HashMap, sync::Mutex};
+
+    use serde_json::Value;
+
+    static FILES: Mutex<Option<HashMap<String, Value>>> = Mutex::new(None);
+
+    pub fn read(file_name: &str) -> Result<Option<Value>, String> {
+        Ok(FILES
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|files| files.get(file_name).cloned()))
+    }
+
+    pub fn write(file_name: &str, value: &Value) -> Result<(), String> {
+        FILES
+            .lock()
+            .unwrap()
+            .get_or_insert_with(HashMap::new)
+            .insert(file_name.to_owned(), value.clone());
+        Ok(())
+    }
+}
+
+/// Read a config file. Returns `None` if it hasn't been written yet.
+pub fn read_json(file_name: &str) -> Result<Option<Value>, String> {
+    storage::read(file_name)
+}
+
+/// Write a config file, replacing any previous data.
+pub fn write_json(file_name: &str, value: &Value) -> Result<(), String> {
+    storage::write(file_name, value)
+}
+
+/// Read a list of strings from a field of a JSON object, ignoring values that
+/// aren't strings.
+pub fn string_list(value: &Value, key: &str) -> Vec<String> {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|item| item.as_str().map(str::to_owned))
+        .collect()
+}
@@ -0,0 +1,199 @@
+//! Firefox's multi-account containers (contextual identities). Tabs that were
+//! opened in a container store its `userContextId` in the sessionstore data
+//! which the session library doesn't provide, and the names of the containers
+//! are stored in `containers.json` in the profile folder.
+
+use std::{collections::HashMap, path::Path};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Data that has container tabs contains this, tabs that aren't in a
+/// container don't store a `userContextId`.
+pub const CONTAINER_MARKER: &[u8] = br#""userContextId":"#;
+
+/// Names of the built-in containers, which are stored as localization IDs
+/// unless the user renamed them.
+const BUILT_IN_NAMES: &[(&str, &str)] = &[
+    ("userContextPersonal.label", "Personal"),
+    ("userContextWork.label", "Work"),
+    ("userContextBanking.label", "Banking"),
+    ("userContextShopping.label", "Shopping"),
+];
+
+/// The URL of the current history entry of a tab in sessionstore JSON data.
+pub fn tab_url(tab: &Value) -> Option<&str> {
+    let entries = tab.get("entries")?.as_array()?;
+    // The index is 1-based:
+    let index = tab
+        .get("index")
+        .and_then(Value::as_u64)
+        .map(|ix| ix.saturating_sub(1) as usize)
+        .unwrap_or(entries.len().saturating_sub(1));
+    entries.get(index)?.get("url")?.as_str()
+}
+
+/// The part of a tab in sessionstore JSON data that stores its container,
+/// other fields are skipped when deserialized.
+#[derive(Debug, Deserialize)]
+pub struct TabContainer {
+    /// `0` or missing for tabs that aren't in a container.
+    #[serde(default, rename = "userContextId")]
+    id: Option<u64>,
+}
+
+/// The containers of the tabs in all windows that have container tabs.
+#[derive(Debug)]
+pub struct TabContainers {
+    /// The container of each tab in a window, keyed by whether the window is
+    /// closed and its index in the sessionstore data.
+    windows: HashMap<(bool, usize), Vec<Option<u64>>>,
+    /// Names from `containers.json`, empty if they couldn't be read.
+    names: HashMap<u64, String>,
+}
+impl TabContainers {
+    /// The containers of the tabs in each open and closed window, in the
+    /// order the windows are stored. `None` if no tab is in a container.
+    pub fn new(open: &[Vec<TabContainer>], closed: &[Vec<TabContainer>]) -> Option<Self> {
+        let windows = [(false, open), (true, closed)]
+            .into_iter()
+            .flat_map(|(is_closed, windows)| {
+                windows.iter().enumerate().filter_map(move |(ix, tabs)| {
+                    let ids = tabs
+                        .iter()
+                        .map(|tab| tab.id.filter(|&id| id != 0))
+                        .collect::<Vec<_>>();
+                    ids.iter().any(Option::is_some).then_some(((is_closed, ix), ids))
+                })
+            })
+            .collect::<HashMap<_, _>>();
+        if windows.is_empty() {
+            return None;
+        }
+        Some(Self {
+            windows,
+            names: HashMap::new(),
+        })
+    }
+
+    /// Read the names of the containers from the profile that the
+    /// sessionstore file at `session_file` belongs to. The file is either in
+    /// the profile folder or in its `sessionstore-backups` folder.
+    pub fn read_names(&mut self, session_file: &Path) {
+        let Some(path) = session_file
+            .ancestors()
+            .skip(1)
+            .take(2)
+            .map(|folder| folder.join("containers.json"))
+            .find(|path| path.is_file())
+        else {
+            return;
+        };
+        let identities = std::fs::read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|data| serde_json::from_slice::<Value>(&data).map_err(|e| e.to_string()))
+            .inspect_err(|_e| {
+                #[cfg(debug_assertions)]
+                {
+                    let path = path.display();
+                    eprintln!("Warning: failed to read container names from {path}: {_e}");
+                }
+            })
+            .ok();
+        let identities = identities
+            .as_ref()
+            .and_then(|value| value.get("identities")?.as_array());
+        for identity in identities.into_iter().flatten() {
+            let Some(id) = identity.get("userContextId").and_then(Value::as_u64) else {
+                continue;
+            };
+            let name = identity
+                .get("name")
+                .and_then(Value::as_str)
+                .filter(|name| !name.is_empty())
+                .or_else(|| {
+                    let l10n_id = identity
+                        .get("l10nID")
+                        .or_else(|| identity.get("l10nId"))?
+                        .as_str()?;
+                    BUILT_IN_NAMES
+                        .iter()
+                        .find(|(key, _)| *key == l10n_id)
+                        .map(|(_, name)| *name)
+                });
+            if let Some(name) = name {
+                self.names.insert(id, name.to_owned());
+            }
+        }
+    }
+
+    /// The container name of each tab in the open or closed window at
+    /// `window_index` in the sessionstore data, `None` for tabs that aren't
+    /// in a container. `None` if no tab in the window is in a container.
+    pub fn names_of(&self, is_closed: bool, window_index: usize) -> Option<Vec<Option<String>>> {
+        Some(
+            self.windows
+                .get(&(is_closed, window_index))?
+                .iter()
+                .map(|id| {
+                    let id = (*id)?;
+                    Some(
+                        self.names
+                            .get(&id)
+                            .cloned()
+                            .unwrap_or_else(|| format!("Container {id}")),
+                    )
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::super::{
+        closed_tabs::ParsedSession,
+        tab_walk,
+        test_session::{parse_session, tab, tab_with, window},
+    };
+    use crate::GenerateOptions;
+
+    /// Two windows with the same tabs where only the first tab of the second
+    /// window is in a container.
+    fn session() -> ParsedSession {
+        let container_window = |container: u64| {
+            let tabs = vec![
+                tab_with("https://a.example/", json!({ "userContextId": container })),
+                tab("https://b.example/"),
+            ];
+            window(tabs, vec![])
+        };
+        parse_session(vec![container_window(0), container_window(5)], vec![])
+    }
+
+    #[test]
+    fn containers_follow_window_index() {
+        let session = session();
+        let generate_options = GenerateOptions {
+            include_container: true,
+            ..Default::default()
+        };
+        let containers = tab_walk::selected_windows(&session, &generate_options)
+            .into_iter()
+            .map(|window| window.tabs.into_iter().map(|tab| tab.container).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            containers,
+            [vec![None, None], vec![Some("Container 5".to_owned()), None]]
+        );
+
+        // The label isn't part of the title that is searched:
+        let generate_options = GenerateOptions {
+            title_url_filter: Some("container".to_owned()),
+            ..generate_options
+        };
+        assert!(tab_walk::selected_windows(&session, &generate_options).is_empty());
+    }
+}
@@ -0,0 +1,378 @@
+//! Output formats that are generated by this crate instead of by
+//! [`firefox_session_data::tabs_to_links`].
+
+use std::{borrow::Cow, collections::HashMap, fmt::Write};
+
+use serde_json::{json, Value};
+
+use super::tab_walk::{TabLink, WindowLinks};
+
+/// Escape text so that it can be placed inside XML elements and attributes.
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Format a time in milliseconds since the Unix epoch as a W3C datetime.
+fn w3c_datetime(unix_ms: u64) -> Option<String> {
+    let time = chrono::DateTime::from_timestamp_millis(i64::try_from(unix_ms).ok()?)?;
+    Some(time.format("%Y-%m-%dT%H:%M:%S+00:00").to_string())
+}
+
+/// Escape text so that it can be placed inside a Markdown table cell.
+fn escape_markdown_cell(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '|' => escaped.push_str(r"\|"),
+            '\\' => escaped.push_str(r"\\"),
+            // A line break would end the table row:
+            '\r' | '\n' => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escape text so that it can be used as the text of a Markdown link.
+fn escape_markdown_link_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '[' | ']' | '\\' | '*' | '_' | '`' | '<' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\r' | '\n' => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escape a URL so that it can be used as the destination of a Markdown link.
+fn escape_markdown_link_url(url: &str) -> String {
+    url.replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+}
+
+/// Markdown with each window's links inside a collapsible `<details>` block,
+/// which is supported by GitHub and GitLab.
+pub fn markdown_details(windows: &[WindowLinks]) -> String {
+    let mut output = String::new();
+    for window in windows {
+        output.push_str("<details>\n");
+        let _ = writeln!(output, "<summary>{}</summary>", escape_xml(&window.name));
+        // Markdown is only rendered inside the block after an empty line:
+        output.push('\n');
+        for tab in &window.tabs {
+            let _ = writeln!(
+                output,
+                "{}- [{}]({})",
+                "  ".repeat(tab.depth as usize),
+                escape_markdown_link_text(tab.display_title()),
+                escape_markdown_link_url(&tab.url),
+            );
+        }
+        output.push_str("\n</details>\n\n");
+    }
+    output
+}
+
+/// Markdown with a heading for each window and a GitHub task list item for each
+/// tab, so that the tabs can be checked off when pasted into an issue.
+pub fn markdown_task_list(windows: &[WindowLinks]) -> String {
+    let mut output = String::new();
+    for window in windows {
+        let _ = writeln!(output, "## {}\n", escape_markdown_link_text(&window.name));
+        for tab in &window.tabs {
+            let _ = writeln!(
+                output,
+                "{}- [ ] [{}]({})",
+                "  ".repeat(tab.depth as usize),
+                escape_markdown_link_text(tab.display_title()),
+                escape_markdown_link_url(&tab.url),
+            );
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// An XML sitemap with one entry per web page, see
+/// <https://www.sitemaps.org/protocol.html>. Tabs with other URL schemes are
+/// skipped and duplicate URLs only use the most recent access time.
+pub fn sitemap(windows: &[WindowLinks], title: Option<&str>, export_id: Option<&str>) -> String {
+    let mut urls = Vec::new();
+    let mut last_accessed = HashMap::<&str, Option<u64>>::new();
+    for tab in windows.iter().flat_map(|window| &window.tabs) {
+        let url = tab.url.as_str();
+        if !(url.starts_with("http://") || url.starts_with("https://")) {
+            continue;
+        }
+        match last_accessed.get_mut(url) {
+            Some(time) => *time = (*time).max(tab.last_accessed),
+            None => {
+                urls.push(url);
+                last_accessed.insert(url, tab.last_accessed);
+            }
+        }
+    }
+
+    let mut output = String::new();
+    output.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    if let Some(title) = title {
+        let _ = writeln!(output, "<!-- {} -->", title.replace("--", "- -"));
+    }
+    if let Some(export_id) = export_id {
+        let _ = writeln!(output, "<!-- Export ID: {export_id} -->");
+    }
+    output.push_str("<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+    for url in urls {
+        output.push_str("  <url>\n");
+        let _ = writeln!(output, "    <loc>{}</loc>", escape_xml(url));
+        if let Some(time) = last_accessed[url].and_then(w3c_datetime) {
+            let _ = writeln!(output, "    <lastmod>{time}</lastmod>");
+        }
+        output.push_str("  </url>\n");
+    }
+    output.push_str("</urlset>\n");
+    output
+}
+
+/// Nest tabs inside the closest earlier tab that has a smaller depth, the same
+/// way they are shown in a tree of tabs.
+fn json_tab_tree(tabs: &[TabLink]) -> Vec<Value> {
+    fn attach(roots: &mut Vec<Value>, ancestors: &mut [(u32, Value)], node: Value) {
+        match ancestors.last_mut() {
+            Some((_, parent)) => parent["children"].as_array_mut().unwrap().push(node),
+            None => roots.push(node),
+        }
+    }
+
+    let mut roots = Vec::new();
+    // Tabs whose children are still being collected, the deepest last:
+    let mut ancestors = Vec::<(u32, Value)>::new();
+    for tab in tabs {
+        while ancestors.last().is_some_and(|(depth, _)| *depth >= tab.depth) {
+            let (_, node) = ancestors.pop().unwrap();
+            attach(&mut roots, &mut ancestors, node);
+        }
+        ancestors.push((
+            tab.depth,
+            json!({
+                "title": tab.display_title(),
+                "url": tab.url,
+                "last_accessed": tab.last_accessed,
+                "children": [],
+            }),
+        ));
+    }
+    while let Some((_, node)) = ancestors.pop() {
+        attach(&mut roots, &mut ancestors, node);
+    }
+    roots
+}
+
+/// A JSON document with every selected window and its tree of tabs, intended
+/// to be processed by scripts.
+pub fn json(windows: &[WindowLinks], title: Option<&str>, export_id: Option<&str>) -> String {
+    let windows = windows
+        .iter()
+        .map(|window| {
+            json!({
+                "index": window.index,
+                "closed": window.is_closed,
+                "name": window.name,
+                "tabs": json_tab_tree(&window.tabs),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut document = json!({ "windows": windows });
+    if let Some(title) = title {
+        document["title"] = title.into();
+    }
+    if let Some(export_id) = export_id {
+        document["export_id"] = export_id.into();
+    }
+    let mut output = serde_json::to_string_pretty(&document).unwrap_or_default();
+    output.push('\n');
+    output
+}
+
+/// An OPML 2.0 outline with one top-level outline per window and the tabs of
+/// each window nested the same way as in its tree of tabs, see
+/// <http://opml.org/spec2.opml>.
+pub fn opml(windows: &[WindowLinks], title: Option<&str>, export_id: Option<&str>) -> String {
+    let mut output = String::new();
+    output.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    if let Some(export_id) = export_id {
+        let _ = writeln!(output, "<!-- Export ID: {export_id} -->");
+    }
+    output.push_str("<opml version=\"2.0\">\n");
+    output.push_str("  <head>\n");
+    let _ = writeln!(
+        output,
+        "    <title>{}</title>",
+        escape_xml(title.unwrap_or("Firefox tabs"))
+    );
+    output.push_str("  </head>\n");
+    output.push_str("  <body>\n");
+    for window in windows {
+        let _ = writeln!(output, "    <outline text=\"{}\">", escape_xml(&window.name));
+        // Depths of the tabs whose outlines are still open, the deepest last:
+        let mut open = Vec::<u32>::new();
+        for (ix, tab) in window.tabs.iter().enumerate() {
+            while open.last().is_some_and(|&depth| depth >= tab.depth) {
+                open.pop();
+                let _ = writeln!(output, "{}</outline>", "  ".repeat(open.len() + 3));
+            }
+            let indent = "  ".repeat(open.len() + 3);
+            let _ = write!(
+                output,
+                "{indent}<outline text=\"{}\" type=\"link\" url=\"{}\"",
+                escape_xml(tab.display_title()),
+                escape_xml(&tab.url),
+            );
+            let has_children = window
+                .tabs
+                .get(ix + 1)
+                .is_some_and(|next| next.depth > tab.depth);
+            if has_children {
+                output.push_str(">\n");
+                open.push(tab.depth);
+            } else {
+                output.push_str("/>\n");
+            }
+        }
+        while open.pop().is_some() {
+            let _ = writeln!(output, "{}</outline>", "  ".repeat(open.len() + 3));
+        }
+        output.push_str("    </outline>\n");
+    }
+    output.push_str("  </body>\n");
+    output.push_str("</opml>\n");
+    output
+}
+
+/// A HTML document where each window is a collapsible `<details>` block.
+/// Tabs that have children in a tree of tabs can be collapsed as well. All
+/// blocks start expanded.
+pub fn html_details(windows: &[WindowLinks], title: Option<&str>, export_id: Option<&str>) -> String {
+    let mut output = String::new();
+    if let Some(export_id) = export_id {
+        let _ = writeln!(output, "<!-- Export ID: {export_id} -->");
+    }
+    output.push_str("<!DOCTYPE html>\n<html>\n<head>\n");
+    let _ = writeln!(
+        output,
+        "<title>{}</title>",
+        escape_xml(title.unwrap_or("Firefox tabs"))
+    );
+    output.push_str("<style>summary { cursor: pointer; } ul { margin: 0; }</style>\n");
+    output.push_str("</head>\n<body>\n");
+    if let Some(title) = title {
+        let _ = writeln!(output, "<h1>{}</h1>", escape_xml(title));
+    }
+    for window in windows {
+        output.push_str("<details open>\n");
+        let _ = writeln!(output, "<summary>{}</summary>", escape_xml(&window.name));
+        output.push_str("<ul>\n");
+        // Depths of the tabs whose lists are still open, the deepest last:
+        let mut open = Vec::<u32>::new();
+        for (ix, tab) in window.tabs.iter().enumerate() {
+            while open.last().is_some_and(|&depth| depth >= tab.depth) {
+                open.pop();
+                output.push_str("</ul>\n</details>\n</li>\n");
+            }
+            let link = format!(
+                "<a href=\"{}\">{}</a>",
+                escape_xml(&tab.url),
+                escape_xml(tab.display_title())
+            );
+            let has_children = window
+                .tabs
+                .get(ix + 1)
+                .is_some_and(|next| next.depth > tab.depth);
+            if has_children {
+                let _ = writeln!(output, "<li>\n<details open>\n<summary>{link}</summary>\n<ul>");
+                open.push(tab.depth);
+            } else {
+                let _ = writeln!(output, "<li>{link}</li>");
+            }
+        }
+        while open.pop().is_some() {
+            output.push_str("</ul>\n</details>\n</li>\n");
+        }
+        output.push_str("</ul>\n</details>\n");
+    }
+    output.push_str("</body>\n</html>\n");
+    output
+}
+
+/// Quote a CSV field if needed, as described by RFC 4180.
+fn escape_csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\r', '\n']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// A CSV file with one row per tab that can be opened in spreadsheet
+/// programs. Starts with a UTF-8 byte order mark since Excel otherwise assumes
+/// a legacy encoding.
+pub fn csv(windows: &[WindowLinks]) -> String {
+    let mut output = String::from("\u{FEFF}");
+    output.push_str("Window,Window name,State,Depth,Title,URL\r\n");
+    for window in windows {
+        let name = escape_csv_field(&window.name);
+        let state = if window.is_closed { "closed" } else { "open" };
+        for tab in &window.tabs {
+            let _ = write!(
+                output,
+                "{},{},{},{},{},{}\r\n",
+                window.index + 1,
+                name,
+                state,
+                tab.depth,
+                escape_csv_field(tab.display_title()),
+                escape_csv_field(&tab.url),
+            );
+        }
+    }
+    output
+}
+
+/// A Markdown table with one row per tab, suitable for pasting into issue
+/// trackers.
+pub fn markdown_table(windows: &[WindowLinks]) -> String {
+    let mut output = String::new();
+    output.push_str("| Window | Title | URL |\n");
+    output.push_str("| --- | --- | --- |\n");
+    for window in windows {
+        let name = escape_markdown_cell(&window.name);
+        for tab in &window.tabs {
+            let _ = writeln!(
+                output,
+                "| {} | {} | {} |",
+                name,
+                escape_markdown_cell(tab.display_title()),
+                escape_markdown_cell(&tab.url),
+            );
+        }
+    }
+    output
+}
@@ -0,0 +1,88 @@
+//! Convert generated UTF-8 text into other character encodings.
+
+use encoding_rs::{EncoderResult, WINDOWS_1252};
+
+use crate::TextEncoding;
+
+/// UTF 8 Byte Order Mark.
+const UTF_8_BOM: &[u8] = b"\xEF\xBB\xBF";
+/// UTF 16 little-endian Byte Order Mark.
+const UTF_16_LE_BOM: &[u8] = b"\xFF\xFE";
+
+/// Convert UTF-8 text to another encoding. Returns the encoded text and the
+/// number of characters that couldn't be represented and were replaced with
+/// `?`.
+pub fn transcode(utf8: &[u8], encoding: TextEncoding) -> (Vec<u8>, usize) {
+    let body = utf8.strip_prefix(UTF_8_BOM).unwrap_or(utf8);
+    let text = String::from_utf8_lossy(body);
+    match encoding {
+        TextEncoding::Utf8 => (utf8.to_vec(), 0),
+        TextEncoding::Utf8Bom => ([UTF_8_BOM, body].concat(), 0),
+        TextEncoding::Utf16Le => {
+            let mut output = Vec::with_capacity(UTF_16_LE_BOM.len() + text.len() * 2);
+            output.extend_from_slice(UTF_16_LE_BOM);
+            output.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+            (output, 0)
+        }
+        TextEncoding::Windows1252 => {
+            let mut encoder = WINDOWS_1252.new_encoder();
+            let mut output = Vec::with_capacity(text.len());
+            let mut input = &*text;
+            let mut replaced = 0;
+            loop {
+                output.reserve(
+                    encoder
+                        .max_buffer_length_from_utf8_without_replacement(input.len())
+                        .unwrap_or(input.len()),
+                );
+                let (result, read) =
+                    encoder.encode_from_utf8_to_vec_without_replacement(input, &mut output, true);
+                input = &input[read..];
+                match result {
+                    EncoderResult::InputEmpty => break,
+                    EncoderResult::OutputFull => {}
+                    EncoderResult::Unmappable(_) => {
+                        replaced += 1;
+                        output.push(b'?');
+                    }
+                }
+            }
+            (output, replaced)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utf8_bom_is_added_once() {
+        let (output, replaced) = transcode("Ä ✓".as_bytes(), TextEncoding::Utf8Bom);
+        assert_eq!(output, [UTF_8_BOM, "Ä ✓".as_bytes()].concat());
+        assert_eq!(replaced, 0);
+
+        let (again, _) = transcode(&output, TextEncoding::Utf8Bom);
+        assert_eq!(again, output);
+    }
+
+    #[test]
+    fn utf16_le_has_bom() {
+        let (output, replaced) = transcode("aÄ✓".as_bytes(), TextEncoding::Utf16Le);
+        assert_eq!(output, b"\xFF\xFEa\x00\xC4\x00\x13\x27");
+        assert_eq!(replaced, 0);
+
+        // A BOM in the input is not written twice:
+        let with_bom = [UTF_8_BOM, b"a"].concat();
+        let (output, _) = transcode(&with_bom, TextEncoding::Utf16Le);
+        assert_eq!(output, b"\xFF\xFEa\x00");
+    }
+
+    #[test]
+    fn windows_1252_replaces_unmappable_characters() {
+        let (output, replaced) =
+            transcode("Ä € ✓ 日本".as_bytes(), TextEncoding::Windows1252);
+        assert_eq!(output, b"\xC4 \x80 ? ??");
+        assert_eq!(replaced, 3);
+    }
+}
@@ -0,0 +1,122 @@
+//! Remember which URLs were exported from a sessionstore file so that later
+//! exports of the same file can include only new tabs.
+//!
+//! Files are identified by a hash of their content. The history is stored
+//! using [`config`].
+
+use std::collections::HashSet;
+
+use serde_json::{Map, Value};
+
+use super::config;
+
+/// Name of the config file that stores the history.
+const HISTORY_FILE: &str = "export-history.json";
+
+/// Key of the list with the history keys, from the least to the most recently
+/// exported file. Can't be confused with a [`history_key`].
+const ORDER_KEY: &str = "order";
+
+/// How many files are remembered. Firefox rewrites its recovery file every few
+/// seconds, so without a limit the history would keep growing.
+const MAX_FILES: usize = 20;
+
+/// Hash of a file's data that stays the same between program versions (unlike
+/// [`std::hash::DefaultHasher`]). Uses the 64-bit FNV-1a algorithm.
+pub fn content_hash(data: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    data.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+/// Key used to store the URLs of a file in the history.
+fn history_key(content_hash: u64) -> String {
+    format!("{content_hash:016x}")
+}
+
+/// The URLs that were exported the last time a file with the same content was
+/// exported. `None` if it was never exported.
+pub fn previous_urls(content_hash: u64) -> Result<Option<HashSet<String>>, String> {
+    let Some(history) = config::read_json(HISTORY_FILE)? else {
+        return Ok(None);
+    };
+    let key = history_key(content_hash);
+    Ok(history
+        .get(&key)
+        .map(|_| config::string_list(&history, &key).into_iter().collect()))
+}
+
+/// Remember the URLs that were exported from a file, replacing any URLs from
+/// earlier exports. Only the [`MAX_FILES`] most recently exported files are
+/// kept.
+pub fn store_urls(content_hash: u64, urls: &HashSet<String>) -> Result<(), String> {
+    let mut history = match config::read_json(HISTORY_FILE)? {
+        Some(Value::Object(history)) => history,
+        _ => Map::new(),
+    };
+    insert_urls(&mut history, history_key(content_hash), urls);
+    config::write_json(HISTORY_FILE, &Value::Object(history))
+}
+
+/// Store the URLs of a file as the most recent export and forget the oldest
+/// files if there are too many.
+fn insert_urls(history: &mut Map<String, Value>, key: String, urls: &HashSet<String>) {
+    let stored_order = history
+        .remove(ORDER_KEY)
+        .and_then(|order| serde_json::from_value::<Vec<String>>(order).ok())
+        .unwrap_or_default();
+    // Files that were stored without an order count as the oldest:
+    let mut order = history
+        .keys()
+        .filter(|existing| !stored_order.contains(existing))
+        .cloned()
+        .collect::<Vec<_>>();
+    order.extend(
+        stored_order
+            .into_iter()
+            .filter(|existing| history.contains_key(existing)),
+    );
+    order.retain(|existing| *existing != key);
+    order.push(key.clone());
+
+    let mut urls = urls.iter().cloned().collect::<Vec<_>>();
+    urls.sort_unstable();
+    history.insert(key, urls.into());
+
+    let removed = order.len().saturating_sub(MAX_FILES);
+    for old in order.drain(..removed) {
+        history.remove(&old);
+    }
+    history.insert(ORDER_KEY.to_owned(), order.into());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_keeps_most_recent_files() {
+        let mut history = Map::new();
+        // Stored by an older version without an order:
+        history.insert(history_key(0), Value::Array(Vec::new()));
+
+        let urls = HashSet::from(["https://example.com/".to_owned()]);
+        for hash in 1..=MAX_FILES as u64 {
+            insert_urls(&mut history, history_key(hash), &urls);
+        }
+        // Exporting a file again makes it the most recent:
+        insert_urls(&mut history, history_key(1), &urls);
+        insert_urls(&mut history, history_key(100), &urls);
+
+        assert_eq!(history.len(), MAX_FILES + 1);
+        assert!(!history.contains_key(&history_key(0)));
+        assert!(!history.contains_key(&history_key(2)));
+        assert!(history.contains_key(&history_key(1)));
+        assert_eq!(
+            config::string_list(&Value::Object(history), ORDER_KEY).last(),
+            Some(&history_key(100))
+        );
+    }
+}
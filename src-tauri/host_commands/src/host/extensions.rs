@@ -0,0 +1,80 @@
+//! Find out which browser extensions the `moz-extension://` tabs in a session
+//! belong to.
+//!
+//! Firefox gives each extension a random UUID that is used as the host of its
+//! pages. The sessionstore file doesn't know which extension a UUID belongs
+//! to, but the profile folder it was saved in does:
+//!
+//! - `prefs.js` maps extension ids to UUIDs with the
+//!   `extensions.webextensions.uuids` preference.
+//! - `extensions.json` has the names of installed extensions.
+
+use std::{collections::HashMap, path::Path};
+
+use serde_json::Value;
+
+use super::profile_dir;
+
+/// The UUID of the extension that a `moz-extension://` URL belongs to.
+pub fn extension_uuid(url: &str) -> Option<&str> {
+    let rest = url.strip_prefix("moz-extension://")?;
+    let uuid = rest.split(['/', '?', '#']).next()?;
+    Some(uuid).filter(|uuid| !uuid.is_empty())
+}
+
+/// Read the extension id for each extension UUID from a profile's `prefs.js`.
+fn uuid_to_extension_id(profile: &Path) -> Option<HashMap<String, String>> {
+    const PREF: &str = "user_pref(\"extensions.webextensions.uuids\",";
+
+    let prefs = std::fs::read_to_string(profile.join("prefs.js")).ok()?;
+    let line = prefs.lines().find(|line| line.starts_with(PREF))?;
+    // The value is a string literal that contains JSON:
+    let literal = line[PREF.len()..].trim().strip_suffix(");")?.trim();
+    let json = serde_json::from_str::<String>(literal).ok()?;
+    let ids = serde_json::from_str::<HashMap<String, String>>(&json).ok()?;
+    Some(ids.into_iter().map(|(id, uuid)| (uuid, id)).collect())
+}
+
+/// Read the name of each installed extension from a profile's
+/// `extensions.json`.
+fn extension_names(profile: &Path) -> HashMap<String, String> {
+    let Some(data) = std::fs::read(profile.join("extensions.json")).ok() else {
+        return HashMap::new();
+    };
+    let Ok(info) = serde_json::from_slice::<Value>(&data) else {
+        return HashMap::new();
+    };
+    info.get("addons")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|addon| {
+            let id = addon.get("id")?.as_str()?;
+            let name = addon.get("defaultLocale")?.get("name")?.as_str()?;
+            Some((id.to_owned(), name.to_owned()))
+        })
+        .collect()
+}
+
+/// Describe each extension UUID, for example `"uBlock Origin (<uuid>)"`. If
+/// the extension can't be found in the profile that the sessionstore file was
+/// loaded from then the UUID is returned unchanged.
+pub fn describe(uuids: Vec<String>, session_file: Option<&Path>) -> Vec<String> {
+    let profile = session_file.and_then(profile_dir);
+    let ids = profile.and_then(uuid_to_extension_id).unwrap_or_default();
+    if ids.is_empty() {
+        return uuids;
+    }
+    let names = profile.map(extension_names).unwrap_or_default();
+
+    uuids
+        .into_iter()
+        .map(|uuid| match ids.get(&uuid) {
+            Some(id) => {
+                let name = names.get(id).unwrap_or(id);
+                format!("{name} ({uuid})")
+            }
+            None => uuid,
+        })
+        .collect()
+}
@@ -0,0 +1,66 @@
+//! Register this program as a handler for Firefox's sessionstore files so that
+//! they can be opened by double-clicking them or from "Open with". The file is
+//! passed to the program as `--open <path>`.
+
+/// Register the running executable as the handler for sessionstore files for
+/// the current user.
+#[cfg(windows)]
+pub fn register() -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+    use std::process::Command;
+
+    /// Don't flash a console window for every `reg.exe` call.
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    const PROG_ID: &str = "FirefoxSessionUi.SessionFile";
+    const CLASSES: &str = r"HKCU\Software\Classes";
+    /// File extensions of sessionstore files and their backups.
+    const EXTENSIONS: &[&str] = &["jsonlz4", "baklz4"];
+
+    let exe = std::env::current_exe()
+        .map_err(|e| format!("failed to find the path of the program: {e}"))?;
+    let exe = exe.display();
+
+    // Keys below the current user's classes don't need admin rights:
+    let reg_add = |key: String, args: &[&str]| -> Result<(), String> {
+        let output = Command::new("reg")
+            .arg("add")
+            .arg(&key)
+            .args(args)
+            .arg("/f")
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+            .map_err(|e| format!("failed to run reg.exe: {e}"))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(format!(
+                "failed to write registry key {key}: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+        }
+    };
+
+    reg_add(format!(r"{CLASSES}\{PROG_ID}"), &["/ve", "/d", "Firefox session file"])?;
+    reg_add(
+        format!(r"{CLASSES}\{PROG_ID}\DefaultIcon"),
+        &["/ve", "/d", &format!("\"{exe}\",0")],
+    )?;
+    reg_add(
+        format!(r"{CLASSES}\{PROG_ID}\shell\open\command"),
+        &["/ve", "/d", &format!("\"{exe}\" --open \"%1\"")],
+    )?;
+    for extension in EXTENSIONS {
+        reg_add(format!(r"{CLASSES}\.{extension}"), &["/ve", "/d", PROG_ID])?;
+        reg_add(
+            format!(r"{CLASSES}\.{extension}\OpenWithProgids"),
+            &["/v", PROG_ID, "/t", "REG_NONE"],
+        )?;
+    }
+    Ok(())
+}
+
+/// Registering file types is only implemented for Windows.
+#[cfg(not(windows))]
+pub fn register() -> Result<(), String> {
+    Err("registering file types is not supported on this platform".to_owned())
+}
@@ -0,0 +1,766 @@
+//! Generate output documents from a parsed session.
+
+use std::{
+    borrow::Cow,
+    io::Write,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use firefox_session_data::{
+    pdf_converter::html_to_pdf::WriteBuilderSimple,
+    session_store::{
+        session_info::TreeDataSource,
+        to_links::{LinkFormat, ToLinksOptions},
+    },
+    to_links::TabsToLinksOutput,
+};
+
+use super::{
+    closed_tabs::ParsedSession,
+    custom_formats, encoding, html_window_filter, markdown_toc,
+    tab_walk::{self, WindowLinks},
+    typst,
+};
+use crate::{GenerateOptions, MarkdownStyle, OutputFormat, OutputOptions, PageSize, TextEncoding};
+
+/// UTF 8 Byte Order Mark. Written to the beginning of a text file to indicate
+/// the text encoding of the data.
+const UTF_8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Delay before rendering a PDF with a headless browser is retried the first
+/// time, doubled for every later retry.
+const PDF_RENDER_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Flags that are set when generation should stop early, for example since a
+/// save timed out or was cancelled. Generation never stops if there are no
+/// flags.
+#[derive(Debug, Clone, Default)]
+pub struct StopFlags(pub Vec<Arc<AtomicBool>>);
+impl StopFlags {
+    pub fn is_stopped(&self) -> bool {
+        self.0.iter().any(|flag| flag.load(Ordering::Acquire))
+    }
+
+    /// Checked between the steps of generating a document.
+    fn check(&self) -> Result<(), String> {
+        if self.is_stopped() {
+            Err("generation was stopped".to_string())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Wrap `inner` so that writes fail once generation should stop, which
+    /// ends generation that writes its output step by step.
+    pub fn writer<W>(&self, inner: W) -> StoppableWriter<W> {
+        StoppableWriter {
+            inner,
+            stop: self.clone(),
+        }
+    }
+}
+
+/// Forwards writes until one of the [`StopFlags`] is set, after that writes
+/// fail.
+pub struct StoppableWriter<W> {
+    inner: W,
+    stop: StopFlags,
+}
+impl<W: Write> Write for StoppableWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.stop.is_stopped() {
+            // Not `Interrupted` since `write_all` retries those errors forever:
+            return Err(std::io::Error::other("generation was stopped"));
+        }
+        self.inner.write(buf)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Page layout of PDF documents. The PDF backends take these settings in
+/// different ways so they are validated here and then converted by the backend
+/// that is used.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PdfPageOptions {
+    pub page_size: PageSize,
+    pub landscape: bool,
+    pub margins_mm: f32,
+    /// See [`OutputOptions::pdf_render_retries`].
+    pub render_retries: u32,
+}
+impl PdfPageOptions {
+    /// Larger margins leave almost no room for content on small pages.
+    pub const MAX_MARGINS_MM: f32 = 50.0;
+
+    /// Validate the page options that were selected by the user. Only Typst
+    /// supports page options so other formats get the default layout, the UI
+    /// hides the options for them.
+    pub fn new(output_options: &OutputOptions) -> Result<Self, String> {
+        if !output_options.format.is_typst() {
+            return Ok(Self {
+                render_retries: output_options.pdf_render_retries,
+                ..Self::default()
+            });
+        }
+        let margins_mm = output_options.margins_mm;
+        if !(0.0..=Self::MAX_MARGINS_MM).contains(&margins_mm) {
+            return Err(format!(
+                "PDF margins must be between 0 and {} mm, but was {margins_mm} mm",
+                Self::MAX_MARGINS_MM
+            ));
+        }
+        Ok(Self {
+            page_size: output_options.page_size,
+            landscape: output_options.landscape,
+            margins_mm,
+            render_retries: output_options.pdf_render_retries,
+        })
+    }
+
+    /// The page layout is the same as the default layout.
+    pub fn is_default(&self) -> bool {
+        let defaults = Self::default();
+        self.page_size == defaults.page_size
+            && self.landscape == defaults.landscape
+            && self.margins_mm == defaults.margins_mm
+    }
+}
+impl Default for PdfPageOptions {
+    fn default() -> Self {
+        let defaults = OutputOptions::default();
+        Self {
+            page_size: defaults.page_size,
+            landscape: defaults.landscape,
+            margins_mm: defaults.margins_mm,
+            render_retries: defaults.pdf_render_retries,
+        }
+    }
+}
+
+/// The file extension that should be used for files with the specified format.
+pub fn file_extension(format: OutputFormat) -> &'static str {
+    let Some(format_info) = format.format_info() else {
+        return match format {
+            OutputFormat::SITEMAP => "xml",
+            OutputFormat::JSON => "json",
+            OutputFormat::CSV => "csv",
+            OutputFormat::OPML => "opml",
+            _ => "txt",
+        };
+    };
+    let (format, as_pdf) = format_info.as_format().to_link_format();
+    if as_pdf.is_some() {
+        "pdf"
+    } else {
+        match format {
+            LinkFormat::TXT => "txt",
+            LinkFormat::RTF { .. } => "rtf",
+            LinkFormat::HTML => "html",
+            LinkFormat::Markdown => "md",
+            LinkFormat::Typst => "typ",
+        }
+    }
+}
+
+/// The MIME type of files with the specified format, including the charset for
+/// text formats.
+pub fn mime_type(format: OutputFormat, text_encoding: TextEncoding) -> String {
+    let mime = match file_extension(format) {
+        "pdf" => return "application/pdf".to_owned(),
+        "rtf" => return "application/rtf".to_owned(),
+        "html" => "text/html",
+        "md" => "text/markdown",
+        "xml" => "application/xml",
+        "json" => "application/json",
+        "csv" => "text/csv",
+        "opml" => "text/x-opml",
+        _ => "text/plain",
+    };
+    let charset = match text_encoding {
+        _ if !supports_text_encoding(format) => "utf-8",
+        TextEncoding::Utf8 | TextEncoding::Utf8Bom => "utf-8",
+        TextEncoding::Utf16Le => "utf-16le",
+        TextEncoding::Windows1252 => "windows-1252",
+    };
+    format!("{mime};charset={charset}")
+}
+
+/// A new unique ID that can be used to identify an export.
+pub fn new_export_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// A document title that summarizes how many windows and tabs were exported.
+fn counts_title(window_count: usize, tab_count: usize) -> String {
+    format!(
+        "Firefox Tabs \u{2014} {}, {}",
+        tab_walk::count_label(window_count, "window"),
+        tab_walk::count_label(tab_count, "tab")
+    )
+}
+
+/// The document title requested by the options, if any.
+fn document_title(
+    generate_options: &GenerateOptions,
+    window_count: usize,
+    tab_count: usize,
+) -> Option<String> {
+    generate_options
+        .include_counts
+        .then(|| counts_title(window_count, tab_count))
+}
+
+/// The windows for formats that are generated by [`custom_formats`] together
+/// with the document title requested by the options.
+fn output_windows_with_title(
+    session: &ParsedSession,
+    generate_options: &GenerateOptions,
+) -> (Vec<WindowLinks>, Option<String>) {
+    let windows = tab_walk::output_windows(session, generate_options);
+    let title = document_title(
+        generate_options,
+        windows.len(),
+        windows.iter().map(|window| window.tabs.len()).sum(),
+    );
+    (windows, title)
+}
+
+/// Text that should be placed at the start of a document with the specified
+/// format to show its title and identify the export. Empty if the format
+/// doesn't support it or if it is handled elsewhere (Typst documents use their
+/// preamble and HTML titles are placed inside the body).
+fn document_header(
+    format: LinkFormat,
+    as_pdf: bool,
+    title: Option<&str>,
+    export_id: Option<&str>,
+) -> String {
+    let mut header = String::new();
+    match format {
+        _ if as_pdf => {}
+        LinkFormat::TXT => {
+            if let Some(title) = title {
+                header.push_str(&format!("{title}\n\n"));
+            }
+            if let Some(export_id) = export_id {
+                header.push_str(&format!("Export ID: {export_id}\n\n"));
+            }
+        }
+        LinkFormat::Markdown => {
+            if let Some(title) = title {
+                header.push_str(&format!("# {title}\n\n"));
+            }
+            if let Some(export_id) = export_id {
+                header.push_str(&format!("Export ID: {export_id}\n\n"));
+            }
+        }
+        LinkFormat::HTML => {
+            if let Some(export_id) = export_id {
+                header.push_str(&format!("<!-- Export ID: {export_id} -->\n"));
+            }
+        }
+        LinkFormat::RTF { .. } | LinkFormat::Typst => {}
+    }
+    header
+}
+
+/// Insert a HTML fragment at the start of a HTML document's body.
+pub fn insert_into_html_body(html: &[u8], fragment: &str) -> Vec<u8> {
+    // Find the end of the opening body tag:
+    let insert_at = html
+        .windows(5)
+        .position(|w| w.eq_ignore_ascii_case(b"<body"))
+        .and_then(|start| {
+            html[start..]
+                .iter()
+                .position(|&b| b == b'>')
+                .map(|end| start + end + 1)
+        })
+        .unwrap_or(0);
+
+    let mut output = Vec::with_capacity(html.len() + fragment.len());
+    output.extend_from_slice(&html[..insert_at]);
+    output.extend_from_slice(fragment.as_bytes());
+    output.extend_from_slice(&html[insert_at..]);
+    output
+}
+
+/// Plain text links to the selected tabs, suitable for previews and the
+/// clipboard. Trees of tabs are read from the first of `tree_sources` that has
+/// data.
+pub fn preview_text(
+    session: &ParsedSession,
+    generate_options: &GenerateOptions,
+    tree_sources: &[TreeDataSource],
+) -> Result<String, String> {
+    if generate_options.markdown_style == MarkdownStyle::Table {
+        let (windows, title) = output_windows_with_title(session, generate_options);
+        let header = document_header(LinkFormat::Markdown, false, title.as_deref(), None);
+        return Ok(header + &custom_formats::markdown_table(&windows));
+    }
+
+    let mut output: Vec<u8> = Vec::new();
+
+    let groups = tab_walk::groups_for_links(session, generate_options);
+    let title = document_title(
+        generate_options,
+        groups.len(),
+        groups.iter().map(|group| group.tabs().len()).sum(),
+    );
+    // Windows with invalid trees were replaced by `groups_for_links` for the
+    // tree sources of the options, but other sources are used when comparing
+    // them for a single window:
+    let tree_sources = if groups
+        .iter()
+        .all(|group| tab_walk::has_valid_tree(group, tree_sources))
+    {
+        tree_sources
+    } else {
+        &[]
+    };
+
+    // Use the separator as the page break between windows:
+    let separator = generate_options
+        .window_separator
+        .as_deref()
+        .filter(|separator| !separator.trim().is_empty())
+        .map(|separator| format!("\n{}\n", separator.trim_end()))
+        .or_else(|| custom_page_break(generate_options));
+
+    firefox_session_data::tabs_to_links(
+        &groups,
+        TabsToLinksOutput {
+            format: LinkFormat::TXT,
+            as_pdf: None,
+            conversion_options: ToLinksOptions {
+                format: LinkFormat::TXT,
+                page_breaks_after_group: separator.is_some(),
+                skip_page_break_after_last_group: true,
+                table_of_contents: generate_options.table_of_content,
+                indent_all_links: !generate_options.flat_links,
+                custom_page_break: separator.unwrap_or_default().into(),
+                tree_sources: Cow::Owned(tree_sources.to_vec()),
+            },
+        },
+        WriteBuilderSimple(&mut output),
+    )
+    .map_err(|e| e.to_string())?;
+
+    let output = output.strip_prefix(UTF_8_BOM).unwrap_or(&output);
+    let header = document_header(LinkFormat::TXT, false, title.as_deref(), None);
+    Ok(header + &String::from_utf8_lossy(output))
+}
+
+/// Call `render` until it succeeds or has been retried `retries` times, waiting
+/// a little longer before each retry. Gives up when `stop` is set.
+fn retry_with_backoff<T>(
+    retries: u32,
+    stop: &StopFlags,
+    mut render: impl FnMut() -> Result<T, String>,
+) -> Result<T, String> {
+    let mut delay = PDF_RENDER_RETRY_DELAY;
+    let mut attempt = 0;
+    loop {
+        stop.check()?;
+        match render() {
+            Ok(value) => return Ok(value),
+            Err(_e) if attempt < retries => {
+                attempt += 1;
+                #[cfg(debug_assertions)]
+                {
+                    eprintln!(
+                        "Failed to render PDF, retrying in {} ms ({attempt}/{retries}): {_e}",
+                        delay.as_millis()
+                    );
+                }
+                // Wake up regularly to notice if generation should stop:
+                let waited = std::time::Instant::now();
+                while waited.elapsed() < delay && !stop.is_stopped() {
+                    std::thread::sleep(Duration::from_millis(50).min(delay));
+                }
+                delay *= 2;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// The [`GenerateOptions::custom_page_break`] on its own line, `None` if it
+/// is empty.
+fn custom_page_break(generate_options: &GenerateOptions) -> Option<String> {
+    let page_break = generate_options.custom_page_break.trim_end();
+    (!page_break.trim().is_empty()).then(|| format!("\n{page_break}\n"))
+}
+
+/// Formats that can be saved with a [`TextEncoding`] other than UTF-8.
+fn supports_text_encoding(format: OutputFormat) -> bool {
+    matches!(format, OutputFormat::TEXT | OutputFormat::MARKDOWN)
+}
+
+/// Like [`write_links`] but text formats are converted to `text_encoding`.
+/// Returns the number of characters that were replaced since they can't be
+/// represented in the encoding.
+pub fn write_encoded_links(
+    session: &ParsedSession,
+    generate_options: &GenerateOptions,
+    output_format: OutputFormat,
+    export_id: Option<&str>,
+    pdf_page: PdfPageOptions,
+    text_encoding: TextEncoding,
+    stop: &StopFlags,
+    mut output: impl Write,
+) -> Result<usize, String> {
+    if text_encoding == TextEncoding::Utf8 || !supports_text_encoding(output_format) {
+        write_links(
+            session,
+            generate_options,
+            output_format,
+            export_id,
+            pdf_page,
+            stop,
+            output,
+        )?;
+        return Ok(0);
+    }
+
+    let mut text = Vec::new();
+    write_links(
+        session,
+        generate_options,
+        output_format,
+        export_id,
+        pdf_page,
+        stop,
+        &mut text,
+    )?;
+    stop.check()?;
+    let (data, replaced) = encoding::transcode(&text, text_encoding);
+    output
+        .write_all(&data)
+        .map_err(|e| format!("failed to write document: {e}"))?;
+    Ok(replaced)
+}
+
+/// Write a document with a format that is implemented by this crate.
+fn write_custom_format(
+    session: &ParsedSession,
+    generate_options: &GenerateOptions,
+    output_format: OutputFormat,
+    export_id: Option<&str>,
+    mut output: impl Write,
+) -> Result<(), String> {
+    let (windows, title) = output_windows_with_title(session, generate_options);
+    let document = match output_format {
+        OutputFormat::SITEMAP => custom_formats::sitemap(&windows, title.as_deref(), export_id),
+        OutputFormat::JSON => custom_formats::json(&windows, title.as_deref(), export_id),
+        // No place for a title or export ID that wouldn't be read as a row:
+        OutputFormat::CSV => custom_formats::csv(&windows),
+        OutputFormat::OPML => custom_formats::opml(&windows, title.as_deref(), export_id),
+        format => return Err(format!("unsupported output format: {}", format.as_str())),
+    };
+    output
+        .write_all(document.as_bytes())
+        .map_err(|e| format!("failed to write document: {e}"))
+}
+
+/// Write a document with links to the selected tabs.
+///
+/// If an `export_id` is specified then it will be included in the document,
+/// currently this is supported by all formats except RTF, CSV and PDFs that
+/// are generated from HTML. The same is true for the title that is added by the
+/// `include_counts` option.
+///
+/// The `pdf_page` options are currently only supported for PDFs that are
+/// created with Typst.
+///
+/// Generation fails early once `stop` is set.
+pub fn write_links(
+    session: &ParsedSession,
+    generate_options: &GenerateOptions,
+    output_format: OutputFormat,
+    export_id: Option<&str>,
+    pdf_page: PdfPageOptions,
+    stop: &StopFlags,
+    mut output: impl Write,
+) -> Result<(), String> {
+    stop.check()?;
+    let Some(format_info) = output_format.format_info() else {
+        return write_custom_format(session, generate_options, output_format, export_id, output);
+    };
+    let (format, as_pdf) = format_info.as_format().to_link_format();
+
+    let style = generate_options.markdown_style;
+    let uses_style = as_pdf.is_none()
+        && match style {
+            MarkdownStyle::List => false,
+            MarkdownStyle::Table | MarkdownStyle::Details | MarkdownStyle::TaskList => {
+                matches!(format, LinkFormat::Markdown)
+            }
+            MarkdownStyle::HtmlDetails => format.is_html(),
+        };
+    if uses_style {
+        let (windows, title) = output_windows_with_title(session, generate_options);
+        let header = document_header(format, false, title.as_deref(), export_id);
+        let document = match style {
+            MarkdownStyle::List => unreachable!("lists are written by firefox_session_data"),
+            MarkdownStyle::Table => header + &custom_formats::markdown_table(&windows),
+            MarkdownStyle::Details => header + &custom_formats::markdown_details(&windows),
+            MarkdownStyle::TaskList => {
+                let mut body = custom_formats::markdown_task_list(&windows);
+                if generate_options.table_of_content {
+                    body = markdown_toc::add_toc(&body, generate_options.markdown_toc_links);
+                }
+                header + &body
+            }
+            // The title is placed inside the body:
+            MarkdownStyle::HtmlDetails => {
+                custom_formats::html_details(&windows, title.as_deref(), export_id)
+            }
+        };
+        return output
+            .write_all(document.as_bytes())
+            .map_err(|e| format!("failed to write document: {e}"));
+    }
+
+    let groups = tab_walk::groups_for_links(session, generate_options);
+    stop.check()?;
+    // Count after filtering so the title matches the document's content:
+    let title = document_title(
+        generate_options,
+        groups.len(),
+        groups.iter().map(|group| group.tabs().len()).sum(),
+    );
+    let header = document_header(format, as_pdf.is_some(), title.as_deref(), export_id);
+    let html_title = title
+        .as_deref()
+        .filter(|_| format.is_html() && as_pdf.is_none())
+        .map(|title| format!("<h1>{}</h1>\n", title.replace('&', "&amp;").replace('<', "&lt;")));
+    let html_window_filter =
+        generate_options.html_window_filter && format.is_html() && as_pdf.is_none();
+    let markdown_toc_links = generate_options.table_of_content
+        && generate_options.markdown_toc_links
+        && matches!(format, LinkFormat::Markdown);
+
+    let page_breaks = !matches!(format, LinkFormat::TXT);
+    let custom_page_break = custom_page_break(generate_options).filter(|_| {
+        as_pdf.is_none() && matches!(format, LinkFormat::TXT | LinkFormat::Markdown)
+    });
+    // A closure since rendering PDFs with a browser might need several tries:
+    let conversion_options = || ToLinksOptions {
+        format,
+        // No page break character for text files so fallback to
+        // several new lines:
+        page_breaks_after_group: page_breaks || custom_page_break.is_some(),
+        skip_page_break_after_last_group: custom_page_break.is_some()
+            || (page_breaks && (format.is_html() || format.is_typst())),
+        // The outline and the linked table of contents replace the inline
+        // table of contents:
+        table_of_contents: generate_options.table_of_content
+            && !(generate_options.pdf_outline && format.is_typst())
+            && !markdown_toc_links,
+        indent_all_links: !generate_options.flat_links,
+        custom_page_break: custom_page_break.clone().unwrap_or_default().into(),
+        // Windows with invalid trees were replaced with copies without tree
+        // data by `groups_for_links`:
+        tree_sources: Cow::Owned(tab_walk::tree_sources(generate_options)),
+    };
+
+    if as_pdf.is_some() && !format.is_typst() && !pdf_page.is_default() {
+        return Err(
+            "PDF page size, orientation and margins are only supported for PDFs created with Typst"
+                .to_string(),
+        );
+    }
+    let typst_preamble = typst::preamble(generate_options, pdf_page, title.as_deref(), export_id);
+    if format.is_typst() && !typst_preamble.is_empty() {
+        // Generate Typst source code so that we can customize it:
+        let mut source = Vec::new();
+        firefox_session_data::tabs_to_links(
+            &groups,
+            TabsToLinksOutput {
+                format,
+                as_pdf: None,
+                conversion_options: conversion_options(),
+            },
+            WriteBuilderSimple(stop.writer(&mut source)),
+        )
+        .map_err(|e| e.to_string())?;
+
+        let source = typst_preamble + &String::from_utf8_lossy(&source);
+        stop.check()?;
+        if as_pdf.is_some() {
+            typst::compile_pdf(&source, output)
+        } else {
+            output
+                .write_all(source.as_bytes())
+                .map_err(|e| format!("failed to write Typst document: {e}"))
+        }
+    } else if !header.is_empty()
+        || html_title.is_some()
+        || html_window_filter
+        || markdown_toc_links
+    {
+        let mut document = Vec::new();
+        firefox_session_data::tabs_to_links(
+            &groups,
+            TabsToLinksOutput {
+                format,
+                as_pdf,
+                conversion_options: conversion_options(),
+            },
+            WriteBuilderSimple(stop.writer(&mut document)),
+        )
+        .map_err(|e| e.to_string())?;
+        stop.check()?;
+        // Must run before the title is added so that it isn't treated as a
+        // window heading:
+        if html_window_filter {
+            document = html_window_filter::add_window_filter(&document);
+        }
+        if let Some(html_title) = &html_title {
+            document = insert_into_html_body(&document, html_title);
+        }
+        if markdown_toc_links {
+            let body = document.strip_prefix(UTF_8_BOM).unwrap_or(&document);
+            let bom = &document[..document.len() - body.len()];
+            document = [
+                bom,
+                markdown_toc::add_linked_toc(&String::from_utf8_lossy(body)).as_bytes(),
+            ]
+            .concat();
+        }
+
+        // The header must be placed after any byte order mark:
+        let body = document.strip_prefix(UTF_8_BOM).unwrap_or(&document);
+        let bom = &document[..document.len() - body.len()];
+        [bom, header.as_bytes(), body]
+            .into_iter()
+            .try_for_each(|data| output.write_all(data))
+            .map_err(|e| format!("failed to write document: {e}"))
+    } else if output_format == OutputFormat::PDF_CHROMIUM_OXIDE
+        && cfg!(not(target_family = "wasm"))
+    {
+        // Launching the headless browser sometimes fails on slow machines, so
+        // render into memory and only write the PDF once it succeeded:
+        let pdf = retry_with_backoff(pdf_page.render_retries, stop, || {
+            let mut pdf = Vec::new();
+            firefox_session_data::tabs_to_links(
+                &groups,
+                TabsToLinksOutput {
+                    format,
+                    as_pdf,
+                    conversion_options: conversion_options(),
+                },
+                WriteBuilderSimple(stop.writer(&mut pdf)),
+            )
+            .map_err(|e| e.to_string())?;
+            Ok(pdf)
+        })?;
+        stop.check()?;
+        output
+            .write_all(&pdf)
+            .map_err(|e| format!("failed to write PDF: {e}"))
+    } else {
+        firefox_session_data::tabs_to_links(
+            &groups,
+            TabsToLinksOutput {
+                format,
+                as_pdf,
+                conversion_options: conversion_options(),
+            },
+            WriteBuilderSimple(stop.writer(&mut output)),
+        )
+        .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_session::{parse_session, tab, window};
+    use super::*;
+    use crate::GroupBy;
+
+    /// Open windows with recently closed tabs and a closed window. The open
+    /// windows are stored in reverse order if `reversed` is set.
+    fn session(reversed: bool) -> ParsedSession {
+        let mut windows = vec![
+            window(
+                vec![tab("https://b.example/"), tab("https://a.example/")],
+                vec![tab("https://c.example/")],
+            ),
+            window(vec![tab("https://d.example/")], vec![tab("https://e.example/")]),
+            window(vec![tab("https://d.example/"), tab("https://b.example/")], vec![]),
+        ];
+        if reversed {
+            windows.reverse();
+        }
+        parse_session(windows, vec![window(vec![tab("https://f.example/")], vec![])])
+    }
+
+    #[test]
+    fn reproducible_output_is_identical() {
+        // Window names can depend on where the windows are stored, so the tabs
+        // are grouped by domain to leave the names out of the output:
+        let generate_options = GenerateOptions {
+            reproducible: true,
+            include_closed_tabs: true,
+            group_by: GroupBy::Domain,
+            ..Default::default()
+        };
+        let write = |session: &ParsedSession| {
+            let mut output = Vec::new();
+            write_links(
+                session,
+                &generate_options,
+                OutputFormat::TEXT,
+                None,
+                PdfPageOptions::default(),
+                &StopFlags::default(),
+                &mut output,
+            )
+            .unwrap();
+            output
+        };
+        let first = write(&session(false));
+        assert!(!first.is_empty());
+        assert_eq!(first, write(&session(true)));
+    }
+
+    #[test]
+    fn page_options_only_apply_to_typst() {
+        let output_options = OutputOptions {
+            format: OutputFormat::PDF_CHROMIUM_OXIDE,
+            landscape: true,
+            ..Default::default()
+        };
+        assert!(PdfPageOptions::new(&output_options).unwrap().is_default());
+        let output_options = OutputOptions {
+            format: OutputFormat::PDF_TYPST,
+            ..output_options
+        };
+        assert!(PdfPageOptions::new(&output_options).unwrap().landscape);
+    }
+
+    #[test]
+    fn stopped_generation_fails_without_output() {
+        let stop = StopFlags(vec![Arc::new(AtomicBool::new(true))]);
+        let mut output = Vec::new();
+        let result = write_links(
+            &session(false),
+            &GenerateOptions::default(),
+            OutputFormat::TEXT,
+            None,
+            PdfPageOptions::default(),
+            &stop,
+            &mut output,
+        );
+        assert!(result.is_err());
+        assert!(output.is_empty());
+    }
+}
@@ -0,0 +1,76 @@
+//! Let readers of a HTML document click a window's heading to only show the
+//! tabs from that window.
+//!
+//! Each heading gets a `data-window` attribute and a small inlined script
+//! hides the content of other windows. Without JavaScript the document is
+//! unchanged except for the extra attributes.
+
+/// Script that makes headings with a `data-window` attribute clickable. Every
+/// heading owns the elements after it up until the next window heading.
+const FILTER_SCRIPT: &str = r#"<script>
+(function () {
+  var headings = Array.prototype.slice.call(document.querySelectorAll("[data-window]"));
+  var selected = null;
+  function content(heading) {
+    var nodes = [];
+    for (var node = heading.nextElementSibling; node; node = node.nextElementSibling) {
+      if (node.hasAttribute("data-window") || node.querySelector("[data-window]")) break;
+      nodes.push(node);
+    }
+    return nodes;
+  }
+  function update() {
+    headings.forEach(function (heading) {
+      var hidden = selected !== null && heading.getAttribute("data-window") !== selected;
+      heading.hidden = hidden;
+      content(heading).forEach(function (node) { node.hidden = hidden; });
+    });
+  }
+  headings.forEach(function (heading) {
+    heading.style.cursor = "pointer";
+    heading.title = "Click to only show this window, click again to show all windows";
+    heading.addEventListener("click", function () {
+      var id = heading.getAttribute("data-window");
+      selected = selected === id ? null : id;
+      update();
+    });
+  });
+})();
+</script>
+"#;
+
+/// Position after the heading tag name if a heading's opening tag starts at
+/// `html[ix..]`.
+fn heading_tag_end(html: &[u8], ix: usize) -> Option<usize> {
+    match html.get(ix..ix + 4)? {
+        [b'<', b'h' | b'H', b'1'..=b'6', b' ' | b'>' | b'\t' | b'\n' | b'\r'] => Some(ix + 3),
+        _ => None,
+    }
+}
+
+/// Add `data-window` attributes to the window headings of a HTML document and
+/// inline the script that uses them.
+pub fn add_window_filter(html: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(html.len() + FILTER_SCRIPT.len());
+    let mut window = 0;
+    let mut copied = 0;
+    for ix in 0..html.len() {
+        if let Some(tag_end) = heading_tag_end(html, ix) {
+            output.extend_from_slice(&html[copied..tag_end]);
+            output.extend_from_slice(format!(" data-window=\"{window}\"").as_bytes());
+            copied = tag_end;
+            window += 1;
+        }
+    }
+    let rest = &html[copied..];
+
+    // Run the script after the headings have been parsed:
+    let script_at = rest
+        .windows(7)
+        .rposition(|w| w.eq_ignore_ascii_case(b"</body>"))
+        .unwrap_or(rest.len());
+    output.extend_from_slice(&rest[..script_at]);
+    output.extend_from_slice(FILTER_SCRIPT.as_bytes());
+    output.extend_from_slice(&rest[script_at..]);
+    output
+}
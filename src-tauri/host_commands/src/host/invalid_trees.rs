@@ -0,0 +1,175 @@
+//! Windows with an invalid tree of tabs. Corrupt data from Tree Style Tab or
+//! Sidebery can make a tab its own ancestor which would make the walk of the
+//! tree loop forever. The tree sources are shared by all windows when links
+//! are generated, so a copy of each such window is parsed without its tree
+//! data and is written as a flat list instead.
+
+use firefox_session_data::session_store::{
+    session_info::{get_groups_from_session, TabGroup as SessionTabGroup, TreeDataSource},
+    FirefoxSessionStore,
+};
+use serde_json::{Map, Value};
+
+use super::tab_walk::has_valid_tree;
+
+/// Every combination of tree sources that links can be generated with, see
+/// [`super::tab_walk::tree_sources`].
+const TREE_SOURCE_CHOICES: &[&[TreeDataSource]] = &[
+    &[TreeDataSource::Sidebery],
+    &[TreeDataSource::TstWebExtension, TreeDataSource::TstLegacy],
+    &[
+        TreeDataSource::Sidebery,
+        TreeDataSource::TstWebExtension,
+        TreeDataSource::TstLegacy,
+    ],
+];
+
+/// Copies of windows with invalid trees that don't have any tree data.
+#[derive(Debug)]
+pub struct FlatWindows {
+    /// Each window is a copy of an open or closed window.
+    session: FirefoxSessionStore,
+    /// If the window at the same index in `session` is a copy of a closed
+    /// window and its index among the open or closed windows.
+    windows: Vec<(bool, usize)>,
+}
+impl FlatWindows {
+    /// Windows in `session` that have an invalid tree for any choice of tree
+    /// sources. Returns if each window is closed and its index among the open
+    /// or closed windows.
+    pub fn invalid_windows(session: &FirefoxSessionStore) -> Vec<(bool, usize)> {
+        [false, true]
+            .into_iter()
+            .flat_map(|is_closed| {
+                // Windows are listed in the order they are stored:
+                get_groups_from_session(session, !is_closed, is_closed, false)
+                    .enumerate()
+                    .filter(|(_, group)| {
+                        TREE_SOURCE_CHOICES
+                            .iter()
+                            .any(|tree_sources| !has_valid_tree(group, tree_sources))
+                    })
+                    .map(move |(ix, _)| (is_closed, ix))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Copy `windows` from the JSON `value` that a session was parsed from
+    /// and remove their tree data.
+    pub fn new(value: &Value, windows: Vec<(bool, usize)>) -> Option<Self> {
+        let root = value.as_object()?;
+
+        // Keep the small top level fields so that the session can be parsed:
+        let mut flat_root = root
+            .iter()
+            .filter(|(key, _)| !matches!(key.as_str(), "windows" | "_closedWindows"))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect::<Map<_, _>>();
+
+        let flat_windows = windows
+            .iter()
+            .map(|&(is_closed, ix)| {
+                let key = if is_closed { "_closedWindows" } else { "windows" };
+                let mut window = root.get(key)?.as_array()?.get(ix)?.as_object()?.clone();
+                // Tree Style Tab and Sidebery store their data as extension
+                // data of the tabs and windows:
+                window.remove("extData");
+                if let Some(tabs) = window.get_mut("tabs").and_then(Value::as_array_mut) {
+                    for tab in tabs.iter_mut().filter_map(Value::as_object_mut) {
+                        tab.remove("extData");
+                    }
+                }
+                // Closed windows are stored as open windows in the copy:
+                window.insert("_closedTabs".to_owned(), Value::Array(Vec::new()));
+                window.entry("selected").or_insert(Value::from(1));
+                Some(Value::Object(window))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        flat_root.insert("windows".to_owned(), Value::Array(flat_windows));
+        flat_root.insert("_closedWindows".to_owned(), Value::Array(Vec::new()));
+
+        let session = serde_json::from_value::<FirefoxSessionStore>(Value::Object(flat_root)).ok()?;
+        Some(Self { session, windows })
+    }
+
+    /// The copy of a window that was returned by
+    /// [`FlatWindows::invalid_windows`].
+    pub fn window(&self, is_closed: bool, index: usize) -> Option<SessionTabGroup<'_>> {
+        let position = self
+            .windows
+            .iter()
+            .position(|&window| window == (is_closed, index))?;
+        get_groups_from_session(&self.session, true, false, false).nth(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::super::{
+        closed_tabs::ParsedSession,
+        tab_walk,
+        test_session::{session_data, tab, tab_with, window},
+    };
+    use super::*;
+    use crate::GenerateOptions;
+
+    /// A session where the first tab of the first window is its own parent,
+    /// written in the formats of Tree Style Tab and Sidebery.
+    fn cyclic_session() -> Vec<u8> {
+        let tst = "extension:treestyletab@piro.sakura.ne.jp";
+        let sidebery = "extension:{3c078156-979c-498b-8990-85f7987dd929}";
+        let cyclic = json!({
+            "treestyletab-id": "tab-a",
+            "treestyletab-parent": "tab-a",
+            "treestyletab-children": "tab-a",
+            format!("{tst}:data-persistent-id"): r#"{"id":"tab-a"}"#,
+            format!("{tst}:ancestors"): r#"["tab-a"]"#,
+            format!("{tst}:children"): r#"["tab-a"]"#,
+            format!("{sidebery}:data"): r#"{"id":1,"parentId":1,"panelId":"p","folded":false}"#,
+        });
+        session_data(
+            vec![
+                window(
+                    vec![
+                        tab_with("https://a.example/", json!({ "extData": cyclic })),
+                        tab("https://b.example/"),
+                    ],
+                    vec![],
+                ),
+                window(vec![tab("https://c.example/")], vec![]),
+            ],
+            vec![],
+        )
+    }
+
+    #[test]
+    fn cyclic_tree_is_written_flat() {
+        let session = ParsedSession::parse(&cyclic_session()).unwrap();
+        assert!(session.has_invalid_trees());
+        assert!(session.tree_warning().is_some());
+
+        let generate_options = GenerateOptions {
+            tree_style_tab_trees: true,
+            sidebery_trees: true,
+            ..Default::default()
+        };
+        let tree_sources = tab_walk::tree_sources(&generate_options);
+        let groups = tab_walk::groups_for_links(&session, &generate_options);
+
+        // Both windows keep all their tabs and can be walked:
+        let urls = groups
+            .iter()
+            .map(|group| group.tabs().iter().map(|tab| tab.url()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            urls,
+            [vec!["https://a.example/", "https://b.example/"], vec!["https://c.example/"]]
+        );
+        assert!(groups
+            .iter()
+            .all(|group| has_valid_tree(group, &tree_sources)));
+    }
+}
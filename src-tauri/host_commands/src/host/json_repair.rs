@@ -0,0 +1,328 @@
+//! Fix minor corruption in sessionstore JSON data, for example from backups
+//! that were saved with a byte order mark or have garbage after the data.
+//! Data that was cut off can also be closed so that the windows before the
+//! damage can be recovered.
+
+use serde_json::{json, Map, Value};
+
+/// UTF 8 Byte Order Mark.
+const UTF_8_BOM: &[u8] = b"\xEF\xBB\xBF";
+/// UTF 16 little-endian Byte Order Mark.
+const UTF_16_LE_BOM: &[u8] = b"\xFF\xFE";
+/// UTF 16 big-endian Byte Order Mark.
+const UTF_16_BE_BOM: &[u8] = b"\xFE\xFF";
+
+/// Top level fields of sessionstore data that store windows, open windows
+/// first.
+const WINDOW_LISTS: [&str; 2] = ["windows", "_closedWindows"];
+
+/// Decode UTF-16 data to UTF-8, replacing invalid characters.
+fn utf16_to_utf8(data: &[u8], from_bytes: fn([u8; 2]) -> u16) -> Vec<u8> {
+    let units = data
+        .chunks_exact(2)
+        .map(|pair| from_bytes([pair[0], pair[1]]))
+        .collect::<Vec<_>>();
+    String::from_utf16_lossy(&units).into_bytes()
+}
+
+/// Attempt to repair JSON data that couldn't be parsed:
+///
+/// - Remove a leading byte order mark and convert UTF-16 text to UTF-8.
+/// - Remove leading and trailing whitespace and null bytes.
+/// - Only keep the outermost `{...}` object.
+///
+/// Returns `None` if no repair could be made.
+pub fn repair(data: &[u8]) -> Option<Vec<u8>> {
+    let decoded;
+    let (data, mut repaired_any) = if let Some(rest) = data.strip_prefix(UTF_8_BOM) {
+        (rest, true)
+    } else if let Some(rest) = data.strip_prefix(UTF_16_LE_BOM) {
+        decoded = utf16_to_utf8(rest, u16::from_le_bytes);
+        (&*decoded, true)
+    } else if let Some(rest) = data.strip_prefix(UTF_16_BE_BOM) {
+        decoded = utf16_to_utf8(rest, u16::from_be_bytes);
+        (&*decoded, true)
+    } else {
+        (data, false)
+    };
+
+    let is_padding = |b: &u8| b.is_ascii_whitespace() || *b == 0;
+    let start = data.iter().position(|b| !is_padding(b))?;
+    let end = data.iter().rposition(|b| !is_padding(b))? + 1;
+    let mut repaired = &data[start..end];
+
+    // Garbage before or after the outermost object:
+    if let (Some(open), Some(close)) = (
+        repaired.iter().position(|&b| b == b'{'),
+        repaired.iter().rposition(|&b| b == b'}'),
+    ) {
+        if open < close {
+            repaired = &repaired[open..=close];
+        }
+    }
+
+    repaired_any |= repaired.len() != data.len();
+    repaired_any.then(|| repaired.to_vec())
+}
+
+/// JSON data that was closed by [`close_truncated`].
+#[derive(Debug)]
+pub struct ClosedJson {
+    pub data: Vec<u8>,
+    /// The top level field whose list had its last item cut off, for example
+    /// `"windows"` if the data ended inside a window. The item is kept in
+    /// `data` without anything that came after the cut.
+    pub cut_item_of: Option<String>,
+}
+
+/// Make JSON data that was cut off valid again by removing everything after
+/// the last complete object or array and then closing all objects and arrays
+/// that are still open. Used to recover data from sessionstore files with a
+/// truncated tail.
+///
+/// Returns `None` if the data isn't truncated or no object or array was
+/// complete.
+pub fn close_truncated(data: &[u8]) -> Option<ClosedJson> {
+    let data = data.strip_prefix(UTF_8_BOM).unwrap_or(data);
+    let mut open = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    // The last string in the outermost object and the key of the value that
+    // is being written in it:
+    let mut last_string = 0..0;
+    let mut key = 0..0;
+    // Length of the data up to the last complete value, how many objects or
+    // arrays were open at that point and the top level key at that point.
+    // Nothing is closed after that point (closing would complete a value) so
+    // the open objects and arrays are still at the start of `open`:
+    let mut last_complete = None;
+
+    for (ix, &byte) in data.iter().enumerate() {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => {
+                    in_string = false;
+                    if open.len() == 1 {
+                        last_string.end = ix;
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'"' => {
+                in_string = true;
+                if open.len() == 1 {
+                    last_string = ix + 1..ix + 1;
+                }
+            }
+            b'{' | b'[' => {
+                if open.len() == 1 {
+                    key = last_string.clone();
+                }
+                open.push(byte);
+            }
+            b'}' | b']' => {
+                open.pop()?;
+                if open.is_empty() {
+                    // The outermost value is complete:
+                    return None;
+                }
+                last_complete = Some((ix + 1, open.len(), key.clone()));
+            }
+            _ => {}
+        }
+    }
+
+    let (end, depth, key) = last_complete?;
+    let mut closed = data[..end].to_vec();
+    closed.extend(open[..depth].iter().rev().map(|&b| match b {
+        b'{' => b'}',
+        _ => b']',
+    }));
+    // Inside an item of a list in the outermost object:
+    let cut_item_of = (depth >= 3 && open[0] == b'{' && open[1] == b'[')
+        .then(|| String::from_utf8_lossy(&data[key]).into_owned());
+    Some(ClosedJson {
+        data: closed,
+        cut_item_of,
+    })
+}
+
+/// Add the top level fields of sessionstore data that are missing, for
+/// example because the data was cut off before they were written. The added
+/// fields are the same as in a session without any windows.
+pub fn fill_missing_fields(root: &mut Map<String, Value>) {
+    let Value::Object(defaults) = json!({
+        "version": ["sessionrestore", 1],
+        "windows": [],
+        "_closedWindows": [],
+        "selectedWindow": 1,
+        "session": { "lastUpdate": 0, "startTime": 0, "recentCrashes": 0 },
+        "global": {},
+    }) else {
+        unreachable!("defaults are an object");
+    };
+    for (key, default) in defaults {
+        match (root.get_mut(&key), default) {
+            (None, default) => {
+                root.insert(key, default);
+            }
+            // The object might have been cut off before some of its fields:
+            (Some(Value::Object(fields)), Value::Object(defaults)) => {
+                for (key, default) in defaults {
+                    fields.entry(key).or_insert(default);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Windows that were removed by [`keep_readable_windows`].
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SkippedWindows {
+    /// The data was cut off inside a window, `true` if it was a closed
+    /// window.
+    pub cut_off: Option<bool>,
+    /// Number of other windows that couldn't be read.
+    pub unreadable: usize,
+}
+
+/// Remove the window that the data was cut off inside of and windows that
+/// `is_readable` rejects from sessionstore data that was closed by
+/// [`close_truncated`]. Each window is checked on its own in `session` with
+/// all other windows removed, so the top level fields should be complete, see
+/// [`fill_missing_fields`].
+pub fn keep_readable_windows(
+    session: &mut Value,
+    cut_item_of: Option<&str>,
+    mut is_readable: impl FnMut(&Value) -> bool,
+) -> SkippedWindows {
+    let mut skipped = SkippedWindows::default();
+    if !session.is_object() {
+        return skipped;
+    }
+    let mut lists = WINDOW_LISTS.map(|key| match session.get_mut(key) {
+        Some(Value::Array(windows)) => std::mem::take(windows),
+        _ => Vec::new(),
+    });
+    for (ix, key) in WINDOW_LISTS.into_iter().enumerate() {
+        let windows = &mut lists[ix];
+        // Its tabs after the cut are missing:
+        if cut_item_of == Some(key) && windows.pop().is_some() {
+            skipped.cut_off = Some(ix == 1);
+        }
+        let mut readable = Vec::with_capacity(windows.len());
+        for window in windows.drain(..) {
+            session[key] = Value::Array(vec![window]);
+            let keep = is_readable(session);
+            let Value::Array(mut checked) = session[key].take() else {
+                unreachable!("the window was inserted as a list");
+            };
+            if keep {
+                readable.extend(checked.pop());
+            } else {
+                skipped.unreadable += 1;
+            }
+        }
+        session[key] = Value::Array(Vec::new());
+        *windows = readable;
+    }
+    for (key, windows) in WINDOW_LISTS.into_iter().zip(lists) {
+        session[key] = Value::Array(windows);
+    }
+    skipped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The closed data as text and the list whose last item was cut off.
+    fn close(data: &str) -> Option<(String, Option<String>)> {
+        let closed = close_truncated(data.as_bytes())?;
+        Some((String::from_utf8(closed.data).unwrap(), closed.cut_item_of))
+    }
+
+    fn closed(data: &str, cut_item_of: Option<&str>) -> Option<(String, Option<String>)> {
+        Some((data.to_owned(), cut_item_of.map(str::to_owned)))
+    }
+
+    #[test]
+    fn complete_data_is_not_closed() {
+        assert_eq!(close(r#"{"windows":[{"tabs":[]}]}"#), None);
+        // Nothing was complete before the cut:
+        assert_eq!(close(r#"{"windows":[{"tabs":"#), None);
+    }
+
+    #[test]
+    fn truncated_inside_string() {
+        // Brackets and escaped quotes in the string that was cut off are
+        // ignored:
+        assert_eq!(
+            close(r#"{"windows":[{"tabs":[1]},{"tabs":[2],"title":"a \" ] } [b"#),
+            closed(r#"{"windows":[{"tabs":[1]},{"tabs":[2]}]}"#, Some("windows")),
+        );
+    }
+
+    #[test]
+    fn truncated_inside_array() {
+        assert_eq!(
+            close(r#"{"windows":[{"tabs":[1]}],"_closedWindows":[{"tabs":[[2],[3"#),
+            closed(
+                r#"{"windows":[{"tabs":[1]}],"_closedWindows":[{"tabs":[[2]]}]}"#,
+                Some("_closedWindows"),
+            ),
+        );
+        // Between two windows, so no window was cut off:
+        assert_eq!(
+            close(r#"{"windows":[{"tabs":[1]}],"_closedWindows":[{"tabs":[2]},"#),
+            closed(r#"{"windows":[{"tabs":[1]}],"_closedWindows":[{"tabs":[2]}]}"#, None),
+        );
+    }
+
+    #[test]
+    fn truncated_inside_nested_objects() {
+        assert_eq!(
+            close(concat!(
+                r#"{"session":{"lastUpdate":1},"windows":[{"tabs":[{"entries":[{"url":"a"}],"#,
+                r#""extData":{"x":{"y":1"#,
+            )),
+            closed(
+                r#"{"session":{"lastUpdate":1},"windows":[{"tabs":[{"entries":[{"url":"a"}]}]}]}"#,
+                Some("windows"),
+            ),
+        );
+    }
+
+    #[test]
+    fn cut_and_unreadable_windows_are_skipped() {
+        let data = r#"{"windows":[{"tabs":[1]},{"bad":true},{"tabs":[2]},{"tabs":[3],"selected":"#;
+        let closed = close_truncated(data.as_bytes()).unwrap();
+        let mut session = serde_json::from_slice::<Value>(&closed.data).unwrap();
+        fill_missing_fields(session.as_object_mut().unwrap());
+
+        let mut checked = 0;
+        let skipped = keep_readable_windows(&mut session, closed.cut_item_of.as_deref(), |session| {
+            checked += 1;
+            let windows = session["windows"].as_array().unwrap();
+            assert_eq!(windows.len(), 1);
+            windows[0].get("bad").is_none()
+        });
+        assert_eq!(
+            skipped,
+            SkippedWindows {
+                cut_off: Some(false),
+                unreadable: 1,
+            }
+        );
+        assert_eq!(checked, 3);
+        assert_eq!(session["windows"], json!([{ "tabs": [1] }, { "tabs": [2] }]));
+        assert_eq!(session["_closedWindows"], json!([]));
+        assert_eq!(session["session"]["lastUpdate"], json!(0));
+    }
+}
@@ -0,0 +1,101 @@
+//! Table of contents for Markdown documents where each entry links to its
+//! window's heading.
+//!
+//! Renderers such as GitHub give each heading an `id` that is derived from its
+//! text, so the links only work if we derive the same "slug" for the heading.
+
+use std::collections::HashMap;
+
+/// The anchor that GitHub creates for a heading with the specified text: the
+/// text is lowercased, punctuation is removed and spaces become hyphens.
+pub fn github_slug(heading: &str) -> String {
+    heading
+        .trim()
+        .chars()
+        .flat_map(char::to_lowercase)
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Gives headings with the same text unique slugs the same way as GitHub, by
+/// appending `-1`, `-2` and so on to later headings.
+#[derive(Debug, Default)]
+pub struct SlugGenerator {
+    seen: HashMap<String, u32>,
+}
+impl SlugGenerator {
+    pub fn slug(&mut self, heading: &str) -> String {
+        let slug = github_slug(heading);
+        let count = self.seen.entry(slug.clone()).or_insert(0);
+        let unique = if *count == 0 {
+            slug
+        } else {
+            format!("{slug}-{count}")
+        };
+        *count += 1;
+        unique
+    }
+}
+
+/// Level and text of an ATX heading such as `## Window 1`.
+fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.bytes().take_while(|&b| b == b'#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let text = &line[level..];
+    if !(text.is_empty() || text.starts_with([' ', '\t'])) {
+        return None;
+    }
+    // Optional closing sequence:
+    let text = text.trim().trim_end_matches('#').trim_end();
+    Some((level, text))
+}
+
+/// Insert a table of contents at the start of a Markdown document that links
+/// to each heading in it. Headings inside fenced code blocks are ignored.
+pub fn add_linked_toc(markdown: &str) -> String {
+    add_toc(markdown, true)
+}
+
+/// Insert a table of contents at the start of a Markdown document with an
+/// entry for each heading in it, optionally linking to the headings.
+pub fn add_toc(markdown: &str, linked: bool) -> String {
+    let mut slugs = SlugGenerator::default();
+    let mut headings = Vec::new();
+    let mut in_code_block = false;
+    for line in markdown.lines() {
+        if line.starts_with("```") || line.starts_with("~~~") {
+            in_code_block = !in_code_block;
+        } else if let Some((level, text)) = parse_heading(line).filter(|_| !in_code_block) {
+            // Escapes don't affect the rendered text that the slug is
+            // created from:
+            let slug = slugs.slug(&text.replace('\\', ""));
+            headings.push((level, text, slug));
+        }
+    }
+    if headings.is_empty() {
+        return markdown.to_owned();
+    }
+
+    // Bold text instead of a heading so that it doesn't get a slug that could
+    // change the slugs of the window headings:
+    let mut output = String::from("**Table of Contents**\n\n");
+    let top_level = headings.iter().map(|(level, ..)| *level).min().unwrap_or(1);
+    for (level, text, slug) in &headings {
+        let indent = "  ".repeat(level - top_level);
+        if linked {
+            output.push_str(&format!("{indent}- [{text}](#{slug})\n"));
+        } else {
+            output.push_str(&format!("{indent}- {text}\n"));
+        }
+    }
+    output.push('\n');
+    output.push_str(markdown);
+    output
+}
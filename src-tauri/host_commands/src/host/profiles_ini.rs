@@ -0,0 +1,71 @@
+//! Read Firefox's `profiles.ini` to find out which profiles are used by
+//! default.
+//!
+//! Newer Firefox versions remember a default profile for each installation in
+//! `[Install<hash>]` sections, older versions mark a `[Profile<n>]` section
+//! with `Default=1`.
+
+use std::path::{Path, PathBuf};
+
+/// A profile's path as written in `profiles.ini`, relative to the folder of
+/// the file unless `IsRelative=0`.
+fn profile_path(root: &Path, path: &str, is_relative: bool) -> PathBuf {
+    if is_relative {
+        root.join(path)
+    } else {
+        PathBuf::from(path)
+    }
+}
+
+/// The folders of the default profiles listed in the `profiles.ini` file
+/// inside `root`. Empty if the file couldn't be read.
+pub fn default_profiles(root: &Path) -> Vec<PathBuf> {
+    let Ok(ini) = std::fs::read_to_string(root.join("profiles.ini")) else {
+        return Vec::new();
+    };
+
+    let mut install_defaults = Vec::new();
+    let mut legacy_defaults = Vec::new();
+    // Values of the current `[Profile<n>]` section:
+    let mut path = None;
+    let mut is_relative = true;
+    let mut is_default = false;
+    let mut in_profile = false;
+    let mut in_install = false;
+
+    let mut end_profile = |path: &mut Option<&str>, is_relative: bool, is_default: bool| {
+        if let Some(path) = path.take().filter(|_| is_default) {
+            legacy_defaults.push(profile_path(root, path, is_relative));
+        }
+    };
+    for line in ini.lines().map(str::trim) {
+        if let Some(section) = line.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            if in_profile {
+                end_profile(&mut path, is_relative, is_default);
+            }
+            in_profile = section.starts_with("Profile");
+            in_install = section.starts_with("Install");
+            (is_relative, is_default) = (true, false);
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        match key.trim() {
+            "Default" if in_install => {
+                // Absolute paths replace the root when joined:
+                install_defaults.push(profile_path(root, value.trim(), true));
+            }
+            "Default" if in_profile => is_default = value.trim() == "1",
+            "Path" if in_profile => path = Some(value.trim()),
+            "IsRelative" if in_profile => is_relative = value.trim() != "0",
+            _ => {}
+        }
+    }
+    if in_profile {
+        end_profile(&mut path, is_relative, is_default);
+    }
+
+    install_defaults.extend(legacy_defaults);
+    install_defaults
+}
@@ -0,0 +1,245 @@
+//! Convert [`SavedUiState`] to and from JSON so that it can be stored with
+//! [`config`](super::config).
+//!
+//! Fields are read one at a time and missing or invalid fields keep their
+//! default values, so that state saved by other versions of the program can
+//! still be restored.
+
+use serde_json::{json, Map, Value};
+
+use crate::{
+    DuplicateNameStrategy, GenerateOptions, GroupBy, MarkdownStyle, OutputFormat, OutputOptions,
+    PageSize, SavedUiState, TabSort, TextEncoding, Theme,
+};
+
+/// Find the enum variant whose `as_str` value matches a JSON string.
+fn parse_variant<T: Copy>(
+    value: Option<&Value>,
+    all: &[T],
+    as_str: fn(T) -> &'static str,
+) -> Option<T> {
+    let name = value?.as_str()?;
+    all.iter().copied().find(|&variant| as_str(variant) == name)
+}
+
+fn index_list(value: Option<&Value>) -> Vec<u32> {
+    value
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|ix| u32::try_from(ix.as_u64()?).ok())
+        .collect()
+}
+
+/// Overwrite `$target.$field` with the JSON value of the same name if it has
+/// the right type.
+macro_rules! read_fields {
+    ($object:expr, $target:expr, $as_value:ident => { $($field:ident),* $(,)? }) => {
+        $(
+            if let Some(value) = $object.get(stringify!($field)).and_then(Value::$as_value) {
+                $target.$field = value.into();
+            }
+        )*
+    };
+}
+
+fn generate_options_to_json(options: &GenerateOptions) -> Value {
+    json!({
+        "sort_groups": options.sort_groups,
+        "table_of_content": options.table_of_content,
+        "tree_style_tab_trees": options.tree_style_tab_trees,
+        "sidebery_trees": options.sidebery_trees,
+        "flat_links": options.flat_links,
+        "markdown_style": options.markdown_style.as_str(),
+        "mark_closed_windows": options.mark_closed_windows,
+        "markdown_toc_links": options.markdown_toc_links,
+        "mark_collapsed_tabs": options.mark_collapsed_tabs,
+        "annotate_languages": options.annotate_languages,
+        "include_timestamps": options.include_timestamps,
+        "include_container": options.include_container,
+        "typst_font": options.typst_font,
+        "typst_template": options.typst_template,
+        "pdf_outline": options.pdf_outline,
+        "window_separator": options.window_separator,
+        "custom_page_break": options.custom_page_break,
+        "include_counts": options.include_counts,
+        "deduplicate_links": options.deduplicate_links,
+        "deduplicate_globally": options.deduplicate_globally,
+        "html_window_filter": options.html_window_filter,
+        "pinned_only": options.pinned_only,
+        "include_closed_tabs": options.include_closed_tabs,
+        "duplicate_names": options.duplicate_names.as_str(),
+        "tab_sort": options.tab_sort.as_str(),
+        "group_by": options.group_by.as_str(),
+        "reproducible": options.reproducible,
+    })
+}
+
+fn generate_options_from_json(value: &Value) -> GenerateOptions {
+    let mut options = GenerateOptions::default();
+    read_fields!(value, options, as_bool => {
+        sort_groups,
+        table_of_content,
+        tree_style_tab_trees,
+        sidebery_trees,
+        flat_links,
+        mark_closed_windows,
+        markdown_toc_links,
+        mark_collapsed_tabs,
+        annotate_languages,
+        include_timestamps,
+        include_container,
+        pdf_outline,
+        include_counts,
+        deduplicate_links,
+        deduplicate_globally,
+        html_window_filter,
+        pinned_only,
+        include_closed_tabs,
+        reproducible,
+    });
+    // null means None so don't keep the default value:
+    options.typst_font = value
+        .get("typst_font")
+        .and_then(Value::as_str)
+        .map(str::to_owned);
+    options.typst_template = value
+        .get("typst_template")
+        .and_then(Value::as_str)
+        .map(str::to_owned);
+    options.window_separator = value
+        .get("window_separator")
+        .and_then(Value::as_str)
+        .map(str::to_owned);
+    if let Some(page_break) = value.get("custom_page_break").and_then(Value::as_str) {
+        options.custom_page_break = page_break.to_owned();
+    }
+    if let Some(strategy) = parse_variant(
+        value.get("duplicate_names"),
+        DuplicateNameStrategy::all(),
+        DuplicateNameStrategy::as_str,
+    ) {
+        options.duplicate_names = strategy;
+    }
+    if let Some(tab_sort) = parse_variant(value.get("tab_sort"), TabSort::all(), TabSort::as_str) {
+        options.tab_sort = tab_sort;
+    }
+    if let Some(group_by) = parse_variant(value.get("group_by"), GroupBy::all(), GroupBy::as_str) {
+        options.group_by = group_by;
+    }
+    options.markdown_style = parse_variant(
+        value.get("markdown_style"),
+        MarkdownStyle::all(),
+        MarkdownStyle::as_str,
+    )
+    .unwrap_or_else(|| legacy_markdown_style(value));
+    options
+}
+
+/// The style from the separate options that older versions saved, in the
+/// order they used to take precedence.
+fn legacy_markdown_style(value: &Value) -> MarkdownStyle {
+    let is_set = |field: &str| value.get(field).and_then(Value::as_bool) == Some(true);
+    if is_set("markdown_table") {
+        MarkdownStyle::Table
+    } else if is_set("markdown_details") {
+        MarkdownStyle::Details
+    } else if is_set("markdown_task_list") {
+        MarkdownStyle::TaskList
+    } else if is_set("html_details") {
+        MarkdownStyle::HtmlDetails
+    } else {
+        MarkdownStyle::List
+    }
+}
+
+fn output_options_to_json(options: &OutputOptions) -> Value {
+    json!({
+        "format": options.format.as_str(),
+        "overwrite": options.overwrite,
+        "create_folder": options.create_folder,
+        "generation_timeout_secs": options.generation_timeout_secs,
+        "include_export_id": options.include_export_id,
+        "restore_bundle": options.restore_bundle,
+        "split_per_window": options.split_per_window,
+        "reveal_after_save": options.reveal_after_save,
+        "save_preview": options.save_preview,
+        "text_encoding": options.text_encoding.as_str(),
+        "page_size": options.page_size.as_str(),
+        "landscape": options.landscape,
+        "margins_mm": options.margins_mm,
+        "pdf_render_retries": options.pdf_render_retries,
+    })
+}
+
+fn output_options_from_json(value: &Value) -> OutputOptions {
+    let mut options = OutputOptions::default();
+    read_fields!(value, options, as_bool => {
+        overwrite,
+        create_folder,
+        include_export_id,
+        restore_bundle,
+        split_per_window,
+        reveal_after_save,
+        save_preview,
+        landscape,
+    });
+    options.generation_timeout_secs = value.get("generation_timeout_secs").and_then(Value::as_u64);
+    if let Some(format) = parse_variant(
+        value.get("format"),
+        OutputFormat::all(),
+        OutputFormat::as_str,
+    ) {
+        options.format = format;
+    }
+    if let Some(encoding) = parse_variant(
+        value.get("text_encoding"),
+        TextEncoding::all(),
+        TextEncoding::as_str,
+    ) {
+        options.text_encoding = encoding;
+    }
+    if let Some(page_size) =
+        parse_variant(value.get("page_size"), PageSize::all(), PageSize::as_str)
+    {
+        options.page_size = page_size;
+    }
+    if let Some(margins_mm) = value.get("margins_mm").and_then(Value::as_f64) {
+        options.margins_mm = margins_mm as f32;
+    }
+    if let Some(retries) = value.get("pdf_render_retries").and_then(Value::as_u64) {
+        options.pdf_render_retries = retries.min(u32::MAX as u64) as u32;
+    }
+    options
+}
+
+pub fn to_json(state: &SavedUiState) -> Value {
+    json!({
+        "loaded_path": state.loaded_path,
+        "output_options": output_options_to_json(&state.output_options),
+        "generate_options": generate_options_to_json(&state.generate_options),
+        "selected_open_windows": state.selected_open_windows,
+        "selected_closed_windows": state.selected_closed_windows,
+        "window_checkbox_list": state.window_checkbox_list,
+        "theme": state.theme.as_str(),
+    })
+}
+
+pub fn from_json(value: &Value) -> SavedUiState {
+    let empty = Value::Object(Map::new());
+    let mut state = SavedUiState {
+        output_options: output_options_from_json(value.get("output_options").unwrap_or(&empty)),
+        generate_options: generate_options_from_json(
+            value.get("generate_options").unwrap_or(&empty),
+        ),
+        selected_open_windows: index_list(value.get("selected_open_windows")),
+        selected_closed_windows: index_list(value.get("selected_closed_windows")),
+        ..SavedUiState::default()
+    };
+    read_fields!(value, state, as_str => { loaded_path });
+    read_fields!(value, state, as_bool => { window_checkbox_list });
+    if let Some(theme) = parse_variant(value.get("theme"), Theme::all(), Theme::as_str) {
+        state.theme = theme;
+    }
+    state
+}
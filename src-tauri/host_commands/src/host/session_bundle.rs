@@ -0,0 +1,109 @@
+//! Save the exported windows as a Firefox sessionstore file that can be placed
+//! next to a HTML document so the tabs can be restored later.
+
+use std::{io::Write, path::Path};
+
+use serde_json::Value;
+
+use super::{
+    compression::{self, Compression},
+    containers::tab_url,
+    generate,
+    tab_walk::WindowLinks,
+    MOZ_LZ4_MAGIC,
+};
+
+/// Decompress the data of a sessionstore file that was read again. The file
+/// might have changed since it was loaded, so the output is limited to
+/// `max_bytes` just like when a file is decompressed for parsing.
+pub fn decompress_session_data(data: Vec<u8>, max_bytes: u64) -> Result<Vec<u8>, String> {
+    Ok(match Compression::detect(&data) {
+        Some(Compression::MozLz4) => {
+            compression::check_moz_lz4_size(&data, max_bytes)?;
+            compression::decompress_moz_lz4(&data)?
+        }
+        Some(Compression::Gzip) => compression::decompress_gzip(&data, max_bytes)?,
+        Some(Compression::Zstd) => compression::decompress_zstd(&data, max_bytes)?,
+        None => data,
+    })
+}
+
+/// Read and parse the sessionstore file at `path` as untyped JSON so that all
+/// data is preserved when it is written again. Decompressed data larger than
+/// `max_bytes` is rejected.
+pub fn read_session_json(path: &Path, max_bytes: u64) -> Result<Value, String> {
+    let data = std::fs::read(path)
+        .map_err(|e| format!("failed to read sessionstore file at \"{}\": {e}", path.display()))?;
+    let data = decompress_session_data(data, max_bytes)?;
+
+    serde_json::from_slice(&data)
+        .map_err(|e| format!("failed to parse sessionstore JSON data: {e}"))
+}
+
+/// Only keep the windows that were exported. Windows are matched by the URLs
+/// of their tabs since the exported windows might have been sorted.
+pub fn retain_windows(session: &mut Value, windows: &[WindowLinks]) {
+    let mut remaining = windows
+        .iter()
+        .map(|window| {
+            let mut urls = window.tabs.iter().map(|tab| tab.url.as_str()).collect::<Vec<_>>();
+            urls.sort_unstable();
+            (window.is_closed, urls)
+        })
+        .collect::<Vec<_>>();
+
+    for (key, is_closed) in [("windows", false), ("_closedWindows", true)] {
+        let Some(Value::Array(session_windows)) = session.get_mut(key) else {
+            continue;
+        };
+        session_windows.retain(|window| {
+            let mut urls = window
+                .get("tabs")
+                .and_then(Value::as_array)
+                .map(|tabs| tabs.iter().filter_map(tab_url).collect::<Vec<_>>())
+                .unwrap_or_default();
+            urls.sort_unstable();
+
+            let found = remaining
+                .iter()
+                .position(|(closed, exported)| *closed == is_closed && *exported == urls);
+            if let Some(ix) = found {
+                remaining.swap_remove(ix);
+            }
+            found.is_some()
+        });
+    }
+    if let Some(Value::Array(session_windows)) = session.get("windows") {
+        // Firefox restores the selected window so ensure it still exists:
+        let selected = session_windows.len().min(1) as u64;
+        session["selectedWindow"] = selected.into();
+    }
+}
+
+/// Write sessionstore JSON data as a LZ4 compressed file that Firefox can
+/// read.
+pub fn write_jsonlz4(session: &Value, mut output: impl Write) -> Result<(), String> {
+    let json = serde_json::to_vec(session)
+        .map_err(|e| format!("failed to serialize sessionstore data: {e}"))?;
+    // Prepends the decompressed size as a little-endian u32, just like Firefox:
+    let compressed = lz4::block::compress(&json, None, true)
+        .map_err(|e| format!("failed to compress sessionstore data: {e}"))?;
+    output
+        .write_all(MOZ_LZ4_MAGIC)
+        .and_then(|_| output.write_all(&compressed))
+        .map_err(|e| format!("failed to write sessionstore file: {e}"))
+}
+
+/// Insert a link to the sessionstore file at the start of a HTML document's
+/// body.
+pub fn insert_restore_link(html: &[u8], bundle_file_name: &str) -> Vec<u8> {
+    let file_name = bundle_file_name
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;");
+    let link = format!(
+        "<p><a href=\"{file_name}\" download>Restore these tabs</a> \
+        (replace the sessionstore file in a Firefox profile with this file)</p>\n"
+    );
+    generate::insert_into_html_body(html, &link)
+}
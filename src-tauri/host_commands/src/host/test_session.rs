@@ -0,0 +1,54 @@
+//! Sessionstore JSON data shared by the tests of the host modules.
+
+use serde_json::{json, Value};
+
+use super::closed_tabs::ParsedSession;
+
+/// A tab with a single history entry whose title is its URL.
+pub fn tab(url: &str) -> Value {
+    json!({
+        "entries": [{ "url": url, "title": url }],
+        "index": 1,
+        "hidden": false,
+        "attributes": {},
+    })
+}
+
+/// A tab with extra fields, for example `"extData"` or `"userContextId"`.
+pub fn tab_with(url: &str, fields: Value) -> Value {
+    let mut tab = tab(url);
+    if let (Some(tab), Value::Object(fields)) = (tab.as_object_mut(), fields) {
+        tab.extend(fields);
+    }
+    tab
+}
+
+/// A window with `tabs` where the tabs in `closed_tabs` were recently closed.
+pub fn window(tabs: Vec<Value>, closed_tabs: Vec<Value>) -> Value {
+    json!({
+        "tabs": tabs,
+        "selected": 1,
+        "_closedTabs": closed_tabs
+            .into_iter()
+            .map(|tab| json!({ "state": tab, "closedAt": 0 }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// Sessionstore JSON data with open and closed windows.
+pub fn session_data(windows: Vec<Value>, closed_windows: Vec<Value>) -> Vec<u8> {
+    let value = json!({
+        "version": ["sessionrestore", 1],
+        "windows": windows,
+        "_closedWindows": closed_windows,
+        "selectedWindow": 1,
+        "session": { "lastUpdate": 0, "startTime": 0, "recentCrashes": 0 },
+        "global": {},
+    });
+    serde_json::to_vec(&value).unwrap()
+}
+
+/// Parse a session with open and closed windows.
+pub fn parse_session(windows: Vec<Value>, closed_windows: Vec<Value>) -> ParsedSession {
+    ParsedSession::parse(&session_data(windows, closed_windows)).unwrap()
+}
@@ -0,0 +1,253 @@
+//! Customize the Typst documents generated by [`firefox_session_data`].
+//!
+//! The Typst source is generated as usual and then we prepend extra set rules
+//! and any custom template before compiling it to a PDF.
+
+use std::io::Write;
+
+#[cfg(not(target_family = "wasm"))]
+use typst::{
+    diag::{FileError, FileResult},
+    foundations::{Bytes, Datetime},
+    layout::PagedDocument,
+    syntax::{FileId, Source, VirtualPath},
+    text::{Font, FontBook},
+    utils::LazyHash,
+    Library, World,
+};
+#[cfg(not(target_family = "wasm"))]
+use typst_kit::fonts::{FontSearcher, FontSlot, Fonts};
+
+use super::generate::PdfPageOptions;
+use crate::{GenerateOptions, PageSize};
+
+/// Find fonts that Typst can use, both system fonts and the fonts that are
+/// embedded in the program.
+#[cfg(not(target_family = "wasm"))]
+fn search_fonts() -> Fonts {
+    FontSearcher::new().include_system_fonts(true).search()
+}
+
+/// Names of all font families that Typst can use.
+#[cfg(not(target_family = "wasm"))]
+pub fn available_fonts() -> Vec<String> {
+    let fonts = search_fonts();
+    let mut families = fonts
+        .book
+        .families()
+        .map(|(family, _)| family.to_owned())
+        .collect::<Vec<_>>();
+    families.sort_unstable_by_key(|family| family.to_lowercase());
+    families.dedup();
+    families
+}
+
+/// Characters in `text` that no variant of the font family can display.
+#[cfg(not(target_family = "wasm"))]
+pub fn missing_chars(font_family: &str, text: &str) -> Vec<char> {
+    let fonts = search_fonts();
+    let Some((_, variants)) = fonts
+        .book
+        .families()
+        .find(|(family, _)| family.eq_ignore_ascii_case(font_family))
+    else {
+        // Unknown font so nothing can be displayed:
+        let mut chars = text.chars().filter(|c| !c.is_whitespace()).collect::<Vec<_>>();
+        chars.sort_unstable();
+        chars.dedup();
+        return chars;
+    };
+    let variants = variants.collect::<Vec<_>>();
+
+    let mut missing = text
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .filter(|&c| !variants.iter().any(|info| info.coverage.contains(c as u32)))
+        .collect::<Vec<_>>();
+    missing.sort_unstable();
+    missing.dedup();
+    missing
+}
+
+/// Names of all font families that Typst can use. Can't search for system
+/// fonts inside a web page.
+#[cfg(target_family = "wasm")]
+pub fn available_fonts() -> Vec<String> {
+    Vec::new()
+}
+
+/// Characters in `text` that no variant of the font family can display. Fonts
+/// can't be inspected inside a web page so this assumes all characters are
+/// supported.
+#[cfg(target_family = "wasm")]
+pub fn missing_chars(_font_family: &str, _text: &str) -> Vec<char> {
+    Vec::new()
+}
+
+/// Escape text so it can be placed inside a Typst string literal.
+fn escape_string(text: &str) -> String {
+    text.replace('\\', r"\\").replace('"', "\\\"")
+}
+
+/// Name of a paper size in Typst's `page` function.
+fn paper_name(page_size: PageSize) -> &'static str {
+    match page_size {
+        PageSize::A4 => "a4",
+        PageSize::Letter => "us-letter",
+        PageSize::Legal => "us-legal",
+        PageSize::A3 => "a3",
+    }
+}
+
+/// Typst code that should be placed before the generated document to apply
+/// the options. Empty if the generated document shouldn't be changed.
+///
+/// The `title` and `export_id` are stored in the document's metadata and shown
+/// at the top of the first page.
+pub fn preamble(
+    generate_options: &GenerateOptions,
+    pdf_page: PdfPageOptions,
+    title: Option<&str>,
+    export_id: Option<&str>,
+) -> String {
+    let mut preamble = String::new();
+    if !pdf_page.is_default() {
+        // Must be set before any content so that it applies to the first page:
+        preamble.push_str(&format!(
+            "#set page(paper: \"{}\", flipped: {}, margin: {}mm)\n",
+            paper_name(pdf_page.page_size),
+            pdf_page.landscape,
+            pdf_page.margins_mm
+        ));
+    }
+    if let Some(title) = title {
+        preamble.push_str(&format!(
+            "#set document(title: \"{}\")\n",
+            escape_string(title)
+        ));
+    }
+    if let Some(export_id) = export_id {
+        preamble.push_str(&format!(
+            "#set document(keywords: (\"export-id:{}\",))\n",
+            escape_string(export_id)
+        ));
+    }
+    if let Some(font) = &generate_options.typst_font {
+        preamble.push_str(&format!("#set text(font: \"{}\")\n", escape_string(font)));
+    }
+    if let Some(template) = generate_options
+        .typst_template
+        .as_deref()
+        .filter(|template| !template.trim().is_empty())
+    {
+        // Before any content so that its rules apply to the whole document:
+        preamble.push_str(template);
+        preamble.push('\n');
+    }
+    if let Some(title) = title {
+        preamble.push_str(&format!(
+            "#align(center, text(size: 16pt, weight: \"bold\")[#\"{}\"])\n",
+            escape_string(title)
+        ));
+    }
+    if let Some(export_id) = export_id {
+        preamble.push_str(&format!(
+            "#text(size: 8pt)[Export ID: #\"{}\"]\n",
+            escape_string(export_id)
+        ));
+    }
+    if generate_options.pdf_outline {
+        // Headings become bookmarks in the PDF's navigation pane and the table
+        // of contents gets its own page with page numbers:
+        preamble.push_str("#set heading(bookmarked: true)\n");
+        preamble.push_str("#outline(title: [Table of Contents])\n");
+        preamble.push_str("#pagebreak()\n");
+    }
+    preamble
+}
+
+/// Only the main file is available when a preview is rendered, custom
+/// templates can't import other files.
+#[cfg(not(target_family = "wasm"))]
+struct PreviewWorld {
+    library: LazyHash<Library>,
+    book: LazyHash<FontBook>,
+    fonts: Vec<FontSlot>,
+    main: Source,
+}
+#[cfg(not(target_family = "wasm"))]
+impl World for PreviewWorld {
+    fn library(&self) -> &LazyHash<Library> {
+        &self.library
+    }
+    fn book(&self) -> &LazyHash<FontBook> {
+        &self.book
+    }
+    fn main(&self) -> FileId {
+        self.main.id()
+    }
+    fn source(&self, id: FileId) -> FileResult<Source> {
+        if id == self.main.id() {
+            Ok(self.main.clone())
+        } else {
+            Err(FileError::NotFound(id.vpath().as_rootless_path().into()))
+        }
+    }
+    fn file(&self, id: FileId) -> FileResult<Bytes> {
+        Err(FileError::NotFound(id.vpath().as_rootless_path().into()))
+    }
+    fn font(&self, index: usize) -> Option<Font> {
+        self.fonts.get(index)?.get()
+    }
+    fn today(&self, _offset: Option<i64>) -> Option<Datetime> {
+        None
+    }
+}
+
+/// Compile Typst source code and render its first page as a PNG image, used
+/// to preview PDFs.
+#[cfg(not(target_family = "wasm"))]
+pub fn render_first_page(source: &str) -> Result<Vec<u8>, String> {
+    /// Twice the resolution of the PDF's points so that text is readable.
+    const PIXELS_PER_POINT: f32 = 2.0;
+
+    let fonts = search_fonts();
+    let world = PreviewWorld {
+        library: LazyHash::new(Library::builder().build()),
+        book: LazyHash::new(fonts.book),
+        fonts: fonts.fonts,
+        main: Source::new(FileId::new(None, VirtualPath::new("main.typ")), source.to_owned()),
+    };
+    // Custom templates can contain anything, just like when compiling PDFs:
+    let document = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        typst::compile::<PagedDocument>(&world)
+            .output
+            .map_err(|errors| {
+                let messages = errors
+                    .iter()
+                    .map(|error| error.message.as_str())
+                    .collect::<Vec<_>>();
+                format!("failed to compile Typst document: {}", messages.join(", "))
+            })
+    }))
+    .unwrap_or_else(|_| Err("compiling the Typst document panicked".to_string()))?;
+
+    let page = document.pages.first().ok_or("the Typst document has no pages")?;
+    typst_render::render(page, PIXELS_PER_POINT)
+        .encode_png()
+        .map_err(|e| format!("failed to encode the preview image: {e}"))
+}
+
+/// Compile Typst source code into a PDF document.
+pub fn compile_pdf(source: &str, mut output: impl Write) -> Result<(), String> {
+    // Custom templates can contain anything, so don't let a bug in the
+    // compiler take down the program:
+    let pdf = std::panic::catch_unwind(|| {
+        firefox_session_data::pdf_converter::typst_to_pdf::compile(source)
+            .map_err(|e| format!("failed to compile Typst document: {e}"))
+    })
+    .unwrap_or_else(|_| Err("compiling the Typst document panicked".to_string()))?;
+    output
+        .write_all(&pdf)
+        .map_err(|e| format!("failed to write PDF document: {e}"))
+}
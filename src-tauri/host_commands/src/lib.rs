@@ -61,6 +61,16 @@ pub struct FileInfo {
     pub data_id: DataId,
     pub status: FileStatus,
     pub file_path: Option<String>,
+    /// Size of the JSON data, known once the data has been decompressed.
+    pub uncompressed_bytes: Option<u64>,
+    /// Number of open and closed windows, known once the data has been
+    /// parsed.
+    pub window_count: Option<u32>,
+    /// Number of tabs in all open and closed windows, known once the data has
+    /// been parsed.
+    pub tab_count: Option<u32>,
+    /// Describes what was lost if only part of damaged data could be parsed.
+    pub parse_warning: Option<String>,
 }
 impl std::fmt::Display for FileInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -78,6 +88,8 @@ impl std::fmt::Display for FileInfo {
 pub struct TabGroup {
     pub index: u32,
     pub name: String,
+    /// Number of tabs in the window.
+    pub tab_count: u32,
 }
 
 #[TauriSerialize]
@@ -98,6 +110,98 @@ pub struct GenerateOptions {
     pub table_of_content: bool,
     pub tree_style_tab_trees: bool,
     pub sidebery_trees: bool,
+    /// Write every tab on its own line without indentation, ignoring the tree
+    /// structure from Tree Style Tab and Sidebery. Tabs that were nested in a
+    /// tree are still included.
+    pub flat_links: bool,
+    /// Layout of saved Markdown and HTML documents, see [`MarkdownStyle`].
+    pub markdown_style: MarkdownStyle,
+    /// Add "(closed)" to the names of closed windows in generated output so
+    /// that they can be told apart from open windows.
+    pub mark_closed_windows: bool,
+    /// Make the entries in the table of contents of Markdown documents link to
+    /// each window's heading, using the same anchors as GitHub. Ignored if
+    /// `table_of_content` isn't set.
+    pub markdown_toc_links: bool,
+    /// Mark tabs that were hidden inside a collapsed subtree of a Tree Style
+    /// Tab or Sidebery tree with "(was collapsed)".
+    pub mark_collapsed_tabs: bool,
+    /// Append the detected language of each tab's title, for example
+    /// "[English]". Titles that are too short to detect are marked
+    /// "[Unknown]".
+    pub annotate_languages: bool,
+    /// Append when each tab was last accessed in the local time zone, for
+    /// example "(last accessed 2024-05-01 14:30)". Tabs that were never
+    /// accessed aren't marked.
+    pub include_timestamps: bool,
+    /// Append the name of the container (contextual identity) that each tab
+    /// was opened in, for example "(container: Work)". Containers whose names
+    /// can't be read from the profile are called "Container N" where N is
+    /// their ID. Tabs that aren't in a container aren't marked.
+    pub include_container: bool,
+    /// Font family used by Typst based formats, see
+    /// [`StatelessCommands::typst_available_fonts`].
+    pub typst_font: Option<String>,
+    /// Put the table of contents on its own page and add bookmarks for each
+    /// window to the PDF's navigation pane. Only supported by Typst based
+    /// formats.
+    pub pdf_outline: bool,
+    /// Typst code that is placed before the generated document, after the
+    /// rules that apply the other options, so that its set and show rules can
+    /// change fonts, margins and headings. Only used by Typst based formats.
+    pub typst_template: Option<String>,
+    /// Line inserted between windows in the preview and when copying links to
+    /// the clipboard. `None` to only separate windows with empty lines.
+    pub window_separator: Option<String>,
+    /// Line inserted between windows in saved text and Markdown documents,
+    /// for example `----8<----`. Also used for the preview if no
+    /// `window_separator` is set. Empty to keep the default spacing.
+    pub custom_page_break: String,
+    /// Include the tabs that were recently closed in each open window, in a
+    /// "Recently closed" section after the window's tabs.
+    pub include_closed_tabs: bool,
+    /// Add the number of windows and tabs to the document's title and the
+    /// number of tabs to each window's heading. Only selected windows are
+    /// counted.
+    pub include_counts: bool,
+    /// Make window headings in HTML documents clickable so that readers can
+    /// show only that window's tabs. Uses a small inlined script. Ignored for
+    /// [`MarkdownStyle::HtmlDetails`].
+    pub html_window_filter: bool,
+    /// How to tell apart windows that have the same name.
+    pub duplicate_names: DuplicateNameStrategy,
+    /// Order of the tabs inside each window. Windows with a tree of tabs keep
+    /// their order so that the tree stays intact.
+    pub tab_sort: TabSort,
+    /// Whether tabs are listed under their window or under their website.
+    pub group_by: GroupBy,
+    /// Only include tabs whose host matches one of these domains. A domain
+    /// matches itself and all its subdomains, so `"example.com"` matches
+    /// `"www.example.com"` but not `"notexample.com"`. Tabs without a host
+    /// (for example `about:` pages) never match.
+    pub domain_allowlist: Option<Vec<String>>,
+    /// Exclude tabs whose host matches one of these domains, using the same
+    /// rules as `domain_allowlist`. Tabs that match the allowlist are always
+    /// included.
+    pub domain_blocklist: Option<Vec<String>>,
+    /// Only include tabs that were pinned in Firefox. Windows without pinned
+    /// tabs are left out.
+    pub pinned_only: bool,
+    /// Only include tabs whose title or URL contains this text, ignoring case.
+    /// `*` matches any text and `?` matches any single character. The
+    /// ancestors of matching tabs are kept so that the tree of tabs stays
+    /// intact.
+    pub title_url_filter: Option<String>,
+    /// Only include the first tab with each URL, in the order tabs appear in
+    /// their tree. Tabs nested below a removed tab are kept.
+    pub deduplicate_links: bool,
+    /// Remove tabs whose URL was already included from an earlier window, not
+    /// just from the same window. Ignored if `deduplicate_links` isn't set.
+    pub deduplicate_globally: bool,
+    /// Sort windows by their tab URLs and name so that exporting the same
+    /// session twice gives identical files (as long as no export ID is
+    /// included). Open windows are still placed before closed windows.
+    pub reproducible: bool,
 }
 impl Default for GenerateOptions {
     fn default() -> Self {
@@ -108,15 +212,49 @@ impl Default for GenerateOptions {
             table_of_content: true,
             tree_style_tab_trees: true,
             sidebery_trees: true,
+            flat_links: false,
+            markdown_style: MarkdownStyle::List,
+            mark_closed_windows: false,
+            markdown_toc_links: false,
+            mark_collapsed_tabs: false,
+            annotate_languages: false,
+            include_timestamps: false,
+            include_container: false,
+            typst_font: None,
+            pdf_outline: false,
+            typst_template: None,
+            window_separator: None,
+            custom_page_break: String::new(),
+            include_closed_tabs: false,
+            include_counts: false,
+            html_window_filter: false,
+            duplicate_names: DuplicateNameStrategy::Index,
+            tab_sort: TabSort::None,
+            group_by: GroupBy::Window,
+            domain_allowlist: None,
+            domain_blocklist: None,
+            pinned_only: false,
+            title_url_filter: None,
+            deduplicate_links: false,
+            deduplicate_globally: false,
+            reproducible: false,
         }
     }
 }
 
+/// Declare the [`OutputFormat`] enum. Formats before the `;` are implemented
+/// by [`firefox_session_data`] and formats after it are implemented by this
+/// crate and need a description.
 macro_rules! declare_formats {
     ($(
         $(#[default $(@ $default_:ident)?])?
         $(#[doc = $($attr:tt)*])*
         $format:ident = $as_str:literal
+    ),* $(,)?
+    ;
+    $(
+        $(#[doc = $($custom_attr:tt)*])*
+        $custom_format:ident = $custom_as_str:literal => $description:literal
     ),* $(,)?) => {
         #[TauriSerialize]
         #[TauriDeserialize]
@@ -128,24 +266,38 @@ macro_rules! declare_formats {
                 $(#[doc = $($attr)*])*
                 $format,
             )*
+            $(
+                $(#[doc = $($custom_attr)*])*
+                $custom_format,
+            )*
         }
         impl OutputFormat {
             pub fn all() -> &'static [Self] {
-                &[$(Self::$format,)*]
+                &[$(Self::$format,)* $(Self::$custom_format,)*]
             }
             pub fn as_str(self) -> &'static str {
                 match self {
                     $(Self::$format => $as_str,)*
+                    $(Self::$custom_format => $custom_as_str,)*
                 }
             }
-        }
-        #[cfg(any(feature = "tauri-export", feature = "dioxus-export", feature = "wasm-standalone"))]
-        impl From<OutputFormat> for firefox_session_data::to_links::ttl_formats::FormatInfo {
-            fn from(format: OutputFormat) -> Self {
-                match format {
+            /// Description of a format that is implemented by this crate, `None`
+            /// for formats implemented by [`firefox_session_data`].
+            pub fn custom_description(self) -> Option<&'static str> {
+                match self {
+                    $(Self::$custom_format => Some($description),)*
+                    _ => None,
+                }
+            }
+            /// Info about a format implemented by [`firefox_session_data`],
+            /// `None` for formats implemented by this crate.
+            #[cfg(any(feature = "tauri-export", feature = "dioxus-export", feature = "wasm-standalone"))]
+            pub fn format_info(self) -> Option<firefox_session_data::to_links::ttl_formats::FormatInfo> {
+                match self {
                     $(
-                        OutputFormat::$format => Self::$format,
+                        Self::$format => Some(firefox_session_data::to_links::ttl_formats::FormatInfo::$format),
                     )*
+                    $(Self::$custom_format => None,)*
                 }
             }
         }
@@ -183,15 +335,350 @@ declare_formats!(
     PDF_WK_HTML_LINKED = "pdf-wk-html-linked",
 
     PDF_CHROMIUM_OXIDE = "pdf-chromium-oxide",
+    ;
+    SITEMAP = "sitemap" => "XML sitemap with the URL of every web page (http and https) tab, without duplicates",
+    JSON = "json" => "JSON document with every window and its tree of tabs, for processing with scripts",
+    CSV = "csv" => "CSV spreadsheet with one row per tab (window, window name, open or closed, tree depth, title and URL)",
+    OPML = "opml" => "OPML outline with every window and its tree of tabs, for importing into read-later and RSS tools",
 );
+impl OutputFormat {
+    /// The output is generated from a Typst document.
+    pub fn is_typst(self) -> bool {
+        matches!(self, Self::TYPST | Self::PDF_TYPST)
+    }
+    /// The output is a PDF document.
+    pub fn is_pdf(self) -> bool {
+        self.as_str().starts_with("pdf")
+    }
+}
 
+/// Where the tree of tabs in a window is read from.
 #[TauriSerialize]
 #[TauriDeserialize]
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeSource {
+    /// Ignore any tree data.
+    Flat,
+    /// The Sidebery extension.
+    Sidebery,
+    /// The Tree Style Tab WebExtension.
+    TstWebExtension,
+    /// The legacy (XUL) version of Tree Style Tab.
+    TstLegacy,
+}
+impl TreeSource {
+    pub fn all() -> &'static [Self] {
+        &[Self::Flat, Self::Sidebery, Self::TstWebExtension, Self::TstLegacy]
+    }
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Flat => "No tree",
+            Self::Sidebery => "Sidebery",
+            Self::TstWebExtension => "Tree Style Tab",
+            Self::TstLegacy => "Tree Style Tab (legacy)",
+        }
+    }
+}
+
+/// Domains that are remembered between restarts and used for
+/// [`GenerateOptions::domain_allowlist`] and
+/// [`GenerateOptions::domain_blocklist`].
+#[TauriSerialize]
+#[TauriDeserialize]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DomainFilters {
+    pub allowlist: Vec<String>,
+    pub blocklist: Vec<String>,
+}
+
+/// How much of a file [`FileManagementCommands::load_data`] has read.
+#[TauriSerialize]
+#[TauriDeserialize]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoadProgress {
+    pub bytes_read: u64,
+    /// Size of the file when reading started. Increased if the file grows
+    /// while it is being read.
+    pub total_bytes: u64,
+}
+
+/// The parts of the UI that are remembered between restarts with
+/// [`StatelessCommands::save_session_state`]. Loaded data and previews aren't
+/// included, instead the file at `loaded_path` is loaded again. The save path
+/// is remembered by [`FileManagementCommands::set_save_path`].
+#[TauriSerialize]
+#[TauriDeserialize]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SavedUiState {
+    /// Path of the file whose tabs were shown, empty if no file was loaded.
+    pub loaded_path: String,
+    pub output_options: OutputOptions,
+    /// The domain filters and window indexes aren't used, see
+    /// [`StatelessCommands::load_domain_filters`] and the selected window
+    /// fields.
+    pub generate_options: GenerateOptions,
+    pub selected_open_windows: Vec<u32>,
+    pub selected_closed_windows: Vec<u32>,
+    /// Windows were selected with checkboxes instead of a list.
+    pub window_checkbox_list: bool,
+    pub theme: Theme,
+}
+
+/// Color theme of the user interface.
+#[TauriSerialize]
+#[TauriDeserialize]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Theme {
+    /// Follow the system's light or dark mode preference.
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+impl Theme {
+    pub fn all() -> &'static [Self] {
+        &[Self::System, Self::Light, Self::Dark]
+    }
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::System => "System",
+            Self::Light => "Light",
+            Self::Dark => "Dark",
+        }
+    }
+}
+
+/// How windows with the same name are told apart in the window list and in
+/// generated headings.
+#[TauriSerialize]
+#[TauriDeserialize]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateNameStrategy {
+    /// Append a number, for example `"Work (2)"`.
+    #[default]
+    Index,
+    /// Append the number of tabs, for example `"Work (12 tabs)"`.
+    TabCount,
+    /// Append the domain of the window's first tab, for example
+    /// `"Work (github.com)"`.
+    FirstDomain,
+}
+impl DuplicateNameStrategy {
+    pub fn all() -> &'static [Self] {
+        &[Self::Index, Self::TabCount, Self::FirstDomain]
+    }
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Index => "Number",
+            Self::TabCount => "Tab count",
+            Self::FirstDomain => "First domain",
+        }
+    }
+}
+
+/// Order of the tabs inside each window.
+#[TauriSerialize]
+#[TauriDeserialize]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TabSort {
+    /// Keep the order the tabs had in the browser.
+    #[default]
+    None,
+    /// Sort by title, ignoring case. Tabs without a title use their URL.
+    Title,
+    Url,
+    /// The most recently used tabs first.
+    LastAccessed,
+}
+impl TabSort {
+    pub fn all() -> &'static [Self] {
+        &[Self::None, Self::Title, Self::Url, Self::LastAccessed]
+    }
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "Browser order",
+            Self::Title => "Title",
+            Self::Url => "URL",
+            Self::LastAccessed => "Last accessed",
+        }
+    }
+}
+
+/// How tabs are grouped into sections in the output.
+#[TauriSerialize]
+#[TauriDeserialize]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GroupBy {
+    /// One section per browser window.
+    #[default]
+    Window,
+    /// One section per website host with the tabs of all selected windows,
+    /// in the order the hosts first appear. Trees of tabs aren't kept.
+    Domain,
+}
+impl GroupBy {
+    pub fn all() -> &'static [Self] {
+        &[Self::Window, Self::Domain]
+    }
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Window => "Window",
+            Self::Domain => "Domain",
+        }
+    }
+}
+
+/// Layout of the links in saved documents. Only one layout can be used at a
+/// time, all of them except `List` and `Table` are ignored by the preview.
+#[TauriSerialize]
+#[TauriDeserialize]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MarkdownStyle {
+    /// A bulleted list that is indented to show trees of tabs.
+    #[default]
+    List,
+    /// A Markdown table with one row per tab. Used for the preview and when
+    /// saving with the Markdown format.
+    Table,
+    /// Place each window's links inside a collapsible `<details>` block when
+    /// saving with the Markdown format.
+    Details,
+    /// Write each link as a GitHub task list item (`- [ ] [title](url)`) when
+    /// saving with the Markdown format.
+    TaskList,
+    /// Write each window of HTML documents as a collapsible `<details>` block
+    /// and make tabs with children in a tree of tabs collapsible as well.
+    HtmlDetails,
+}
+impl MarkdownStyle {
+    pub fn all() -> &'static [Self] {
+        &[Self::List, Self::Table, Self::Details, Self::TaskList, Self::HtmlDetails]
+    }
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::List => "List",
+            Self::Table => "Table",
+            Self::Details => "Collapsible windows",
+            Self::TaskList => "Task list",
+            Self::HtmlDetails => "Collapsible HTML windows",
+        }
+    }
+}
+
+/// Character encoding used when saving text formats.
+#[TauriSerialize]
+#[TauriDeserialize]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextEncoding {
+    /// UTF-8 exactly as generated.
+    #[default]
+    Utf8,
+    /// UTF-8 that starts with a byte order mark.
+    Utf8Bom,
+    /// UTF-16 little-endian that starts with a byte order mark.
+    Utf16Le,
+    /// Windows-1252, characters that can't be represented are replaced with
+    /// `?`.
+    Windows1252,
+}
+impl TextEncoding {
+    pub fn all() -> &'static [Self] {
+        &[Self::Utf8, Self::Utf8Bom, Self::Utf16Le, Self::Windows1252]
+    }
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Utf8 => "UTF-8",
+            Self::Utf8Bom => "UTF-8 with BOM",
+            Self::Utf16Le => "UTF-16 LE",
+            Self::Windows1252 => "Windows-1252",
+        }
+    }
+}
+
+/// Paper size of generated PDF documents.
+#[TauriSerialize]
+#[TauriDeserialize]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PageSize {
+    #[default]
+    A4,
+    Letter,
+    Legal,
+    A3,
+}
+impl PageSize {
+    pub fn all() -> &'static [Self] {
+        &[Self::A4, Self::Letter, Self::Legal, Self::A3]
+    }
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::A4 => "A4",
+            Self::Letter => "Letter",
+            Self::Legal => "Legal",
+            Self::A3 => "A3",
+        }
+    }
+}
+
+/// Wall-clock time in milliseconds that each step of processing a
+/// sessionstore file took. `None` if the step wasn't performed or wasn't
+/// timed.
+#[TauriSerialize]
+#[TauriDeserialize]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StepTimings {
+    pub load_ms: Option<u64>,
+    pub decompress_ms: Option<u64>,
+    pub parse_ms: Option<u64>,
+    /// Time it took to generate the latest preview.
+    pub generate_ms: Option<u64>,
+}
+
+#[TauriSerialize]
+#[TauriDeserialize]
+#[derive(Debug, Clone, PartialEq)]
 pub struct OutputOptions {
     pub format: OutputFormat,
     pub overwrite: bool,
     pub create_folder: bool,
+    /// Give up on saving if generating the output takes longer than this many
+    /// seconds. Ignored on the WebAssembly target.
+    pub generation_timeout_secs: Option<u64>,
+    /// Embed a unique ID in the saved document so that it can be correlated
+    /// with logs.
+    pub include_export_id: bool,
+    /// Also save the exported windows as a sessionstore file next to HTML
+    /// output and link to it, so that the tabs can be restored in Firefox.
+    /// Ignored for other formats and on the WebAssembly target.
+    pub restore_bundle: bool,
+    /// Character encoding of saved text and Markdown files. Other formats
+    /// always use UTF-8 or are binary.
+    pub text_encoding: TextEncoding,
+    /// Paper size of PDF documents. Currently only used for PDFs that are
+    /// created with Typst.
+    pub page_size: PageSize,
+    /// Rotate the pages of PDF documents so that they are wider than they are
+    /// tall. Currently only used for PDFs that are created with Typst.
+    pub landscape: bool,
+    /// Space between the content and the edges of each page in PDF documents,
+    /// in millimeters. Currently only used for PDFs that are created with
+    /// Typst.
+    pub margins_mm: f32,
+    /// How many times rendering a PDF with a headless Chromium browser is
+    /// retried if it fails, for example since the browser couldn't be launched
+    /// in time. Only used for [`OutputFormat::PDF_CHROMIUM_OXIDE`].
+    pub pdf_render_retries: u32,
+    /// Save each selected window to its own file. A number is added to the
+    /// name of the save file for each window, for example `links-window-1.pdf`.
+    pub split_per_window: bool,
+    /// Open the saved file with the default program once it has been saved,
+    /// or the folder it was saved in if several files were saved. Ignored on
+    /// the WebAssembly target where the file is downloaded instead.
+    pub reveal_after_save: bool,
+    /// Save the preview exactly as it is shown, as a text file, instead of
+    /// generating a document in `format`. The frontend passes the preview to
+    /// [`FileManagementCommands::save_text`] when this is set.
+    pub save_preview: bool,
 }
 impl Default for OutputOptions {
     fn default() -> Self {
@@ -199,10 +686,170 @@ impl Default for OutputOptions {
             format: Default::default(),
             overwrite: false,
             create_folder: false,
+            generation_timeout_secs: None,
+            include_export_id: false,
+            restore_bundle: false,
+            text_encoding: TextEncoding::Utf8,
+            page_size: PageSize::A4,
+            landscape: false,
+            // Same as Typst's default margins for A4 pages:
+            margins_mm: 25.0,
+            pdf_render_retries: 2,
+            split_per_window: false,
+            reveal_after_save: false,
+            save_preview: false,
         }
     }
 }
 
+/// Result of [`FileManagementCommands::export_combined_urls`].
+#[TauriSerialize]
+#[TauriDeserialize]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CombinedUrlsSummary {
+    /// Number of URLs that were written.
+    pub unique_urls: u64,
+    /// Number of tabs that were skipped since their URL was already written.
+    pub duplicates_removed: u64,
+}
+
+/// The result of [`FileManagementCommands::save_links`] and
+/// [`FileManagementCommands::save_text`].
+#[TauriSerialize]
+#[TauriDeserialize]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SavedLinks {
+    /// Number of files that were written, more than one if
+    /// [`OutputOptions::split_per_window`] is set.
+    pub file_count: u32,
+    /// Paths of the files that were written. Empty on the WebAssembly target
+    /// where the browser chooses where files are saved.
+    pub paths: Vec<String>,
+    /// Number of characters that were replaced since they can't be
+    /// represented in [`OutputOptions::text_encoding`].
+    pub replaced_characters: u64,
+    /// Why the saved output couldn't be opened after it was saved, see
+    /// [`OutputOptions::reveal_after_save`]. The output was still saved.
+    pub reveal_error: Option<String>,
+}
+
+/// The result of [`FileManagementCommands::load_and_export`].
+#[TauriSerialize]
+#[TauriDeserialize]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SaveSummary {
+    /// Number of windows that were included in the saved file.
+    pub window_count: u64,
+    /// Number of tabs that were included in the saved file.
+    pub tab_count: u64,
+    pub timings: StepTimings,
+}
+
+/// The result of exporting a single profile with
+/// [`FileManagementCommands::export_all_profiles`].
+#[TauriSerialize]
+#[TauriDeserialize]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileExport {
+    pub profile_name: String,
+    /// The session file that was exported, `None` if the profile had no
+    /// session file.
+    pub session_file: Option<String>,
+    /// `None` if the export failed.
+    pub summary: Option<SaveSummary>,
+    /// Why the export failed.
+    pub error: Option<String>,
+}
+
+/// A tab and the window it belongs to.
+#[TauriSerialize]
+#[TauriDeserialize]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TabEntry {
+    /// Index among open or closed windows, as returned by
+    /// [`FileManagementCommands::get_groups_from_session`].
+    pub window_index: u32,
+    pub is_closed: bool,
+    pub window_name: String,
+    pub title: String,
+    pub url: String,
+    /// When the tab was last active, in milliseconds since the Unix epoch.
+    pub last_accessed: Option<u64>,
+}
+
+/// Problems with a save path, see
+/// [`FileManagementCommands::validate_save_path`].
+#[TauriSerialize]
+#[TauriDeserialize]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SavePathStatus {
+    /// The folder that the file would be saved in exists.
+    pub folder_exists: bool,
+    /// A file already exists at the path. Paths without an extension are
+    /// checked with the format's extension, since that is added when saving.
+    pub file_exists: bool,
+    /// The extension that the selected format uses if the path has a
+    /// different extension.
+    pub expected_extension: Option<String>,
+}
+
+/// A tab in a window's tree of tabs, see
+/// [`FileManagementCommands::get_tabs_for_group`].
+#[TauriSerialize]
+#[TauriDeserialize]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TabInfo {
+    pub title: String,
+    pub url: String,
+    /// Lowercase host of the URL, for example `"www.mozilla.org"`. `None` if
+    /// it isn't a `http` or `https` URL.
+    pub host: Option<String>,
+    /// How deeply nested the tab is inside a tree of tabs (`0` for top level
+    /// tabs).
+    pub depth: u32,
+}
+
+/// Number of windows and tabs that would be included in the output, see
+/// [`FileManagementCommands::count_tabs`].
+#[TauriSerialize]
+#[TauriDeserialize]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TabCounts {
+    pub open_windows: u32,
+    pub open_tabs: u32,
+    pub closed_windows: u32,
+    pub closed_tabs: u32,
+}
+
+/// Statistics about all windows in a parsed session, returned by
+/// [`FileManagementCommands::get_session_stats`].
+#[TauriSerialize]
+#[TauriDeserialize]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SessionStats {
+    pub open_windows: u32,
+    pub closed_windows: u32,
+    /// Tabs in both open and closed windows.
+    pub total_tabs: u32,
+    /// Number of different hosts among tabs with http or https URLs.
+    pub unique_domains: u32,
+    pub pinned_tabs: u32,
+    /// Tabs that show a page in Firefox's reader view (`about:reader` URLs).
+    pub reader_mode_tabs: u32,
+}
+
+#[TauriSerialize]
+#[TauriDeserialize]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeltaExportSummary {
+    /// Number of tabs whose URL wasn't included in the previous export.
+    pub new_tabs: u64,
+    /// Number of tabs in the selected windows.
+    pub total_tabs: u64,
+    /// The file had never been exported before so all tabs were new.
+    pub first_export: bool,
+}
+
 #[TauriSerialize]
 #[TauriDeserialize]
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -210,6 +857,9 @@ pub struct FirefoxProfileInfo {
     pub name: String,
     pub file_path: String,
     pub modified_at: Option<u64>,
+    /// The profile is used by default according to Firefox's
+    /// `profiles.ini`.
+    pub is_default: bool,
     pub session_files: Vec<FoundSessionFile>,
 }
 
@@ -232,6 +882,32 @@ pub trait StatelessCommands {
     async fn format_descriptions(&self) -> Vec<(OutputFormat, String)>;
 
     async fn find_firefox_profiles(&self) -> Result<Vec<FirefoxProfileInfo>, String>;
+
+    /// Names of font families that can be used by Typst based formats.
+    async fn typst_available_fonts(&self) -> Vec<String>;
+
+    /// Load the domain filters that were saved with
+    /// [`StatelessCommands::save_domain_filters`].
+    async fn load_domain_filters(&self) -> Result<DomainFilters, String>;
+
+    /// Remember domain filters between restarts.
+    async fn save_domain_filters(&self, filters: DomainFilters) -> Result<(), String>;
+
+    /// Remember the state of the UI so that it can be restored the next time
+    /// the program is started. The state is only written once it has stopped
+    /// changing for a moment, earlier calls then return without writing.
+    async fn save_session_state(&self, state: SavedUiState) -> Result<(), String>;
+
+    /// The UI state that was saved with
+    /// [`StatelessCommands::save_session_state`], `None` if nothing was
+    /// saved. Options that weren't saved (for example by an older version of
+    /// the program) keep their default values.
+    async fn restore_session_state(&self) -> Result<Option<SavedUiState>, String>;
+
+    /// Register this program as the handler for sessionstore files
+    /// (`.jsonlz4` and `.baklz4`) for the current user so that they can be
+    /// opened by double-clicking them. Only supported on Windows.
+    async fn register_file_associations(&self) -> Result<(), String>;
 }
 
 #[tauri_commands::tauri_commands(wasm_client_impl_for = WasmClient)]
@@ -241,6 +917,9 @@ pub trait FileManagementCommands {
     type State<'a>;
 
     async fn get_info_for_slot(&self, state: Self::State<'_>, slot: FileSlot) -> FileInfo;
+    /// Info for all file slots, first [`FileSlot::New`] and then
+    /// [`FileSlot::Current`].
+    async fn all_file_infos(&self, state: Self::State<'_>) -> Vec<FileInfo>;
     async fn get_info_for_path_id(&self, state: Self::State<'_>, id: PathId) -> Option<FileInfo>;
     async fn get_info_for_data_id(&self, state: Self::State<'_>, id: DataId) -> Option<FileInfo>;
 
@@ -251,11 +930,33 @@ pub trait FileManagementCommands {
         file_path: String,
     ) -> PathId;
 
+    /// Change where links are saved. The path is remembered between restarts
+    /// once it hasn't changed for a moment, so that a path that is being typed
+    /// isn't written to disk on every key press.
     async fn set_save_path(&self, state: Self::State<'_>, file_path: String);
     async fn get_save_path(&self, state: Self::State<'_>) -> Option<String>;
+    /// Check a save path for problems before links are saved to it. Paths are
+    /// never used on the WebAssembly target so they are always fine there.
+    async fn validate_save_path(
+        &self,
+        state: Self::State<'_>,
+        file_path: String,
+        format: OutputFormat,
+    ) -> SavePathStatus;
+
+    /// Files that were recently picked with [`FilePromptCommands::file_open`]
+    /// or read with [`FileManagementCommands::load_data`], the most recent
+    /// first. Paths that are only typed aren't remembered until they are
+    /// loaded.
+    async fn get_recent_paths(&self, state: Self::State<'_>) -> Vec<String>;
+    /// Forget all recent files.
+    async fn clear_recent_paths(&self, state: Self::State<'_>) -> Result<(), String>;
 
     async fn forget_data(&self, state: Self::State<'_>, id: DataId);
     async fn forget_path(&self, state: Self::State<'_>, id: PathId);
+    /// Forget the paths and data of both file slots so that all loaded
+    /// sessions are freed, leaving them as [`FileStatus::Empty`].
+    async fn forget_all(&self, state: Self::State<'_>);
 
     /// Commit the data loaded into the [`FileSlot::New`] into [`FileSlot::Current`].
     async fn commit_new_file(&self, state: Self::State<'_>);
@@ -265,17 +966,39 @@ pub trait FileManagementCommands {
     async fn set_data(&self, state: Self::State<'_>, id: PathId, data: Vec<u8>)  -> Result<DataId, String>;
     /// Read data from the selected file.
     async fn load_data(&self, state: Self::State<'_>, id: PathId) -> Result<DataId, String>;
-    /// Decompress loaded data.
+    /// Progress of [`FileManagementCommands::load_data`] for a file, can be
+    /// polled while the data is being read. `None` if the path id has expired.
+    /// Data provided with [`FileManagementCommands::set_data`] is already in
+    /// memory so it has no progress.
+    async fn load_progress(&self, state: Self::State<'_>, id: PathId) -> Option<LoadProgress>;
+    /// Decompress loaded data. Firefox's LZ4 format, gzip and zstd are
+    /// supported.
     async fn decompress_data(&self, state: Self::State<'_>, id: DataId) -> Result<(), String>;
     /// Parse uncompressed data as JSON.
     async fn parse_session_data(&self, state: Self::State<'_>, id: DataId) -> Result<(), String>;
+    /// Parse JSON data that was cut off, keeping the windows that are
+    /// complete. Only meant to be used after `parse_session_data` failed since
+    /// windows might be lost, which is described by
+    /// [`FileInfo::parse_warning`].
+    async fn parse_session_data_lenient(
+        &self,
+        state: Self::State<'_>,
+        id: DataId,
+    ) -> Result<(), String>;
+    /// The uncompressed JSON text of the data. Parsing frees the text so
+    /// afterwards it is read again from the file the data was loaded from,
+    /// which fails if that file has changed.
+    async fn get_raw_json(&self, state: Self::State<'_>, id: DataId) -> Result<String, String>;
 
     /// Get info about browser windows/groups from the parsed JSON data.
+    /// Windows with the same name are told apart using `duplicate_names`, just
+    /// like in generated output.
     async fn get_groups_from_session(
         &self,
         state: Self::State<'_>,
         id: DataId,
         sort_groups: bool,
+        duplicate_names: DuplicateNameStrategy,
     ) -> Result<AllTabGroups, String>;
 
     /// Generate text with links from JSON data.
@@ -293,7 +1016,203 @@ pub trait FileManagementCommands {
         id: DataId,
         generate_options: GenerateOptions,
         output_options: OutputOptions,
+    ) -> Result<SavedLinks, String>;
+
+    /// Write text to the save file as a `.txt` file, using the text encoding
+    /// and file options of `output_options`. Used to save the preview exactly
+    /// as it is shown.
+    async fn save_text(
+        &self,
+        state: Self::State<'_>,
+        text: String,
+        output_options: OutputOptions,
+    ) -> Result<SavedLinks, String>;
+
+    /// Save links again using the same data and [`GenerateOptions`] as the
+    /// latest call to [`FileManagementCommands::save_links`] but with
+    /// different output options, for example to save another format.
+    ///
+    /// This only replays the save: nothing from the previous save is cached,
+    /// so the tabs are selected and the links are generated again from the
+    /// parsed session.
+    async fn save_links_again(
+        &self,
+        state: Self::State<'_>,
+        output_options: OutputOptions,
+    ) -> Result<SavedLinks, String>;
+
+    /// Load, decompress and parse the sessionstore file at `file_path` and
+    /// then save links to `save_path`, all in one call. The loaded data is
+    /// kept separate from the file slots that are shown in the UI.
+    async fn load_and_export(
+        &self,
+        state: Self::State<'_>,
+        file_path: String,
+        generate_options: GenerateOptions,
+        output_options: OutputOptions,
+        save_path: String,
+    ) -> Result<SaveSummary, String>;
+
+    /// Export the newest session file of every Firefox profile that
+    /// [`StatelessCommands::find_firefox_profiles`] finds to its own file in
+    /// `target_folder`, named after the profile. A profile that fails to
+    /// export doesn't stop the other profiles from being exported.
+    async fn export_all_profiles(
+        &self,
+        state: Self::State<'_>,
+        generate_options: GenerateOptions,
+        output_options: OutputOptions,
+        target_folder: String,
+    ) -> Result<Vec<ProfileExport>, String>;
+
+    /// Generate links for a single window once for each [`TreeSource`] so
+    /// that the differences between them can be compared.
+    async fn compare_tree_sources(
+        &self,
+        state: Self::State<'_>,
+        id: DataId,
+        is_closed: bool,
+        group_index: u32,
+    ) -> Result<Vec<(TreeSource, String)>, String>;
+
+    /// The tabs of a single window in the order they appear in its tree of
+    /// tabs.
+    async fn get_tabs_for_group(
+        &self,
+        state: Self::State<'_>,
+        id: DataId,
+        is_closed: bool,
+        group_index: u32,
+    ) -> Result<Vec<TabInfo>, String>;
+
+    /// The `limit` tabs that were least recently accessed, oldest first. Tabs
+    /// without a last accessed time are sorted last.
+    async fn oldest_tabs(
+        &self,
+        state: Self::State<'_>,
+        id: DataId,
+        limit: u32,
+    ) -> Result<Vec<TabEntry>, String>;
+
+    /// Count the windows and tabs that would be included in the output when
+    /// links are generated with the specified options.
+    async fn count_tabs(
+        &self,
+        state: Self::State<'_>,
+        id: DataId,
+        generate_options: GenerateOptions,
+    ) -> Result<TabCounts, String>;
+
+    /// Statistics about all open and closed windows in a parsed session,
+    /// ignoring which windows are selected.
+    async fn get_session_stats(
+        &self,
+        state: Self::State<'_>,
+        id: DataId,
+    ) -> Result<SessionStats, String>;
+
+    /// A warning if another sessionstore file in the same Firefox profile as
+    /// the loaded file was modified more recently, since then the loaded
+    /// session is likely outdated. For example when `previous.jsonlz4` was
+    /// loaded but `recovery.jsonlz4` has newer data.
+    async fn newer_session_file_warning(
+        &self,
+        state: Self::State<'_>,
+        id: DataId,
+    ) -> Result<Option<String>, String>;
+
+    /// The extensions that `moz-extension://` tabs belong to. Names are read
+    /// from the Firefox profile that the file was loaded from when possible,
+    /// otherwise only the extension's UUID is returned.
+    async fn referenced_extensions(
+        &self,
+        state: Self::State<'_>,
+        id: DataId,
+    ) -> Result<Vec<String>, String>;
+
+    /// Enable or disable measuring how long each step of processing a
+    /// sessionstore file takes. Disabled by default.
+    async fn set_record_timings(&self, state: Self::State<'_>, enabled: bool);
+
+    /// The largest size in bytes that loaded data may have once it is
+    /// decompressed. Larger data fails to decompress with an error instead of
+    /// using up all memory. Defaults to 1 GiB.
+    async fn get_max_decompressed_size(&self, state: Self::State<'_>) -> u64;
+    /// Change the limit returned by
+    /// [`FileManagementCommands::get_max_decompressed_size`]. The limit is
+    /// remembered between restarts.
+    async fn set_max_decompressed_size(
+        &self,
+        state: Self::State<'_>,
+        max_bytes: u64,
     ) -> Result<(), String>;
+
+    /// Get the timings recorded for a file's data, see
+    /// [`FileManagementCommands::set_record_timings`].
+    async fn get_timings(&self, state: Self::State<'_>, id: DataId) -> Option<StepTimings>;
+
+    /// Generate a document with links from JSON data and return its bytes
+    /// instead of writing them to the save file. Used to preview binary
+    /// formats such as PDF.
+    async fn generate_output(
+        &self,
+        state: Self::State<'_>,
+        id: DataId,
+        generate_options: GenerateOptions,
+        format: OutputFormat,
+    ) -> Result<Vec<u8>, String>;
+
+    /// Render the first page of the PDF that would be saved with
+    /// `output_options` as a PNG image, used to preview it. Only supported
+    /// for [`OutputFormat::PDF_TYPST`] and not on the WebAssembly target.
+    async fn render_pdf_preview(
+        &self,
+        state: Self::State<'_>,
+        id: DataId,
+        generate_options: GenerateOptions,
+        output_options: OutputOptions,
+    ) -> Result<Vec<u8>, String>;
+
+    /// Generate a document with links from JSON data and return it as a
+    /// base64 encoded `data:` URL that can be opened in a new browser tab.
+    /// Fails if the URL would be too large for browsers to open.
+    async fn generate_data_url(
+        &self,
+        state: Self::State<'_>,
+        id: DataId,
+        generate_options: GenerateOptions,
+        output_options: OutputOptions,
+    ) -> Result<String, String>;
+
+    /// Check if the font selected by [`GenerateOptions::typst_font`] can
+    /// display all tab titles. Returns a warning if some characters are
+    /// missing.
+    async fn typst_font_warning(
+        &self,
+        state: Self::State<'_>,
+        id: DataId,
+        generate_options: GenerateOptions,
+    ) -> Result<Option<String>, String>;
+
+    /// Write the URLs of all tabs in both the current and the new file to the
+    /// save file, with one URL per line and without any duplicates.
+    async fn export_combined_urls(
+        &self,
+        state: Self::State<'_>,
+        output_options: OutputOptions,
+    ) -> Result<CombinedUrlsSummary, String>;
+
+    /// Save only the tabs whose URLs weren't part of the previous export of a
+    /// file with the same content, then remember the URLs of all selected
+    /// tabs for the next export. Every tab is new the first time a file is
+    /// exported.
+    async fn export_new_since_last(
+        &self,
+        state: Self::State<'_>,
+        id: DataId,
+        generate_options: GenerateOptions,
+        output_options: OutputOptions,
+    ) -> Result<DeltaExportSummary, String>;
 }
 
 #[tauri_commands::tauri_commands(wasm_client_impl_for = WasmClient)]
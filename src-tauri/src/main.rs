@@ -2,7 +2,11 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use host_commands::*;
-use std::sync::Mutex;
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
 
 mod commands {
     use host_commands::*;
@@ -57,6 +61,7 @@ mod commands {
         type State<'a> = tauri::State<'a, Mutex<host::UiState>>;
 
         async fn get_info_for_slot(&self, state: Self::State<'_>, slot: FileSlot) -> FileInfo {}
+        async fn all_file_infos(&self, state: Self::State<'_>) -> Vec<FileInfo> {}
         async fn get_info_for_path_id(
             &self,
             state: Self::State<'_>,
@@ -79,14 +84,25 @@ mod commands {
         }
         async fn set_save_path(&self, state: Self::State<'_>, file_path: String) {}
         async fn get_save_path(&self, state: Self::State<'_>) -> Option<String> {}
+        async fn validate_save_path(
+            &self,
+            state: Self::State<'_>,
+            file_path: String,
+            format: OutputFormat,
+        ) -> SavePathStatus {
+        }
+        async fn get_recent_paths(&self, state: Self::State<'_>) -> Vec<String> {}
+        async fn clear_recent_paths(&self, state: Self::State<'_>) -> Result<(), String> {}
 
         async fn forget_data(&self, state: Self::State<'_>, id: DataId) {}
         async fn forget_path(&self, state: Self::State<'_>, id: PathId) {}
+        async fn forget_all(&self, state: Self::State<'_>) {}
 
         async fn commit_new_file(&self, state: Self::State<'_>) {}
 
         async fn set_data(&self, state: Self::State<'_>, id: PathId, data: Vec<u8>)  -> Result<DataId, String> {}
         async fn load_data(&self, state: Self::State<'_>, id: PathId) -> Result<DataId, String> {}
+        async fn load_progress(&self, state: Self::State<'_>, id: PathId) -> Option<LoadProgress> {}
         async fn decompress_data(&self, state: Self::State<'_>, id: DataId) -> Result<(), String> {}
         async fn parse_session_data(
             &self,
@@ -94,12 +110,20 @@ mod commands {
             id: DataId,
         ) -> Result<(), String> {
         }
+        async fn parse_session_data_lenient(
+            &self,
+            state: Self::State<'_>,
+            id: DataId,
+        ) -> Result<(), String> {
+        }
+        async fn get_raw_json(&self, state: Self::State<'_>, id: DataId) -> Result<String, String> {}
 
         async fn get_groups_from_session(
             &self,
             state: Self::State<'_>,
             id: DataId,
             sort_groups: bool,
+            duplicate_names: DuplicateNameStrategy,
         ) -> Result<AllTabGroups, String> {
         }
         async fn to_text_links(
@@ -115,8 +139,140 @@ mod commands {
             id: DataId,
             generate_options: GenerateOptions,
             output_options: OutputOptions,
+        ) -> Result<SavedLinks, String> {
+        }
+        async fn compare_tree_sources(
+            &self,
+            state: Self::State<'_>,
+            id: DataId,
+            is_closed: bool,
+            group_index: u32,
+        ) -> Result<Vec<(TreeSource, String)>, String> {
+        }
+        async fn get_tabs_for_group(
+            &self,
+            state: Self::State<'_>,
+            id: DataId,
+            is_closed: bool,
+            group_index: u32,
+        ) -> Result<Vec<TabInfo>, String> {
+        }
+        async fn save_text(
+            &self,
+            state: Self::State<'_>,
+            text: String,
+            output_options: OutputOptions,
+        ) -> Result<SavedLinks, String> {
+        }
+        async fn save_links_again(
+            &self,
+            state: Self::State<'_>,
+            output_options: OutputOptions,
+        ) -> Result<SavedLinks, String> {
+        }
+        async fn load_and_export(
+            &self,
+            state: Self::State<'_>,
+            file_path: String,
+            generate_options: GenerateOptions,
+            output_options: OutputOptions,
+            save_path: String,
+        ) -> Result<SaveSummary, String> {
+        }
+        async fn export_all_profiles(
+            &self,
+            state: Self::State<'_>,
+            generate_options: GenerateOptions,
+            output_options: OutputOptions,
+            target_folder: String,
+        ) -> Result<Vec<ProfileExport>, String> {
+        }
+        async fn oldest_tabs(
+            &self,
+            state: Self::State<'_>,
+            id: DataId,
+            limit: u32,
+        ) -> Result<Vec<TabEntry>, String> {
+        }
+        async fn count_tabs(
+            &self,
+            state: Self::State<'_>,
+            id: DataId,
+            generate_options: GenerateOptions,
+        ) -> Result<TabCounts, String> {
+        }
+        async fn newer_session_file_warning(
+            &self,
+            state: Self::State<'_>,
+            id: DataId,
+        ) -> Result<Option<String>, String> {
+        }
+        async fn referenced_extensions(
+            &self,
+            state: Self::State<'_>,
+            id: DataId,
+        ) -> Result<Vec<String>, String> {
+        }
+        async fn get_session_stats(
+            &self,
+            state: Self::State<'_>,
+            id: DataId,
+        ) -> Result<SessionStats, String> {
+        }
+        async fn set_record_timings(&self, state: Self::State<'_>, enabled: bool) {}
+        async fn get_max_decompressed_size(&self, state: Self::State<'_>) -> u64 {}
+        async fn set_max_decompressed_size(
+            &self,
+            state: Self::State<'_>,
+            max_bytes: u64,
         ) -> Result<(), String> {
         }
+        async fn get_timings(&self, state: Self::State<'_>, id: DataId) -> Option<StepTimings> {}
+        async fn generate_output(
+            &self,
+            state: Self::State<'_>,
+            id: DataId,
+            generate_options: GenerateOptions,
+            format: OutputFormat,
+        ) -> Result<Vec<u8>, String> {
+        }
+        async fn render_pdf_preview(
+            &self,
+            state: Self::State<'_>,
+            id: DataId,
+            generate_options: GenerateOptions,
+            output_options: OutputOptions,
+        ) -> Result<Vec<u8>, String> {
+        }
+        async fn generate_data_url(
+            &self,
+            state: Self::State<'_>,
+            id: DataId,
+            generate_options: GenerateOptions,
+            output_options: OutputOptions,
+        ) -> Result<String, String> {
+        }
+        async fn typst_font_warning(
+            &self,
+            state: Self::State<'_>,
+            id: DataId,
+            generate_options: GenerateOptions,
+        ) -> Result<Option<String>, String> {
+        }
+        async fn export_combined_urls(
+            &self,
+            state: Self::State<'_>,
+            output_options: OutputOptions,
+        ) -> Result<CombinedUrlsSummary, String> {
+        }
+        async fn export_new_since_last(
+            &self,
+            state: Self::State<'_>,
+            id: DataId,
+            generate_options: GenerateOptions,
+            output_options: OutputOptions,
+        ) -> Result<DeltaExportSummary, String> {
+        }
     }
 
     #[tauri_commands::tauri_commands(
@@ -129,6 +285,12 @@ mod commands {
     impl StatelessCommands for TauriCommands {
         async fn format_descriptions(&self) -> Vec<(OutputFormat, String)> {}
         async fn find_firefox_profiles(&self) -> Result<Vec<FirefoxProfileInfo>, String> {}
+        async fn typst_available_fonts(&self) -> Vec<String> {}
+        async fn load_domain_filters(&self) -> Result<DomainFilters, String> {}
+        async fn save_domain_filters(&self, filters: DomainFilters) -> Result<(), String> {}
+        async fn save_session_state(&self, state: SavedUiState) -> Result<(), String> {}
+        async fn restore_session_state(&self) -> Result<Option<SavedUiState>, String> {}
+        async fn register_file_associations(&self) -> Result<(), String> {}
     }
 }
 
@@ -140,16 +302,35 @@ tauri_commands::combine_commands!(with_all_commands,
 
 // Learn more about Tauri commands at https://tauri.app/v1/guides/features/command
 
+/// A file to open in the GUI, given as `--open <path>` or as the only argument
+/// if that is an existing file. Used when the app is chosen in "Open with".
+fn file_to_open(args: &[OsString]) -> Option<PathBuf> {
+    match args {
+        [flag, path] if flag == "--open" => Some(PathBuf::from(path)),
+        [path] if Path::new(path).is_file() => Some(PathBuf::from(path)),
+        _ => None,
+    }
+}
+
 #[allow(unused_mut)]
 fn main() -> firefox_session_data::Result<()> {
-    if std::env::args_os().nth(1).is_some() {
-        // If called with arguments then behave like a CLI tool:
+    let args = std::env::args_os().skip(1).collect::<Vec<_>>();
+    let open_file = file_to_open(&args);
+    if open_file.is_none() && !args.is_empty() {
+        // If called with other arguments then behave like a CLI tool:
         return firefox_session_data::run();
     }
 
+    let mut ui_state = host::UiState::load();
+    if let Some(path) = open_file {
+        // The frontend loads this file when it starts:
+        ui_state.new_file.path_id = PathId::new();
+        ui_state.new_file.file_path = Some(path);
+    }
+
     // Build app:
     let mut builder = tauri::Builder::default()
-        .manage(Mutex::new(host::UiState::default()))
+        .manage(Mutex::new(ui_state))
         .invoke_handler(with_all_commands!(tauri::generate_handler));
     #[cfg(debug_assertions)]
     {
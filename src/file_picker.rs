@@ -72,10 +72,50 @@ mod web_file_picker {
     //! [`showSaveFilePicker`]:
     //!     https://developer.mozilla.org/en-US/docs/Web/API/Window/showSaveFilePicker
 
+    use std::cell::RefCell;
+
     use js_sys::{ArrayBuffer, Uint8Array};
     use wasm_bindgen::prelude::*;
     use web_sys::File;
 
+    thread_local! {
+        /// The file that was selected by the last save prompt.
+        static SAVE_FILE_HANDLE: RefCell<Option<FileSystemFileHandle>> = const { RefCell::new(None) };
+        /// A stream that was opened for the next save.
+        static SAVE_STREAM: RefCell<Option<FileSystemWritableFileStream>> = const { RefCell::new(None) };
+    }
+
+    /// Remember the file selected by the save prompt so that later saves can
+    /// write to it.
+    pub fn set_save_file_handle(handle: FileSystemFileHandle) {
+        SAVE_FILE_HANDLE.with_borrow_mut(|v| *v = Some(handle));
+    }
+
+    /// Open a writable stream to the file selected by the last save prompt,
+    /// or prompt for a file if none was selected. Returns `false` if the
+    /// browser doesn't support the save prompt.
+    pub async fn open_save_stream() -> Result<bool, JsValue> {
+        if !has_save_file_picker() {
+            return Ok(false);
+        }
+        let handle = match SAVE_FILE_HANDLE.with_borrow(|v| v.clone()) {
+            Some(handle) => handle,
+            None => {
+                let handle = Window::show_save_file_picker().await?;
+                set_save_file_handle(handle.clone());
+                handle
+            }
+        };
+        let stream = handle.create_writable().await?;
+        SAVE_STREAM.with_borrow_mut(|v| *v = Some(stream));
+        Ok(true)
+    }
+
+    /// Take the stream opened by [`open_save_stream`].
+    pub fn take_save_stream() -> Option<FileSystemWritableFileStream> {
+        SAVE_STREAM.with_borrow_mut(Option::take)
+    }
+
     #[wasm_bindgen]
     extern "C" {
         pub type FileSystemFileHandle;
@@ -111,6 +151,9 @@ mod web_file_picker {
 
         #[wasm_bindgen(catch, method)]
         pub async fn close(this: &FileSystemWritableFileStream) -> Result<(), JsValue>;
+
+        #[wasm_bindgen(catch, method)]
+        pub async fn abort(this: &FileSystemWritableFileStream) -> Result<(), JsValue>;
     }
 
     impl Window {
@@ -143,6 +186,24 @@ mod web_file_picker {
             self.close().await?;
             Ok(())
         }
+
+        /// Write `data` in chunks so that the browser never needs a copy of
+        /// all of it, waiting for each chunk to be written before the next is
+        /// copied. The file is only changed if everything was written, since
+        /// the stream is aborted if a write fails.
+        pub async fn write_chunked(self, data: Vec<u8>) -> Result<(), String> {
+            const CHUNK_SIZE: usize = 1024 * 1024;
+
+            for chunk in data.chunks(CHUNK_SIZE) {
+                if let Err(e) = self.write(Uint8Array::from(chunk).buffer()).await {
+                    let _ = self.abort().await;
+                    return Err(format!("failed to write to file: {e:?}"));
+                }
+            }
+            self.close()
+                .await
+                .map_err(|e| format!("failed to close file: {e:?}"))
+        }
     }
 
     pub fn has_save_file_picker() -> bool {
@@ -157,6 +218,8 @@ mod web_file_picker {
     }
 }
 pub use web_file_picker::has_save_file_picker as has_web_view_file_picker;
+#[cfg(target_family = "wasm")]
+pub use web_file_picker::{open_save_stream, take_save_stream};
 
 #[derive(PartialEq, Props, Clone)]
 pub struct SaveFilePickerProps {
@@ -199,6 +262,7 @@ pub fn SaveFilePicker(props: SaveFilePickerProps) -> Element {
                                 let handle = Window::show_save_file_picker().await.ok()?;
                                 let file = handle.get_file().await.ok()?;
                                 let name = file.name();
+                                set_save_file_handle(handle);
 
                                 Commands.set_save_path(crate::ui_state(), name.clone()).await;
 
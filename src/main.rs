@@ -3,13 +3,23 @@
 
 mod file_picker;
 
-use std::{collections::VecDeque, fmt::Debug, future::Future};
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+};
 
 use dioxus::prelude::*;
 use file_picker::{OpenFilePicker, SaveFilePicker};
 use host_commands::{
-    DataId, FileManagementCommands, FileSlot, FileStatus, FirefoxProfileInfo, GenerateOptions,
-    OutputFormat, OutputOptions, PathId, StatelessCommands,
+    DataId, DomainFilters, DuplicateNameStrategy, FileManagementCommands, FileSlot, FileStatus,
+    FirefoxProfileInfo, GenerateOptions, GroupBy, MarkdownStyle, OutputFormat, OutputOptions,
+    PageSize, PathId, SavePathStatus, SavedUiState, SessionStats, StatelessCommands, StepTimings,
+    TabCounts, TabEntry, TabSort, TextEncoding, Theme, TreeSource,
 };
 #[cfg(target_family = "wasm")]
 use wasm_bindgen::prelude::*;
@@ -29,7 +39,14 @@ pub fn ui_state() -> &'static std::sync::Mutex<host_commands::host::UiState> {
         std::sync::Mutex::new(host_commands::host::UiState {
             #[cfg(target_family = "wasm")]
             handle_saved_data: Box::new(save_file_on_web_target),
-            ..Default::default()
+            #[cfg(target_family = "wasm")]
+            open_saved_stream: Box::new(|_| {
+                let stream = file_picker::take_save_stream()?;
+                let write: host_commands::host::SavedStream =
+                    Box::new(move |data| Box::pin(stream.write_chunked(data)));
+                Some(write)
+            }),
+            ..host_commands::host::UiState::load()
         })
     })
 }
@@ -99,6 +116,10 @@ extern "C" {
     /// <https://developer.mozilla.org/en-US/docs/Web/API/Clipboard/writeText>
     #[wasm_bindgen(catch, js_name = "writeText", js_namespace = ["navigator", "clipboard"])]
     async fn write_text_to_web_clipboard(text: &str) -> Result<(), wasm_bindgen::JsValue>;
+
+    /// <https://tauri.app/v1/api/js/clipboard/>
+    #[wasm_bindgen(catch, js_name = "readText", js_namespace = ["window", "__TAURI__", "clipboard"])]
+    async fn read_text_from_tauri_clipboard() -> Result<wasm_bindgen::JsValue, wasm_bindgen::JsValue>;
 }
 #[cfg(target_family = "wasm")]
 async fn write_text_to_clipboard(text: &str) -> Result<(), String> {
@@ -113,10 +134,27 @@ async fn write_text_to_clipboard(text: &str) -> Result<(), String> {
     }
 }
 
+/// Only used with host access, web pages would need to ask for permission.
+#[cfg(target_family = "wasm")]
+async fn read_text_from_clipboard() -> Result<String, String> {
+    read_text_from_tauri_clipboard()
+        .await
+        .map(|text| text.as_string().unwrap_or_default())
+        .map_err(|e| e.as_string().unwrap_or_default())
+}
+
+/// Web pages can only write plain text, so the HTML source is copied.
+#[cfg(target_family = "wasm")]
+async fn write_html_to_clipboard(html: &str, _alt_text: &str) -> Result<(), String> {
+    write_text_to_clipboard(html).await
+}
+
 #[cfg(not(target_family = "wasm"))]
 static CLIPBOARD: std::sync::Mutex<Option<arboard::Clipboard>> = std::sync::Mutex::new(None);
 #[cfg(not(target_family = "wasm"))]
-async fn write_text_to_clipboard(text: &str) -> Result<(), String> {
+fn with_clipboard<T>(
+    f: impl FnOnce(&mut arboard::Clipboard) -> Result<T, arboard::Error>,
+) -> Result<T, String> {
     let mut guard = CLIPBOARD.lock().unwrap();
     let clipboard = if let Some(clipboard) = &mut *guard {
         clipboard
@@ -124,8 +162,21 @@ async fn write_text_to_clipboard(text: &str) -> Result<(), String> {
         let clipboard = arboard::Clipboard::new().map_err(|e| e.to_string())?;
         guard.insert(clipboard)
     };
-    clipboard.set_text(text).map_err(|e| e.to_string())?;
-    Ok(())
+    f(clipboard).map_err(|e| e.to_string())
+}
+#[cfg(not(target_family = "wasm"))]
+async fn write_text_to_clipboard(text: &str) -> Result<(), String> {
+    with_clipboard(|clipboard| clipboard.set_text(text))
+}
+#[cfg(not(target_family = "wasm"))]
+async fn read_text_from_clipboard() -> Result<String, String> {
+    with_clipboard(|clipboard| clipboard.get_text())
+}
+/// Copy HTML as rich text so that it keeps its formatting when pasted into
+/// editors. Programs that only accept plain text get `alt_text` instead.
+#[cfg(not(target_family = "wasm"))]
+async fn write_html_to_clipboard(html: &str, alt_text: &str) -> Result<(), String> {
+    with_clipboard(|clipboard| clipboard.set_html(html, Some(alt_text)))
 }
 
 /// Returned by [`use_elm`]
@@ -224,6 +275,8 @@ fn main() {
         fn start_app() -> Element {
             dioxus::dioxus_core::use_drop(|| {
                 eprintln!("Window closing");
+                #[cfg(feature = "dioxus-desktop")]
+                host_commands::host::cancel_saves(ui_state());
                 if let Ok(mut guard) = CLIPBOARD.lock() {
                     *guard = None; // drop the clipboard
                 }
@@ -325,15 +378,130 @@ fn StyleRef() -> Element {
     })
 }
 
+#[derive(PartialEq, Props, Clone)]
+struct NotificationListProps {
+    notifications: Vec<Notification>,
+    on_dismiss: Option<EventHandler<u64>>,
+}
+
+/// The latest status messages, stacked in a corner of the window with the
+/// newest at the bottom.
+#[component]
+fn NotificationList(props: NotificationListProps) -> Element {
+    let NotificationListProps {
+        notifications,
+        on_dismiss,
+    } = props;
+
+    if notifications.is_empty() {
+        return rsx! {};
+    }
+    rsx! {
+        div { class: "notifications",
+            for notification in notifications {
+                div {
+                    key: "{notification.id}",
+                    class: if notification.is_error {
+                        "notification error"
+                    } else {
+                        "notification"
+                    },
+                    span { class: "notification-time", "{notification.time}" }
+                    span { class: "notification-text", "{notification.text}" }
+                    button {
+                        class: "notification-dismiss",
+                        title: "Dismiss",
+                        onclick: move |_| {
+                            on_dismiss.inspect(|f| f(notification.id));
+                        },
+                        "×"
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[derive(PartialEq, Props, Clone)]
 struct WindowSelectProps {
     open_windows: Vec<String>,
     closed_windows: Vec<String>,
     selected_open_windows: Vec<u32>,
     selected_closed_windows: Vec<u32>,
+    /// Show a list of checkboxes instead of a multiple selection list.
+    checkbox_list: bool,
     /// Will be called with selected indexes for open windows and closed windows
     /// whenever the selection changes.
     on_change: Option<EventHandler<(Vec<u32>, Vec<u32>)>>,
+    on_checkbox_list_change: Option<EventHandler<bool>>,
+}
+
+/// The `value` of an option in the [`WindowSelect`] list. Uses ids instead of
+/// the displayed text so that window names can't be confused with them.
+fn window_option_value(is_closed: bool, ix: usize) -> String {
+    format!("{}:{ix}", if is_closed { 'c' } else { 'o' })
+}
+
+/// Parse a value created by [`window_option_value`]. Returns if the window is
+/// closed and its index.
+fn parse_window_option_value(value: &str) -> Option<(bool, u32)> {
+    let (kind, ix) = value.split_once(':')?;
+    let is_closed = match kind {
+        "o" => false,
+        "c" => true,
+        _ => return None,
+    };
+    Some((is_closed, ix.parse().ok()?))
+}
+
+/// A row in the [`WindowSelect`] checkbox list.
+fn window_checkbox_row(
+    is_closed: bool,
+    ix: u32,
+    name: &str,
+    selected_open_windows: &[u32],
+    selected_closed_windows: &[u32],
+    on_change: Option<EventHandler<(Vec<u32>, Vec<u32>)>>,
+) -> Element {
+    let mut open_ix = selected_open_windows.to_vec();
+    let mut closed_ix = selected_closed_windows.to_vec();
+    let selected = if is_closed { &closed_ix } else { &open_ix };
+    let checked = selected.contains(&ix);
+    let id = format!("window-{}-{ix}", if is_closed { "closed" } else { "open" });
+    let (badge_class, badge) = if is_closed {
+        ("window-badge closed", "Closed")
+    } else {
+        ("window-badge open", "Open")
+    };
+
+    rsx! {
+        div { class: "contains-columns window-checkbox-row",
+            input {
+                r#type: "checkbox",
+                id: "{id}",
+                checked: "{checked}",
+                onchange: move |e| {
+                    let selected = if is_closed { &mut closed_ix } else { &mut open_ix };
+                    if e.checked() {
+                        selected.push(ix);
+                        selected.sort_unstable();
+                    } else {
+                        selected.retain(|&selected| selected != ix);
+                    }
+                    log::trace!(
+                        "Changed window filter\n\tOpen window indexes: {open_ix:?}\n\tClosed window indexes: {closed_ix:?}"
+                    );
+                    on_change.inspect(|f| f((open_ix.clone(), closed_ix.clone())));
+                },
+            }
+            label {
+                class: "vertically-centered-text",
+                r#for: "{id}",
+                span { class: "{badge_class}", "{badge}" }
+                "{name}"
+            }
+        }
+    }
 }
 
 /// A list of windows in the loaded session. Allows selecting some of the
@@ -346,83 +514,172 @@ fn WindowSelect(props: WindowSelectProps) -> Element {
         closed_windows,
         selected_open_windows,
         selected_closed_windows,
+        checkbox_list,
         on_change,
+        on_checkbox_list_change,
     } = props;
 
+    let header = rsx! {
+        div { class: "contains-columns",
+            {window_select_mode_toggle(checkbox_list, on_checkbox_list_change)}
+            div { class: "spacer", style: "flex: 1 1 auto;" }
+            {window_select_all_button(
+                open_windows.len() as u32,
+                closed_windows.len() as u32,
+                &selected_open_windows,
+                &selected_closed_windows,
+                on_change,
+            )}
+        }
+    };
+
+    if checkbox_list {
+        let rows = open_windows
+            .iter()
+            .enumerate()
+            .map(|(ix, window)| (false, ix, window))
+            .chain(
+                closed_windows
+                    .iter()
+                    .enumerate()
+                    .map(|(ix, window)| (true, ix, window)),
+            )
+            .map(|(is_closed, ix, window)| {
+                window_checkbox_row(
+                    is_closed,
+                    ix as u32,
+                    window,
+                    &selected_open_windows,
+                    &selected_closed_windows,
+                    on_change,
+                )
+            });
+        return rsx! {
+            div { id: "window-select", class: "contains-rows",
+                {header}
+                div {
+                    class: "contains-rows window-checkbox-list",
+                    role: "group",
+                    aria_label: "Windows",
+                    {rows}
+                }
+            }
+        };
+    }
+
     rsx! {
-        select {
-            id: "window-select",
-            name: "windows",
-            multiple: true,
-            onchange: move |evt| {
-                log::debug!("multi select event: {evt:?}");
-                let (values_wasm, values_desktop);
-                let values = if cfg!(target_family = "wasm") {
-                    values_wasm = evt.get("options");
-                    values_wasm
-                        .iter()
-                        .filter_map(|form_value| {
-                            if let FormValue::Text(text) = form_value {
-                                Some(text.as_str())
-                            } else {
-                                None
-                            }
-                        })
-                        .collect::<Vec<&str>>()
-                } else {
-                    values_desktop = evt.value();
-                    values_desktop.split(',').collect::<Vec<&str>>()
-                };
-                log::debug!("Changed which windows are selected to: {values:?}");
-                let mut open_ix = Vec::new();
-                let mut closed_ix = Vec::new();
-                for value in values {
-                    if value.is_empty() {
-                        continue;
-                    }
-                    let (is_closed, ix) = if let Some(v) = value.strip_prefix("Window ") {
-                        (false, v)
-                    } else if let Some(v) = value.strip_prefix("Closed window ") {
-                        (true, v)
+        div { id: "window-select", class: "contains-rows",
+            {header}
+            select {
+                name: "windows",
+                style: "flex: 1 1 auto;",
+                multiple: true,
+                onchange: move |evt| {
+                    log::debug!("multi select event: {evt:?}");
+                    let (values_wasm, values_desktop);
+                    let values = if cfg!(target_family = "wasm") {
+                        values_wasm = evt.get("options");
+                        values_wasm
+                            .iter()
+                            .filter_map(|form_value| {
+                                if let FormValue::Text(text) = form_value {
+                                    Some(text.as_str())
+                                } else {
+                                    None
+                                }
+                            })
+                            .collect::<Vec<&str>>()
                     } else {
-                        log::warn!("Malformed value in window select: {value}");
-                        continue;
+                        values_desktop = evt.value();
+                        values_desktop.split(',').collect::<Vec<&str>>()
                     };
-                    match ix.parse::<u32>() {
-                        Err(e) => log::warn!("Malformed index in window select \"{ix}\": {e}"),
-                        Ok(ix) => {
-                            if is_closed {
-                                closed_ix.push(ix - 1);
-                            } else {
-                                open_ix.push(ix - 1);
-                            }
+                    log::debug!("Changed which windows are selected to: {values:?}");
+                    let mut open_ix = Vec::new();
+                    let mut closed_ix = Vec::new();
+                    // Separators have empty values and are skipped:
+                    for (is_closed, ix) in values.into_iter().filter_map(parse_window_option_value) {
+                        if is_closed {
+                            closed_ix.push(ix);
+                        } else {
+                            open_ix.push(ix);
                         }
                     }
+                    log::trace!(
+                        "Changed window filter\n\tOpen window indexes: {open_ix:?}\n\tClosed window indexes: {closed_ix:?}"
+                    );
+                    if let Some(on_change) = on_change {
+                        on_change((open_ix, closed_ix));
+                    }
+                },
+                for (ix , window) in open_windows.iter().enumerate() {
+                    option {
+                        value: window_option_value(false, ix),
+                        selected: Some(selected_open_windows.contains(&(ix as u32))),
+                        "{window}"
+                    }
                 }
-                log::trace!(
-                    "Changed window filter\n\tOpen window indexes: {open_ix:?}\n\tClosed window indexes: {closed_ix:?}"
-                );
-                if let Some(on_change) = on_change {
-                    on_change((open_ix, closed_ix));
+                if !closed_windows.is_empty() {
+                    option { value: "", disabled: true, "" }
+                    option { value: "", disabled: true, "Closed Windows:" }
                 }
-            },
-            for (ix , window) in open_windows.iter().enumerate() {
-                option {
-                    value: "Window {ix + 1}",
-                    selected: Some(selected_open_windows.contains(&(ix as u32))),
-                    "{window}"
+                for (ix , window) in closed_windows.iter().enumerate() {
+                    option {
+                        value: window_option_value(true, ix),
+                        selected: Some(selected_closed_windows.contains(&(ix as u32))),
+                        "{window}"
+                    }
                 }
             }
-            if !closed_windows.is_empty() {
-                option { value: "", disabled: true, "" }
-                option { value: "", disabled: true, "Closed Windows:" }
+        }
+    }
+}
+
+/// Button that selects every window in the [`WindowSelect`] list. There is no
+/// button to deselect all windows since nothing selected means that every
+/// window is included.
+fn window_select_all_button(
+    open_count: u32,
+    closed_count: u32,
+    selected_open_windows: &[u32],
+    selected_closed_windows: &[u32],
+    on_change: Option<EventHandler<(Vec<u32>, Vec<u32>)>>,
+) -> Element {
+    let all_selected = selected_open_windows.len() as u32 >= open_count
+        && selected_closed_windows.len() as u32 >= closed_count;
+
+    rsx! {
+        button {
+            title: "Select all open and closed windows.",
+            style: "margin-left: 5px;",
+            disabled: Some(true).filter(|_| all_selected),
+            onclick: move |_| {
+                on_change.inspect(|f| f(((0..open_count).collect(), (0..closed_count).collect())));
+            },
+            "Select all"
+        }
+    }
+}
+
+/// Switch between showing windows in a selection list or as checkboxes.
+fn window_select_mode_toggle(
+    checkbox_list: bool,
+    on_checkbox_list_change: Option<EventHandler<bool>>,
+) -> Element {
+    rsx! {
+        div { class: "contains-columns",
+            input {
+                r#type: "checkbox",
+                id: "window-checkbox-list",
+                checked: "{checkbox_list}",
+                onchange: move |e| {
+                    on_checkbox_list_change.inspect(|f| f(e.checked()));
+                },
             }
-            for (ix , window) in closed_windows.iter().enumerate() {
-                option {
-                    value: "Closed window {ix + 1}",
-                    selected: Some(selected_closed_windows.contains(&(ix as u32))),
-                    "{window}"
-                }
+            label {
+                class: "vertically-centered-text",
+                r#for: "window-checkbox-list",
+                title: "Show a checkbox for each window instead of a list where windows are selected with Ctrl and Shift.",
+                "Checkboxes"
             }
         }
     }
@@ -438,15 +695,38 @@ struct InputPanelProps {
     /// The `input_path` has been manually edited and this change should be sent
     /// to the backend if accepted.
     on_input_path_edit: Option<EventHandler<String>>,
+    /// A path or `file://` URL should be pasted from the clipboard into
+    /// `input_path`.
+    on_paste_input_path: Option<EventHandler<()>>,
     /// The `input_path` has changed in the backend because the user browsed and
     /// selected a file.
     on_input_path_changed: Option<EventHandler<PathId>>,
     /// The user has requested that the file path that has been entered into
     /// `input_path` should now be loaded.
     on_load_new_data: Option<EventHandler<()>>,
+    /// The file that the current data was loaded from should be read again.
+    on_reload: Option<EventHandler<()>>,
+    /// All loaded data should be forgotten.
+    on_clear: Option<EventHandler<()>>,
+    /// The uncompressed JSON of the current data should be copied.
+    on_copy_raw_json: Option<EventHandler<()>>,
     on_open_wizard: Option<EventHandler<()>>,
+    /// Sessionstore files should be opened with this program.
+    on_register_file_types: Option<EventHandler<()>>,
+    /// Files that were recently opened, the most recent first.
+    recent_paths: Vec<String>,
+    /// A recent file was picked and should be loaded.
+    on_recent_path_selected: Option<EventHandler<String>>,
+    on_clear_recent_paths: Option<EventHandler<()>>,
+    /// Only tabs whose title or URL matches this text are included.
+    search_filter: String,
+    on_search_filter_edit: Option<EventHandler<String>>,
 }
 
+/// Option value in the recent files list that clears the list. Can't be a
+/// file path since paths can't contain null characters.
+const CLEAR_RECENT_PATHS: &str = "\0clear";
+
 /// Configure where the sessionstore file is loaded from.
 #[component]
 fn InputPanel(props: InputPanelProps) -> Element {
@@ -455,9 +735,19 @@ fn InputPanel(props: InputPanelProps) -> Element {
         input_path,
         loaded_file_path,
         on_input_path_edit,
+        on_paste_input_path,
         on_input_path_changed,
         on_load_new_data,
+        on_reload,
+        on_clear,
+        on_copy_raw_json,
         on_open_wizard,
+        on_register_file_types,
+        recent_paths,
+        on_recent_path_selected,
+        on_clear_recent_paths,
+        search_filter,
+        on_search_filter_edit,
     } = props;
 
     rsx! {
@@ -477,6 +767,15 @@ fn InputPanel(props: InputPanelProps) -> Element {
                 },
             }
             if host_commands::has_host_access() {
+                button {
+                    title: "Paste a file path or a file:// URL from the clipboard.",
+                    style: "margin-right: 5px;",
+                    onclick: move |_| {
+                        log::debug!("Requested to paste input path",);
+                        on_paste_input_path.inspect(|f| f(()));
+                    },
+                    "Paste"
+                }
                 button {
                     title: "Open a \"software wizard\"/\"setup assistant\" to help you select a Firefox sessionstore file.",
                     style: "margin-right: 5px;",
@@ -486,6 +785,15 @@ fn InputPanel(props: InputPanelProps) -> Element {
                     },
                     "Wizard"
                 }
+                button {
+                    title: "Open sessionstore files (.jsonlz4 and .baklz4) with this program when they are double-clicked. Only supported on Windows.",
+                    style: "margin-right: 5px;",
+                    onclick: move |_| {
+                        log::debug!("Requested to register file types",);
+                        on_register_file_types.inspect(|f| f(()));
+                    },
+                    "Open with..."
+                }
             }
             OpenFilePicker {
                 on_input: move |v| {
@@ -494,6 +802,30 @@ fn InputPanel(props: InputPanelProps) -> Element {
                 },
                 "Browse"
             }
+            if host_commands::has_host_access() && !recent_paths.is_empty() {
+                select {
+                    id: "recent-paths",
+                    title: "Load a file that was opened recently.",
+                    style: "margin-left: 5px; max-width: 10em;",
+                    // Always show the placeholder so the same file can be
+                    // picked again:
+                    value: "",
+                    onchange: move |evt| {
+                        let value = evt.value();
+                        log::debug!("Selected recent file: {value:?}");
+                        if value == CLEAR_RECENT_PATHS {
+                            on_clear_recent_paths.inspect(|f| f(()));
+                        } else if !value.is_empty() {
+                            on_recent_path_selected.inspect(|f| f(value));
+                        }
+                    },
+                    option { value: "", disabled: true, "Recent" }
+                    for path in recent_paths.iter() {
+                        option { value: "{path}", title: "{path}", "{path}" }
+                    }
+                    option { value: CLEAR_RECENT_PATHS, "Clear recent files" }
+                }
+            }
         }
         div { class: "file-input contains-columns",
             label { r#for: "loaded-file-path", "Current data was loaded from:" }
@@ -504,6 +836,36 @@ fn InputPanel(props: InputPanelProps) -> Element {
                 disabled: true,
                 value: "{loaded_file_path}",
             }
+            button {
+                title: "Read the current file from disk again, for example after Firefox has updated it.",
+                style: "margin-right: 5px;",
+                // Files picked inside a web page can't be read again:
+                disabled: Some(true).filter(|_| !host_commands::has_host_access() || loaded_file_path.is_empty()),
+                onclick: move |_| {
+                    log::debug!("Requested to reload the current file",);
+                    on_reload.inspect(|f| f(()));
+                },
+                "Reload"
+            }
+            button {
+                title: "Forget the loaded data to free its memory and clear the preview.",
+                style: "margin-right: 5px;",
+                onclick: move |_| {
+                    log::debug!("Requested to clear all loaded data",);
+                    on_clear.inspect(|f| f(()));
+                },
+                "Clear"
+            }
+            button {
+                title: "Copy the uncompressed JSON text of the current data to the clipboard.",
+                style: "margin-right: 5px;",
+                disabled: Some(true).filter(|_| loaded_file_path.is_empty()),
+                onclick: move |_| {
+                    log::debug!("Requested to copy the raw JSON of the current data",);
+                    on_copy_raw_json.inspect(|f| f(()));
+                },
+                "Copy JSON"
+            }
             button {
                 onclick: move |_| {
                     log::debug!("Requested to load new data from input path",);
@@ -512,17 +874,154 @@ fn InputPanel(props: InputPanelProps) -> Element {
                 "Load new data"
             }
         }
+        div { class: "file-input contains-columns",
+            label {
+                r#for: "search-filter",
+                title: "Only include tabs whose title or URL contains this text, ignoring case. Use * to match any text and ? to match any single character. Tabs that contain a matching tab in their tree are kept as well.",
+                "Only include tabs matching:"
+            }
+            input {
+                id: "search-filter",
+                r#type: "search",
+                placeholder: "Title or URL",
+                value: "{search_filter}",
+                oninput: move |evt| {
+                    on_search_filter_edit.inspect(|f| f(evt.value()));
+                },
+            }
+        }
+    }
+}
+
+#[derive(PartialEq, Props, Clone)]
+struct DomainFilterEditorProps {
+    allowlist: Vec<String>,
+    blocklist: Vec<String>,
+    /// Called when the user has finished editing one of the lists.
+    on_change: Option<EventHandler<DomainFilters>>,
+}
+
+/// Parse a list of domains with one domain per line.
+fn parse_domain_list(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Edit which domains should be included or excluded from the output.
+#[component]
+fn DomainFilterEditor(props: DomainFilterEditorProps) -> Element {
+    log::trace!("Rendering DomainFilterEditor");
+    let DomainFilterEditorProps {
+        allowlist,
+        blocklist,
+        on_change,
+    } = props;
+
+    let allowlist_text = allowlist.join("\n");
+    let blocklist_text = blocklist.join("\n");
+    let summary = match (allowlist.len(), blocklist.len()) {
+        (0, 0) => "Domain filters".to_owned(),
+        (allowed, blocked) => format!("Domain filters ({allowed} allowed, {blocked} blocked)"),
+    };
+
+    rsx! {
+        details { style: "margin: 8px;",
+            summary {
+                title: "Only include tabs from some domains or exclude tabs from some domains. A domain also matches its subdomains. The lists are remembered between restarts.",
+                "{summary}"
+            }
+            div { class: "contains-columns",
+                div { class: "contains-rows", style: "flex: 1 1 auto;",
+                    label { r#for: "domain-allowlist", "Only include (one domain per line):" }
+                    textarea {
+                        id: "domain-allowlist",
+                        rows: 4,
+                        value: "{allowlist_text}",
+                        onchange: {
+                            let blocklist = blocklist.clone();
+                            move |e: FormEvent| {
+                                on_change.inspect(|f| f(DomainFilters {
+                                    allowlist: parse_domain_list(&e.value()),
+                                    blocklist: blocklist.clone(),
+                                }));
+                            }
+                        },
+                    }
+                }
+                div { class: "contains-rows", style: "flex: 1 1 auto; margin-left: 10px;",
+                    label { r#for: "domain-blocklist", "Exclude (one domain per line):" }
+                    textarea {
+                        id: "domain-blocklist",
+                        rows: 4,
+                        value: "{blocklist_text}",
+                        onchange: move |e: FormEvent| {
+                            on_change.inspect(|f| f(DomainFilters {
+                                allowlist: allowlist.clone(),
+                                blocklist: parse_domain_list(&e.value()),
+                            }));
+                        },
+                    }
+                }
+            }
+        }
     }
 }
 
 #[derive(PartialEq, Props, Clone)]
 struct OutputPanelProps {
     output_options: OutputOptions,
+    generate_options: GenerateOptions,
     format_info: Vec<(OutputFormat, String)>,
+    /// Font families that can be selected for Typst based formats.
+    typst_fonts: Vec<String>,
     output_path: String,
+    /// Problems with `output_path`, `None` until it has been checked.
+    save_path_status: Option<SavePathStatus>,
     on_overwrite_change: Option<EventHandler<bool>>,
     on_create_folder_change: Option<EventHandler<bool>>,
+    /// Timeout in seconds, `None` if the generation should never time out.
+    on_generation_timeout_change: Option<EventHandler<Option<u64>>>,
+    on_include_export_id_change: Option<EventHandler<bool>>,
+    on_restore_bundle_change: Option<EventHandler<bool>>,
+    on_split_per_window_change: Option<EventHandler<bool>>,
+    on_reveal_after_save_change: Option<EventHandler<bool>>,
+    on_save_preview_change: Option<EventHandler<bool>>,
+    on_text_encoding_change: Option<EventHandler<TextEncoding>>,
+    on_page_size_change: Option<EventHandler<PageSize>>,
+    on_landscape_change: Option<EventHandler<bool>>,
+    on_margins_change: Option<EventHandler<f32>>,
+    on_pdf_render_retries_change: Option<EventHandler<u32>>,
+    on_duplicate_names_change: Option<EventHandler<DuplicateNameStrategy>>,
+    on_tab_sort_change: Option<EventHandler<TabSort>>,
+    on_group_by_change: Option<EventHandler<GroupBy>>,
     on_output_format_change: Option<EventHandler<OutputFormat>>,
+    on_markdown_style_change: Option<EventHandler<MarkdownStyle>>,
+    on_markdown_toc_links_change: Option<EventHandler<bool>>,
+    on_use_tst_change: Option<EventHandler<bool>>,
+    on_use_sidebery_change: Option<EventHandler<bool>>,
+    on_flat_links_change: Option<EventHandler<bool>>,
+    on_mark_collapsed_tabs_change: Option<EventHandler<bool>>,
+    on_include_counts_change: Option<EventHandler<bool>>,
+    on_mark_closed_windows_change: Option<EventHandler<bool>>,
+    on_deduplicate_links_change: Option<EventHandler<bool>>,
+    on_deduplicate_globally_change: Option<EventHandler<bool>>,
+    on_annotate_languages_change: Option<EventHandler<bool>>,
+    on_include_timestamps_change: Option<EventHandler<bool>>,
+    on_include_container_change: Option<EventHandler<bool>>,
+    on_pinned_only_change: Option<EventHandler<bool>>,
+    on_include_closed_tabs_change: Option<EventHandler<bool>>,
+    on_reproducible_change: Option<EventHandler<bool>>,
+    on_html_window_filter_change: Option<EventHandler<bool>>,
+    on_typst_font_change: Option<EventHandler<Option<String>>>,
+    on_pdf_outline_change: Option<EventHandler<bool>>,
+    /// Custom Typst template, `None` to use the default styling.
+    on_typst_template_change: Option<EventHandler<Option<String>>>,
+    /// Line between windows when copying links, `None` to disable.
+    on_window_separator_change: Option<EventHandler<Option<String>>>,
+    on_custom_page_break_change: Option<EventHandler<String>>,
     /// User manually edited the save file path. If this change is accepted then
     /// it should be sent to the backend.
     on_output_path_edit: Option<EventHandler<String>>,
@@ -531,8 +1030,22 @@ struct OutputPanelProps {
     on_output_path_changed: Option<EventHandler<String>>,
     on_copy_to_clipboard: Option<EventHandler<()>>,
     on_write_to_file: Option<EventHandler<()>>,
+    /// Generate the selected PDF format and show it in the preview area.
+    on_preview_pdf: Option<EventHandler<()>>,
+    on_open_in_new_tab: Option<EventHandler<()>>,
+    /// Write the URLs from both the loaded and the newly selected file to the
+    /// save file.
+    on_export_combined_urls: Option<EventHandler<()>>,
+    on_export_new_since_last: Option<EventHandler<()>>,
+    /// Reset output and generate options to their default values.
+    on_reset_options: Option<EventHandler<()>>,
+    theme: Theme,
+    on_theme_change: Option<EventHandler<Theme>>,
 }
 
+/// Separator that is used when window separators are first enabled.
+const DEFAULT_WINDOW_SEPARATOR: &str = "----------------------------------------";
+
 /// Handle configuration of output format and path and has a button to start
 /// writing links from the sessionstore to a file or to the user's clipboard.
 #[component]
@@ -541,17 +1054,70 @@ fn OutputPanel(props: OutputPanelProps) -> Element {
 
     let OutputPanelProps {
         output_options,
+        generate_options,
         format_info,
+        typst_fonts,
         output_path,
+        save_path_status,
         on_overwrite_change,
         on_create_folder_change,
+        on_generation_timeout_change,
+        on_include_export_id_change,
+        on_restore_bundle_change,
+        on_split_per_window_change,
+        on_reveal_after_save_change,
+        on_save_preview_change,
+        on_text_encoding_change,
+        on_page_size_change,
+        on_landscape_change,
+        on_margins_change,
+        on_pdf_render_retries_change,
+        on_duplicate_names_change,
+        on_tab_sort_change,
+        on_group_by_change,
         on_output_format_change,
+        on_markdown_style_change,
+        on_markdown_toc_links_change,
+        on_use_tst_change,
+        on_use_sidebery_change,
+        on_flat_links_change,
+        on_mark_collapsed_tabs_change,
+        on_include_counts_change,
+        on_mark_closed_windows_change,
+        on_deduplicate_links_change,
+        on_deduplicate_globally_change,
+        on_annotate_languages_change,
+        on_include_timestamps_change,
+        on_include_container_change,
+        on_pinned_only_change,
+        on_include_closed_tabs_change,
+        on_reproducible_change,
+        on_html_window_filter_change,
+        on_typst_font_change,
+        on_pdf_outline_change,
+        on_typst_template_change,
+        on_window_separator_change,
+        on_custom_page_break_change,
         on_output_path_edit,
         on_output_path_changed,
         on_copy_to_clipboard,
         on_write_to_file,
+        on_preview_pdf,
+        on_open_in_new_tab,
+        on_export_combined_urls,
+        on_export_new_since_last,
+        on_reset_options,
+        theme,
+        on_theme_change,
     } = props;
 
+    let markdown_style = generate_options.markdown_style;
+    // Checking a style replaces the current one, unchecking goes back to a list:
+    let set_markdown_style = move |style: MarkdownStyle, checked: bool| {
+        let style = if checked { style } else { MarkdownStyle::List };
+        on_markdown_style_change.inspect(|f| f(style));
+    };
+
     let get_title_for_format = |format: OutputFormat| {
         format_info
             .iter()
@@ -584,6 +1150,13 @@ fn OutputPanel(props: OutputPanelProps) -> Element {
                         "Browse"
                     }
                 }
+                if let Some(hint) = save_path_status
+                    .as_ref()
+                    .filter(|_| !output_path.trim().is_empty())
+                    .and_then(|status| save_path_hint(status, &output_options))
+                {
+                    div { class: "save-path-hint", "{hint}" }
+                }
                 div { class: "contains-columns",
                     div { class: "contains-columns",
                         input {
@@ -611,53 +1184,937 @@ fn OutputPanel(props: OutputPanelProps) -> Element {
                         }
                         label { r#for: "overwrite-output-file", "Overwrite file if it already exists" }
                     }
-                }
-            }
-            div { class: "spacer", style: "flex: 0 1 auto; height: 5px;" }
-            div { class: "contains-columns",
-                button {
-                    onclick: move |_| {
-                        on_copy_to_clipboard.inspect(|f| f(()));
-                    },
-                    "Copy links to clipboard"
-                }
-                div { class: "spacer", style: "flex: 1 1 auto;" }
-                fieldset {
-                    class: "contains-rows output-format-group output-format-drop-down",
-                    style: "margin: 6px;",
-                    title: get_title_for_format(output_options.format),
-                    legend { "Output format" }
-                    select {
-                        id: "output-format",
-                        style: "flex: 1 1 auto;",
-                        onchange: move |evt| {
-                            let Some(on_output_format_change) = &on_output_format_change else {
-                                return;
-                            };
-                            let value = evt.value();
-                            if let Some(&format) = OutputFormat::all().iter().find(|f| f.as_str() == value) {
-                                on_output_format_change(format);
+                    div {
+                        class: "contains-columns",
+                        style: "margin-left: 10px;",
+                        input {
+                            r#type: "checkbox",
+                            id: "split-per-window",
+                            checked: "{output_options.split_per_window}",
+                            onchange: move |e| {
+                                log::trace!("Clicked on split per window checkbox {e:?}");
+                                on_split_per_window_change.inspect(|f| f(e.checked()));
+                            },
+                        }
+                        label {
+                            r#for: "split-per-window",
+                            title: "Save each selected window to its own file. The window's number is added to the file name, for example \"links-window-1.pdf\".",
+                            "One file per window"
+                        }
+                    }
+                    div {
+                        class: "contains-columns",
+                        style: "margin-left: 10px;",
+                        input {
+                            r#type: "checkbox",
+                            id: "save-preview",
+                            checked: "{output_options.save_preview}",
+                            onchange: move |e| {
+                                log::trace!("Clicked on save preview checkbox {e:?}");
+                                on_save_preview_change.inspect(|f| f(e.checked()));
+                            },
+                        }
+                        label {
+                            r#for: "save-preview",
+                            title: "Save the preview exactly as it is shown, as a text file, instead of using the selected format.",
+                            "Save preview as shown"
+                        }
+                    }
+                    if host_commands::has_host_access() {
+                        div {
+                            class: "contains-columns",
+                            style: "margin-left: 10px;",
+                            input {
+                                r#type: "checkbox",
+                                id: "reveal-after-save",
+                                checked: "{output_options.reveal_after_save}",
+                                onchange: move |e| {
+                                    log::trace!("Clicked on reveal after save checkbox {e:?}");
+                                    on_reveal_after_save_change.inspect(|f| f(e.checked()));
+                                },
                             }
-                        },
-                        for format in OutputFormat::all().iter().copied() {
-                            option {
-                                value: format.as_str(),
-                                selected: Some(output_options.format == format),
-                                title: get_title_for_format(format),
-                                "{format.as_str()}"
+                            label {
+                                r#for: "reveal-after-save",
+                                title: "Open the saved file with its default program. If one file per window is saved then the folder is opened instead.",
+                                "Open after saving"
+                            }
+                        }
+                    }
+                    div {
+                        class: "contains-columns",
+                        style: "margin-left: 10px;",
+                        label {
+                            class: "vertically-centered-text",
+                            r#for: "generation-timeout",
+                            title: "Give up on saving if generating the file takes longer than this. Leave empty to never time out.",
+                            "Timeout (seconds):"
+                        }
+                        input {
+                            r#type: "number",
+                            id: "generation-timeout",
+                            min: "1",
+                            style: "width: 6em; margin-left: 5px;",
+                            value: output_options.generation_timeout_secs.map(|secs| secs.to_string()).unwrap_or_default(),
+                            onchange: move |e| {
+                                let value = e.value();
+                                log::trace!("Changed generation timeout to {value:?}");
+                                let timeout = value.trim().parse::<u64>().ok().filter(|&secs| secs > 0);
+                                on_generation_timeout_change.inspect(|f| f(timeout));
+                            },
+                        }
+                    }
+                    if output_options.format == OutputFormat::HTML {
+                        div {
+                            class: "contains-columns",
+                            style: "margin-left: 10px;",
+                            input {
+                                r#type: "checkbox",
+                                id: "html-details",
+                                checked: Some(markdown_style == MarkdownStyle::HtmlDetails),
+                                onchange: move |e| {
+                                    log::trace!("Clicked on HTML details checkbox {e:?}");
+                                    set_markdown_style(MarkdownStyle::HtmlDetails, e.checked());
+                                },
+                            }
+                            label {
+                                r#for: "html-details",
+                                title: "Each window in the saved HTML file can be collapsed and expanded, as can tabs that have children in a tree of tabs.",
+                                "Collapsible windows"
+                            }
+                        }
+                        div {
+                            class: "contains-columns",
+                            style: "margin-left: 10px;",
+                            input {
+                                r#type: "checkbox",
+                                id: "html-window-filter",
+                                checked: "{generate_options.html_window_filter}",
+                                // Collapsible windows have no headings to click:
+                                disabled: Some(true).filter(|_| {
+                                    markdown_style == MarkdownStyle::HtmlDetails
+                                }),
+                                onchange: move |e| {
+                                    log::trace!("Clicked on HTML window filter checkbox {e:?}");
+                                    on_html_window_filter_change.inspect(|f| f(e.checked()));
+                                },
+                            }
+                            label {
+                                r#for: "html-window-filter",
+                                title: "Clicking a window's heading in the saved HTML file hides all other windows (requires JavaScript).",
+                                "Clickable windows"
+                            }
+                        }
+                    }
+                    if cfg!(not(target_family = "wasm")) && output_options.format == OutputFormat::HTML {
+                        div {
+                            class: "contains-columns",
+                            style: "margin-left: 10px;",
+                            input {
+                                r#type: "checkbox",
+                                id: "restore-bundle",
+                                checked: "{output_options.restore_bundle}",
+                                onchange: move |e| {
+                                    log::trace!("Clicked on restore bundle checkbox {e:?}");
+                                    on_restore_bundle_change.inspect(|f| f(e.checked()));
+                                },
+                            }
+                            label {
+                                r#for: "restore-bundle",
+                                title: "Also save the exported windows as a sessionstore file next to the HTML file and link to it so the tabs can be restored in Firefox.",
+                                "Restore bundle"
                             }
                         }
                     }
                 }
-                fieldset {
-                    class: "contains-columns output-format-group output-format-radio-buttons",
-                    style: "margin: 6px; align-items: baseline; align-self: stretch;",
-                    legend { "Output format" }
+            }
+            div { class: "contains-columns",
+                div { class: "contains-columns",
                     input {
-                        r#type: "radio",
-                        name: "output-format",
-                        id: "output-format-text",
-                        checked: Some(output_options.format == OutputFormat::TEXT),
+                        r#type: "checkbox",
+                        id: "markdown-table",
+                        checked: Some(markdown_style == MarkdownStyle::Table),
+                        onchange: move |e| {
+                            log::trace!("Clicked on Markdown table checkbox {e:?}");
+                            set_markdown_style(MarkdownStyle::Table, e.checked());
+                        },
+                    }
+                    label {
+                        r#for: "markdown-table",
+                        title: "Generate a table with one row per tab when copying links or saving with the Markdown format.",
+                        "Markdown table"
+                    }
+                }
+                if output_options.format == OutputFormat::MARKDOWN {
+                    div {
+                        class: "contains-columns",
+                        style: "margin-left: 10px;",
+                        input {
+                            r#type: "checkbox",
+                            id: "markdown-details",
+                            checked: Some(markdown_style == MarkdownStyle::Details),
+                            onchange: move |e| {
+                                log::trace!("Clicked on Markdown details checkbox {e:?}");
+                                set_markdown_style(MarkdownStyle::Details, e.checked());
+                            },
+                        }
+                        label {
+                            r#for: "markdown-details",
+                            title: "Put each window's links inside a collapsible block when saving with the Markdown format (supported by GitHub and GitLab).",
+                            "Collapsible windows"
+                        }
+                    }
+                    div {
+                        class: "contains-columns",
+                        style: "margin-left: 10px;",
+                        input {
+                            r#type: "checkbox",
+                            id: "markdown-task-list",
+                            checked: Some(markdown_style == MarkdownStyle::TaskList),
+                            onchange: move |e| {
+                                log::trace!("Clicked on Markdown task list checkbox {e:?}");
+                                set_markdown_style(MarkdownStyle::TaskList, e.checked());
+                            },
+                        }
+                        label {
+                            r#for: "markdown-task-list",
+                            title: "Write each link as a task list item with a checkbox when saving with the Markdown format (supported by GitHub and GitLab).",
+                            "Task list"
+                        }
+                    }
+                    div {
+                        class: "contains-columns",
+                        style: "margin-left: 10px;",
+                        input {
+                            r#type: "checkbox",
+                            id: "markdown-toc-links",
+                            checked: "{generate_options.markdown_toc_links}",
+                            disabled: Some(true).filter(|_| {
+                                !generate_options.table_of_content
+                                    || matches!(
+                                        markdown_style,
+                                        MarkdownStyle::Table | MarkdownStyle::Details
+                                    )
+                            }),
+                            onchange: move |e| {
+                                log::trace!("Clicked on Markdown TOC links checkbox {e:?}");
+                                on_markdown_toc_links_change.inspect(|f| f(e.checked()));
+                            },
+                        }
+                        label {
+                            r#for: "markdown-toc-links",
+                            title: "Make the table of contents link to each window's heading when saving with the Markdown format (uses the same anchors as GitHub).",
+                            "Linked table of contents"
+                        }
+                    }
+                }
+                div {
+                    class: "contains-columns",
+                    style: "margin-left: 10px;",
+                    input {
+                        r#type: "checkbox",
+                        id: "use-tst-trees",
+                        checked: "{generate_options.tree_style_tab_trees}",
+                        disabled: Some(true).filter(|_| generate_options.flat_links),
+                        onchange: move |e| {
+                            log::trace!("Clicked on Tree Style Tab trees checkbox {e:?}");
+                            on_use_tst_change.inspect(|f| f(e.checked()));
+                        },
+                    }
+                    label {
+                        r#for: "use-tst-trees",
+                        title: "Nest tabs using the tree structure that the Tree Style Tab extension stored in the session. Sidebery's trees are preferred if both are enabled and available.",
+                        "Tree Style Tab trees"
+                    }
+                }
+                div {
+                    class: "contains-columns",
+                    style: "margin-left: 10px;",
+                    input {
+                        r#type: "checkbox",
+                        id: "use-sidebery-trees",
+                        checked: "{generate_options.sidebery_trees}",
+                        disabled: Some(true).filter(|_| generate_options.flat_links),
+                        onchange: move |e| {
+                            log::trace!("Clicked on Sidebery trees checkbox {e:?}");
+                            on_use_sidebery_change.inspect(|f| f(e.checked()));
+                        },
+                    }
+                    label {
+                        r#for: "use-sidebery-trees",
+                        title: "Nest tabs using the tree structure that the Sidebery extension stored in the session.",
+                        "Sidebery trees"
+                    }
+                }
+                div {
+                    class: "contains-columns",
+                    style: "margin-left: 10px;",
+                    input {
+                        r#type: "checkbox",
+                        id: "flat-links",
+                        checked: "{generate_options.flat_links}",
+                        onchange: move |e| {
+                            log::trace!("Clicked on flat links checkbox {e:?}");
+                            on_flat_links_change.inspect(|f| f(e.checked()));
+                        },
+                    }
+                    label {
+                        r#for: "flat-links",
+                        title: "Write one tab per line without indentation. Tabs that are nested in a tree are still included.",
+                        "Flat list"
+                    }
+                }
+                div {
+                    class: "contains-columns",
+                    style: "margin-left: 10px;",
+                    input {
+                        r#type: "checkbox",
+                        id: "mark-collapsed-tabs",
+                        checked: "{generate_options.mark_collapsed_tabs}",
+                        onchange: move |e| {
+                            log::trace!("Clicked on mark collapsed tabs checkbox {e:?}");
+                            on_mark_collapsed_tabs_change.inspect(|f| f(e.checked()));
+                        },
+                    }
+                    label {
+                        r#for: "mark-collapsed-tabs",
+                        title: "Mark tabs that were hidden inside a collapsed Tree Style Tab or Sidebery tree.",
+                        "Mark collapsed tabs"
+                    }
+                }
+                div {
+                    class: "contains-columns",
+                    style: "margin-left: 10px;",
+                    input {
+                        r#type: "checkbox",
+                        id: "include-counts",
+                        checked: "{generate_options.include_counts}",
+                        onchange: move |e| {
+                            log::trace!("Clicked on include counts checkbox {e:?}");
+                            on_include_counts_change.inspect(|f| f(e.checked()));
+                        },
+                    }
+                    label {
+                        r#for: "include-counts",
+                        title: "Add the number of windows and tabs to the document's title and the number of tabs to each window's heading.",
+                        "Include counts"
+                    }
+                }
+                div {
+                    class: "contains-columns",
+                    style: "margin-left: 10px;",
+                    input {
+                        r#type: "checkbox",
+                        id: "mark-closed-windows",
+                        checked: "{generate_options.mark_closed_windows}",
+                        onchange: move |e| {
+                            log::trace!("Clicked on mark closed windows checkbox {e:?}");
+                            on_mark_closed_windows_change.inspect(|f| f(e.checked()));
+                        },
+                    }
+                    label {
+                        r#for: "mark-closed-windows",
+                        title: "Add \"(closed)\" to the headings of windows that were closed so that they can be told apart from open windows.",
+                        "Mark closed windows"
+                    }
+                }
+                div {
+                    class: "contains-columns",
+                    style: "margin-left: 10px;",
+                    input {
+                        r#type: "checkbox",
+                        id: "deduplicate-links",
+                        checked: "{generate_options.deduplicate_links}",
+                        onchange: move |e| {
+                            log::trace!("Clicked on deduplicate links checkbox {e:?}");
+                            on_deduplicate_links_change.inspect(|f| f(e.checked()));
+                        },
+                    }
+                    label {
+                        r#for: "deduplicate-links",
+                        title: "Only include the first tab with each URL. Tabs nested below a removed tab are kept.",
+                        "Remove duplicate URLs"
+                    }
+                }
+                div {
+                    class: "contains-columns",
+                    style: "margin-left: 10px;",
+                    input {
+                        r#type: "checkbox",
+                        id: "deduplicate-globally",
+                        checked: "{generate_options.deduplicate_globally}",
+                        disabled: Some(true).filter(|_| !generate_options.deduplicate_links),
+                        onchange: move |e| {
+                            log::trace!("Clicked on deduplicate globally checkbox {e:?}");
+                            on_deduplicate_globally_change.inspect(|f| f(e.checked()));
+                        },
+                    }
+                    label {
+                        r#for: "deduplicate-globally",
+                        title: "Also remove tabs whose URL was already included from an earlier window. Otherwise duplicates are only removed within each window.",
+                        "Across windows"
+                    }
+                }
+                div {
+                    class: "contains-columns",
+                    style: "margin-left: 10px;",
+                    input {
+                        r#type: "checkbox",
+                        id: "annotate-languages",
+                        checked: "{generate_options.annotate_languages}",
+                        onchange: move |e| {
+                            log::trace!("Clicked on annotate languages checkbox {e:?}");
+                            on_annotate_languages_change.inspect(|f| f(e.checked()));
+                        },
+                    }
+                    label {
+                        r#for: "annotate-languages",
+                        title: "Add the detected language of each tab's title, for example \"[English]\". Short titles are marked \"[Unknown]\".",
+                        "Title languages"
+                    }
+                }
+                div {
+                    class: "contains-columns",
+                    style: "margin-left: 10px;",
+                    input {
+                        r#type: "checkbox",
+                        id: "include-timestamps",
+                        checked: "{generate_options.include_timestamps}",
+                        onchange: move |e| {
+                            log::trace!("Clicked on include timestamps checkbox {e:?}");
+                            on_include_timestamps_change.inspect(|f| f(e.checked()));
+                        },
+                    }
+                    label {
+                        r#for: "include-timestamps",
+                        title: "Add when each tab was last accessed, in the local time zone.",
+                        "Last accessed times"
+                    }
+                }
+                div {
+                    class: "contains-columns",
+                    style: "margin-left: 10px;",
+                    input {
+                        r#type: "checkbox",
+                        id: "include-container",
+                        checked: "{generate_options.include_container}",
+                        onchange: move |e| {
+                            log::trace!("Clicked on include container checkbox {e:?}");
+                            on_include_container_change.inspect(|f| f(e.checked()));
+                        },
+                    }
+                    label {
+                        r#for: "include-container",
+                        title: "Add the name of the container (from Firefox Multi-Account Containers) that each tab was opened in.",
+                        "Container names"
+                    }
+                }
+                div {
+                    class: "contains-columns",
+                    style: "margin-left: 10px;",
+                    input {
+                        r#type: "checkbox",
+                        id: "pinned-only",
+                        checked: "{generate_options.pinned_only}",
+                        onchange: move |e| {
+                            log::trace!("Clicked on pinned only checkbox {e:?}");
+                            on_pinned_only_change.inspect(|f| f(e.checked()));
+                        },
+                    }
+                    label {
+                        r#for: "pinned-only",
+                        title: "Only include tabs that were pinned in Firefox.",
+                        "Only pinned tabs"
+                    }
+                }
+                div {
+                    class: "contains-columns",
+                    style: "margin-left: 10px;",
+                    input {
+                        r#type: "checkbox",
+                        id: "include-closed-tabs",
+                        checked: "{generate_options.include_closed_tabs}",
+                        onchange: move |e| {
+                            log::trace!("Clicked on include closed tabs checkbox {e:?}");
+                            on_include_closed_tabs_change.inspect(|f| f(e.checked()));
+                        },
+                    }
+                    label {
+                        r#for: "include-closed-tabs",
+                        title: "Include the tabs that were recently closed in each open window, in a section after the window's tabs.",
+                        "Recently closed tabs"
+                    }
+                }
+                div {
+                    class: "contains-columns",
+                    style: "margin-left: 10px;",
+                    input {
+                        r#type: "checkbox",
+                        id: "reproducible",
+                        checked: "{generate_options.reproducible}",
+                        onchange: move |e| {
+                            log::trace!("Clicked on reproducible checkbox {e:?}");
+                            on_reproducible_change.inspect(|f| f(e.checked()));
+                        },
+                    }
+                    label {
+                        r#for: "reproducible",
+                        title: "Sort windows by name and URLs so that saving the same session twice gives identical files, useful for version control. Don't include an export ID when using this.",
+                        "Reproducible"
+                    }
+                }
+                div {
+                    class: "contains-columns",
+                    style: "margin-left: 10px;",
+                    label {
+                        class: "vertically-centered-text",
+                        r#for: "duplicate-names",
+                        title: "What to add to the names of windows that have the same name so that they can be told apart.",
+                        "Same names:"
+                    }
+                    select {
+                        id: "duplicate-names",
+                        style: "margin-left: 5px;",
+                        onchange: move |evt| {
+                            let value = evt.value();
+                            log::trace!("Selected duplicate name strategy: {value:?}");
+                            if let Some(&strategy) = DuplicateNameStrategy::all().iter().find(|s| s.as_str() == value) {
+                                on_duplicate_names_change.inspect(|f| f(strategy));
+                            }
+                        },
+                        for strategy in DuplicateNameStrategy::all().iter().copied() {
+                            option {
+                                value: strategy.as_str(),
+                                selected: Some(generate_options.duplicate_names == strategy),
+                                "{strategy.as_str()}"
+                            }
+                        }
+                    }
+                }
+                div {
+                    class: "contains-columns",
+                    style: "margin-left: 10px;",
+                    label {
+                        class: "vertically-centered-text",
+                        r#for: "tab-sort",
+                        title: "Order of the tabs inside each window. Windows that are shown as a tree of tabs keep their order.",
+                        "Sort tabs by:"
+                    }
+                    select {
+                        id: "tab-sort",
+                        style: "margin-left: 5px;",
+                        onchange: move |evt| {
+                            let value = evt.value();
+                            log::trace!("Selected tab sort: {value:?}");
+                            if let Some(&tab_sort) = TabSort::all().iter().find(|s| s.as_str() == value) {
+                                on_tab_sort_change.inspect(|f| f(tab_sort));
+                            }
+                        },
+                        for tab_sort in TabSort::all().iter().copied() {
+                            option {
+                                value: tab_sort.as_str(),
+                                selected: Some(generate_options.tab_sort == tab_sort),
+                                "{tab_sort.as_str()}"
+                            }
+                        }
+                    }
+                }
+                div {
+                    class: "contains-columns",
+                    style: "margin-left: 10px;",
+                    label {
+                        class: "vertically-centered-text",
+                        r#for: "group-by",
+                        title: "List tabs under their browser window or under their website. Grouping by domain combines the tabs of all selected windows and doesn't keep trees of tabs.",
+                        "Group tabs by:"
+                    }
+                    select {
+                        id: "group-by",
+                        style: "margin-left: 5px;",
+                        onchange: move |evt| {
+                            let value = evt.value();
+                            log::trace!("Selected tab grouping: {value:?}");
+                            if let Some(&group_by) = GroupBy::all().iter().find(|g| g.as_str() == value) {
+                                on_group_by_change.inspect(|f| f(group_by));
+                            }
+                        },
+                        for group_by in GroupBy::all().iter().copied() {
+                            option {
+                                value: group_by.as_str(),
+                                selected: Some(generate_options.group_by == group_by),
+                                "{group_by.as_str()}"
+                            }
+                        }
+                    }
+                }
+                div {
+                    class: "contains-columns",
+                    style: "margin-left: 10px;",
+                    input {
+                        r#type: "checkbox",
+                        id: "window-separator",
+                        checked: "{generate_options.window_separator.is_some()}",
+                        onchange: move |e| {
+                            log::trace!("Clicked on window separator checkbox {e:?}");
+                            let separator = e.checked().then(|| DEFAULT_WINDOW_SEPARATOR.to_owned());
+                            on_window_separator_change.inspect(|f| f(separator));
+                        },
+                    }
+                    label {
+                        r#for: "window-separator",
+                        title: "Put a separator line between windows in the preview and when copying links to the clipboard.",
+                        "Window separator"
+                    }
+                    if let Some(separator) = generate_options.window_separator.clone() {
+                        input {
+                            r#type: "text",
+                            style: "width: 8em; margin-left: 5px;",
+                            value: "{separator}",
+                            onchange: move |e| {
+                                let separator = e.value();
+                                log::trace!("Changed window separator to {separator:?}");
+                                on_window_separator_change.inspect(|f| f(Some(separator)));
+                            },
+                        }
+                    }
+                }
+                if matches!(output_options.format, OutputFormat::TEXT | OutputFormat::MARKDOWN) {
+                    div {
+                        class: "contains-columns",
+                        style: "margin-left: 10px;",
+                        label {
+                            class: "vertically-centered-text",
+                            r#for: "custom-page-break",
+                            title: "Line that is put between windows in saved text and Markdown files. Leave empty to only separate windows with empty lines.",
+                            "Page break:"
+                        }
+                        input {
+                            r#type: "text",
+                            id: "custom-page-break",
+                            placeholder: "----8<----",
+                            style: "width: 8em; margin-left: 5px;",
+                            value: "{generate_options.custom_page_break}",
+                            onchange: move |e| {
+                                let page_break = e.value();
+                                log::trace!("Changed custom page break to {page_break:?}");
+                                on_custom_page_break_change.inspect(|f| f(page_break));
+                            },
+                        }
+                    }
+                }
+                div {
+                    class: "contains-columns",
+                    style: "margin-left: 10px;",
+                    input {
+                        r#type: "checkbox",
+                        id: "include-export-id",
+                        checked: "{output_options.include_export_id}",
+                        onchange: move |e| {
+                            log::trace!("Clicked on include export ID checkbox {e:?}");
+                            on_include_export_id_change.inspect(|f| f(e.checked()));
+                        },
+                    }
+                    label {
+                        r#for: "include-export-id",
+                        title: "Add a unique ID to the top of saved files so they can be matched with log messages. Not supported for RTF, CSV and PDFs generated from HTML.",
+                        "Include export ID"
+                    }
+                }
+                if matches!(output_options.format, OutputFormat::TEXT | OutputFormat::MARKDOWN) {
+                    div {
+                        class: "contains-columns",
+                        style: "margin-left: 10px;",
+                        label {
+                            class: "vertically-centered-text",
+                            r#for: "text-encoding",
+                            title: "Character encoding of saved files. Characters that can't be represented in Windows-1252 are replaced with \"?\".",
+                            "Encoding:"
+                        }
+                        select {
+                            id: "text-encoding",
+                            style: "margin-left: 5px;",
+                            onchange: move |evt| {
+                                let value = evt.value();
+                                log::trace!("Selected text encoding: {value:?}");
+                                if let Some(&encoding) = TextEncoding::all().iter().find(|e| e.as_str() == value) {
+                                    on_text_encoding_change.inspect(|f| f(encoding));
+                                }
+                            },
+                            for encoding in TextEncoding::all().iter().copied() {
+                                option {
+                                    value: encoding.as_str(),
+                                    selected: Some(output_options.text_encoding == encoding),
+                                    "{encoding.as_str()}"
+                                }
+                            }
+                        }
+                    }
+                }
+                if output_options.format.is_pdf() {
+                    div {
+                        class: "contains-columns",
+                        style: "margin-left: 10px;",
+                        // The other PDF backends don't support page options:
+                        if output_options.format.is_typst() {
+                            label {
+                                class: "vertically-centered-text",
+                                r#for: "page-size",
+                                title: "Paper size of the PDF.",
+                                "Page size:"
+                            }
+                            select {
+                                id: "page-size",
+                                style: "margin-left: 5px;",
+                                onchange: move |evt| {
+                                    let value = evt.value();
+                                    log::trace!("Selected page size: {value:?}");
+                                    if let Some(&page_size) = PageSize::all().iter().find(|s| s.as_str() == value) {
+                                        on_page_size_change.inspect(|f| f(page_size));
+                                    }
+                                },
+                                for page_size in PageSize::all().iter().copied() {
+                                    option {
+                                        value: page_size.as_str(),
+                                        selected: Some(output_options.page_size == page_size),
+                                        "{page_size.as_str()}"
+                                    }
+                                }
+                            }
+                            label {
+                                class: "vertically-centered-text",
+                                r#for: "page-orientation",
+                                title: "Orientation of the PDF's pages.",
+                                style: "margin-left: 10px;",
+                                "Orientation:"
+                            }
+                            select {
+                                id: "page-orientation",
+                                style: "margin-left: 5px;",
+                                onchange: move |evt| {
+                                    let value = evt.value();
+                                    log::trace!("Selected page orientation: {value:?}");
+                                    on_landscape_change.inspect(|f| f(value == "landscape"));
+                                },
+                                option {
+                                    value: "portrait",
+                                    selected: Some(!output_options.landscape),
+                                    "Portrait"
+                                }
+                                option {
+                                    value: "landscape",
+                                    selected: Some(output_options.landscape),
+                                    "Landscape"
+                                }
+                            }
+                            label {
+                                class: "vertically-centered-text",
+                                r#for: "page-margins",
+                                title: "Space between the content and the edges of each page.",
+                                style: "margin-left: 10px;",
+                                "Margins (mm):"
+                            }
+                            input {
+                                r#type: "number",
+                                id: "page-margins",
+                                min: "0",
+                                max: "50",
+                                step: "any",
+                                style: "width: 5em; margin-left: 5px;",
+                                value: "{output_options.margins_mm}",
+                                onchange: move |e| {
+                                    let value = e.value();
+                                    log::trace!("Changed PDF margins to {value:?}");
+                                    if let Ok(margins) = value.trim().parse::<f32>() {
+                                        on_margins_change.inspect(|f| f(margins.clamp(0.0, 50.0)));
+                                    }
+                                },
+                            }
+                        }
+                        if output_options.format == OutputFormat::PDF_CHROMIUM_OXIDE {
+                            label {
+                                class: "vertically-centered-text",
+                                r#for: "pdf-render-retries",
+                                title: "How many times to try again if the headless browser that renders the PDF fails to start.",
+                                style: "margin-left: 10px;",
+                                "Retries:"
+                            }
+                            input {
+                                r#type: "number",
+                                id: "pdf-render-retries",
+                                min: "0",
+                                max: "10",
+                                style: "width: 4em; margin-left: 5px;",
+                                value: "{output_options.pdf_render_retries}",
+                                onchange: move |e| {
+                                    let value = e.value();
+                                    log::trace!("Changed PDF render retries to {value:?}");
+                                    if let Ok(retries) = value.trim().parse::<u32>() {
+                                        on_pdf_render_retries_change.inspect(|f| f(retries.min(10)));
+                                    }
+                                },
+                            }
+                        }
+                    }
+                }
+                if output_options.format.is_typst() {
+                    div {
+                        class: "contains-columns",
+                        style: "margin-left: 10px;",
+                        label {
+                            class: "vertically-centered-text",
+                            r#for: "typst-font",
+                            title: "Font used by the Typst document. Pick a font that supports all characters in your tab titles.",
+                            "Font:"
+                        }
+                        select {
+                            id: "typst-font",
+                            style: "margin-left: 5px;",
+                            onchange: move |evt| {
+                                let value = evt.value();
+                                log::trace!("Selected Typst font: {value:?}");
+                                on_typst_font_change.inspect(|f| f(Some(value).filter(|v| !v.is_empty())));
+                            },
+                            option {
+                                value: "",
+                                selected: Some(generate_options.typst_font.is_none()),
+                                "Default"
+                            }
+                            for font in typst_fonts.iter() {
+                                option {
+                                    value: "{font}",
+                                    selected: Some(generate_options.typst_font.as_ref() == Some(font)),
+                                    "{font}"
+                                }
+                            }
+                        }
+                    }
+                    div {
+                        class: "contains-columns",
+                        style: "margin-left: 10px;",
+                        input {
+                            r#type: "checkbox",
+                            id: "pdf-outline",
+                            checked: "{generate_options.pdf_outline}",
+                            onchange: move |e| {
+                                log::trace!("Clicked on PDF outline checkbox {e:?}");
+                                on_pdf_outline_change.inspect(|f| f(e.checked()));
+                            },
+                        }
+                        label {
+                            r#for: "pdf-outline",
+                            title: "Put the table of contents on its own page and add bookmarks for each window.",
+                            "Outline page"
+                        }
+                    }
+                    div {
+                        class: "contains-columns",
+                        style: "margin-left: 10px;",
+                        input {
+                            r#type: "checkbox",
+                            id: "custom-typst-template",
+                            checked: "{generate_options.typst_template.is_some()}",
+                            onchange: move |e| {
+                                log::trace!("Clicked on custom Typst template checkbox {e:?}");
+                                on_typst_template_change.inspect(|f| f(Some(String::new()).filter(|_| e.checked())));
+                            },
+                        }
+                        label {
+                            r#for: "custom-typst-template",
+                            title: "Typst code that is placed before the generated document. Use set and show rules to change fonts, margins and headings.",
+                            "Custom template"
+                        }
+                    }
+                    if let Some(template) = &generate_options.typst_template {
+                        textarea {
+                            id: "typst-template",
+                            style: "margin-left: 10px; min-width: 20em; min-height: 4em;",
+                            placeholder: "#set text(size: 10pt)\n#show heading: set text(fill: blue)",
+                            value: "{template}",
+                            onchange: move |e| {
+                                log::trace!("Changed custom Typst template");
+                                on_typst_template_change.inspect(|f| f(Some(e.value())));
+                            },
+                        }
+                    }
+                }
+                div { class: "spacer", style: "flex: 1 1 auto;" }
+                label {
+                    class: "vertically-centered-text",
+                    r#for: "theme",
+                    title: "Colors of the user interface. \"System\" follows the system's dark mode setting.",
+                    "Theme:"
+                }
+                select {
+                    id: "theme",
+                    style: "margin-left: 5px; margin-right: 10px;",
+                    onchange: move |evt| {
+                        let value = evt.value();
+                        log::trace!("Selected theme: {value:?}");
+                        if let Some(&theme) = Theme::all().iter().find(|t| t.as_str() == value) {
+                            on_theme_change.inspect(|f| f(theme));
+                        }
+                    },
+                    for option_theme in Theme::all().iter().copied() {
+                        option {
+                            value: option_theme.as_str(),
+                            selected: Some(theme == option_theme),
+                            "{option_theme.as_str()}"
+                        }
+                    }
+                }
+                button {
+                    title: "Reset all output options to their default values.",
+                    onclick: move |_| {
+                        on_reset_options.inspect(|f| f(()));
+                    },
+                    "Reset options"
+                }
+            }
+            div { class: "spacer", style: "flex: 0 1 auto; height: 5px;" }
+            div { class: "contains-columns",
+                button {
+                    title: "Copy the links in the selected output format. HTML is copied as rich text that keeps its formatting when pasted into editors and PDF formats are copied as plain text.",
+                    onclick: move |_| {
+                        on_copy_to_clipboard.inspect(|f| f(()));
+                    },
+                    "Copy links to clipboard"
+                }
+                div { class: "spacer", style: "flex: 1 1 auto;" }
+                fieldset {
+                    class: "contains-rows output-format-group output-format-drop-down",
+                    style: "margin: 6px;",
+                    title: get_title_for_format(output_options.format),
+                    legend { "Output format" }
+                    select {
+                        id: "output-format",
+                        style: "flex: 1 1 auto;",
+                        onchange: move |evt| {
+                            let Some(on_output_format_change) = &on_output_format_change else {
+                                return;
+                            };
+                            let value = evt.value();
+                            if let Some(&format) = OutputFormat::all().iter().find(|f| f.as_str() == value) {
+                                on_output_format_change(format);
+                            }
+                        },
+                        for format in OutputFormat::all().iter().copied() {
+                            option {
+                                value: format.as_str(),
+                                selected: Some(output_options.format == format),
+                                title: get_title_for_format(format),
+                                "{format.as_str()}"
+                            }
+                        }
+                    }
+                }
+                fieldset {
+                    class: "contains-columns output-format-group output-format-radio-buttons",
+                    style: "margin: 6px; align-items: baseline; align-self: stretch;",
+                    legend { "Output format" }
+                    input {
+                        r#type: "radio",
+                        name: "output-format",
+                        id: "output-format-text",
+                        checked: Some(output_options.format == OutputFormat::TEXT),
                         title: get_title_for_format(OutputFormat::TEXT),
                         onclick: move |_| {
                             log::trace!("Checked Text output format");
@@ -721,6 +2178,45 @@ fn OutputPanel(props: OutputPanelProps) -> Element {
                         "PDF"
                     }
                 }
+                // The first page is rendered by the host, only Typst can do that:
+                if output_options.format == OutputFormat::PDF_TYPST
+                    && host_commands::has_host_access()
+                {
+                    button {
+                        title: "Generate the PDF and show its first page in the preview area without saving it.",
+                        style: "margin-right: 5px;",
+                        onclick: move |_| {
+                            on_preview_pdf.inspect(|f| f(()));
+                        },
+                        "Preview PDF"
+                    }
+                }
+                if cfg!(target_family = "wasm") {
+                    button {
+                        title: "Generate the document and open it in a new browser tab without saving it.",
+                        style: "margin-right: 5px;",
+                        onclick: move |_| {
+                            on_open_in_new_tab.inspect(|f| f(()));
+                        },
+                        "Open in new tab"
+                    }
+                }
+                button {
+                    title: "Save the URLs of all tabs in the loaded file and the file at the input path without any duplicates.",
+                    style: "margin-right: 5px;",
+                    onclick: move |_| {
+                        on_export_combined_urls.inspect(|f| f(()));
+                    },
+                    "Save combined URLs"
+                }
+                button {
+                    title: "Save only the tabs that weren't included the last time this file was saved this way.",
+                    style: "margin-right: 5px;",
+                    onclick: move |_| {
+                        on_export_new_since_last.inspect(|f| f(()));
+                    },
+                    "Save new tabs"
+                }
                 button {
                     onclick: move |_| {
                         on_write_to_file.inspect(|f| f(()));
@@ -735,35 +2231,632 @@ fn OutputPanel(props: OutputPanelProps) -> Element {
 #[derive(Debug, Clone)]
 pub enum Message {
     SetInputPath(String),
+    /// Set the input path to a path or `file://` URL from the clipboard.
+    PasteInputPath,
     UpdateInputPath(PathId),
     SyncInputPath(String, PathId),
     OpenWizard,
     CloseWizard,
+    /// Open sessionstore files with this program.
+    RegisterFileTypes,
     FetchedFirefoxProfiles(Vec<FirefoxProfileInfo>),
     SyncLoadedPath(String, PathId),
     SetPreview(String),
+    SetTabCounts(Option<TabCounts>),
     LoadInputPath(String),
     LoadNewData,
+    /// Read the file that the current data was loaded from again.
+    ReloadLoadedFile,
+    /// Forget all loaded data and clear the preview.
+    ClearLoadedData,
+    /// Copy the uncompressed JSON of the current data to the clipboard.
+    CopyRawJson,
     SetTabGroups {
         open: Vec<String>,
         closed: Vec<String>,
         open_selected: Vec<u32>,
         closed_selected: Vec<u32>,
+        /// The data and naming strategy that the windows were listed for,
+        /// `None` if the list was cleared.
+        source: Option<(DataId, DuplicateNameStrategy)>,
     },
     SetSelectedTabGroups {
         open: Vec<u32>,
         closed: Vec<u32>,
+        /// `true` if the user selected the windows, only then can the change
+        /// be undone.
+        user_change: bool,
     },
     SetOutputPath(String),
+    ValidateSavePath,
+    SetTheme(Theme),
+    /// Result of checking a save path, ignored if the path has changed since.
+    SetSavePathStatus(String, SavePathStatus),
     /// Backend changed its output path.
     SyncOutputPath(String),
     SetOverwrite(bool),
     SetCreateFolder(bool),
+    SetGenerationTimeout(Option<u64>),
+    SetIncludeExportId(bool),
+    SetRestoreBundle(bool),
+    SetSplitPerWindow(bool),
+    SetRevealAfterSave(bool),
+    SetSavePreview(bool),
+    SetTextEncoding(TextEncoding),
+    SetPageSize(PageSize),
+    SetLandscape(bool),
+    SetMargins(f32),
+    SetPdfRenderRetries(u32),
+    SetDuplicateNames(DuplicateNameStrategy),
+    SetTabSort(TabSort),
+    SetGroupBy(GroupBy),
+    /// Domain filters were loaded from the backend.
+    SetDomainFilters(DomainFilters),
+    SetSearchFilter(String),
+    /// The UI state that was saved before the last restart, or `None` if
+    /// nothing was saved.
+    RestoreUiState(Option<SavedUiState>),
+    /// Fetch the recent files from the backend.
+    RefreshRecentPaths,
+    SetRecentPaths(Vec<String>),
+    ClearRecentPaths,
+    /// The user changed the domain filters, they should be saved.
+    EditDomainFilters(DomainFilters),
     SetOutputFormat(OutputFormat),
+    SetMarkdownStyle(MarkdownStyle),
+    SetMarkdownTocLinks(bool),
+    SetUseTst(bool),
+    SetUseSidebery(bool),
+    SetFlatLinks(bool),
+    SetMarkCollapsedTabs(bool),
+    SetIncludeCounts(bool),
+    SetMarkClosedWindows(bool),
+    SetDeduplicateLinks(bool),
+    SetDeduplicateGlobally(bool),
+    SetAnnotateLanguages(bool),
+    SetIncludeTimestamps(bool),
+    SetPinnedOnly(bool),
+    SetIncludeClosedTabs(bool),
+    SetIncludeContainer(bool),
+    SetReproducible(bool),
+    SetHtmlWindowFilter(bool),
+    SetTypstFont(Option<String>),
+    SetPdfOutline(bool),
+    SetTypstTemplate(Option<String>),
+    SetWindowSeparator(Option<String>),
+    SetCustomPageBreak(String),
+    FetchedTypstFonts(Vec<String>),
+    ResetOptions,
+    SetRecordTimings(bool),
+    /// Largest size in MiB that loaded data may have once decompressed.
+    SetMaxDecompressedSize(u64),
+    FetchedMaxDecompressedSize(u64),
+    /// Revert the latest change to the options or the window selection.
+    Undo,
+    /// Reapply the latest change that was undone.
+    Redo,
     SetStatus(String),
+    /// Report a failure or a warning, unlike other status messages it is kept
+    /// until the user dismisses it.
+    SetError(String),
+    /// Progress of the current operation, only shown in the status bar and
+    /// not as a notification since it is updated often.
+    SetProgress(String),
+    DismissNotification(u64),
+    /// The step that the latest preview is working on.
+    SetStage(Option<PreviewStage>),
+    /// Data that couldn't be parsed, the user can choose to recover the
+    /// windows that are intact.
+    SetDamagedData(Option<DataId>),
+    RecoverWindows,
     FetchedOutputFormatInfo(Vec<(OutputFormat, String)>),
+    /// Generate a PDF and show it instead of the text preview.
+    PreviewPdf,
+    OpenInNewTab,
+    /// A `data:` URL with an image of the PDF's first page that should be
+    /// previewed, `None` to show the text preview again.
+    SetPdfPreview(Option<String>),
+    /// Generate links for a selected window with each tree source.
+    CompareTreeSources,
+    FindOldestTabs,
+    FindExtensions,
+    ShowSessionStats,
+    SetWindowCheckboxList(bool),
+    SetOldestTabs(Vec<TabEntry>),
+    /// Links generated with each tree source, empty to show the normal
+    /// preview again.
+    SetTreeSourceComparison(Vec<(TreeSource, String)>),
+    SelectTreeSourceComparison(usize),
     CopyLinksToClipboard,
     WriteLinksToFile,
+    ExportCombinedUrls,
+    ExportNewSinceLast,
+}
+impl Message {
+    /// The message changes options or selects windows on behalf of the user,
+    /// so the change can be undone with [`Message::Undo`].
+    fn is_undoable(&self) -> bool {
+        matches!(
+            self,
+            Message::SetSelectedTabGroups { user_change: true, .. }
+                | Message::SetOverwrite(_)
+                | Message::SetCreateFolder(_)
+                | Message::SetGenerationTimeout(_)
+                | Message::SetIncludeExportId(_)
+                | Message::SetRestoreBundle(_)
+                | Message::SetSplitPerWindow(_)
+                | Message::SetRevealAfterSave(_)
+                | Message::SetSavePreview(_)
+                | Message::SetTextEncoding(_)
+                | Message::SetPageSize(_)
+                | Message::SetLandscape(_)
+                | Message::SetMargins(_)
+                | Message::SetPdfRenderRetries(_)
+                | Message::SetDuplicateNames(_)
+                | Message::SetTabSort(_)
+                | Message::SetGroupBy(_)
+                | Message::SetOutputFormat(_)
+                | Message::SetMarkdownStyle(_)
+                | Message::SetMarkdownTocLinks(_)
+                | Message::SetUseTst(_)
+                | Message::SetUseSidebery(_)
+                | Message::SetFlatLinks(_)
+                | Message::SetMarkCollapsedTabs(_)
+                | Message::SetIncludeCounts(_)
+                | Message::SetMarkClosedWindows(_)
+                | Message::SetDeduplicateLinks(_)
+                | Message::SetDeduplicateGlobally(_)
+                | Message::SetAnnotateLanguages(_)
+                | Message::SetIncludeTimestamps(_)
+                | Message::SetPinnedOnly(_)
+                | Message::SetIncludeClosedTabs(_)
+                | Message::SetIncludeContainer(_)
+                | Message::SetReproducible(_)
+                | Message::SetHtmlWindowFilter(_)
+                | Message::SetTypstFont(_)
+                | Message::SetPdfOutline(_)
+                | Message::SetTypstTemplate(_)
+                | Message::SetWindowSeparator(_)
+                | Message::SetCustomPageBreak(_)
+                | Message::ResetOptions
+        )
+    }
+}
+
+/// Describe how long each step of processing a file took, for example
+/// `"load 5 ms, parse 120 ms"`.
+fn format_timings(timings: &StepTimings) -> String {
+    [
+        ("load", timings.load_ms),
+        ("decompress", timings.decompress_ms),
+        ("parse", timings.parse_ms),
+        ("generate", timings.generate_ms),
+    ]
+    .into_iter()
+    .filter_map(|(step, ms)| Some(format!("{step} {} ms", ms?)))
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+/// Describe how much of a file has been read, for example
+/// `"45%, 90.0 of 200.0 MiB"`.
+#[cfg(not(target_family = "wasm"))]
+fn format_load_progress(progress: host_commands::LoadProgress) -> String {
+    const MIB: f64 = 1024.0 * 1024.0;
+    let percent = (progress.bytes_read.saturating_mul(100) / progress.total_bytes.max(1)).min(100);
+    format!(
+        "{percent}%, {:.1} of {:.1} MiB",
+        progress.bytes_read as f64 / MIB,
+        progress.total_bytes as f64 / MIB
+    )
+}
+
+/// Format a number with thousands separators, for example `"1,234"`.
+fn format_count(count: u32) -> String {
+    let digits = count.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+    for (ix, digit) in digits.chars().enumerate() {
+        if ix > 0 && (digits.len() - ix) % 3 == 0 {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+    formatted
+}
+
+/// Describe problems with the save path that would make saving fail or give an
+/// unexpected result. `None` if there are no problems.
+fn save_path_hint(status: &SavePathStatus, output_options: &OutputOptions) -> Option<String> {
+    let mut problems = Vec::new();
+    if !status.folder_exists && !output_options.create_folder {
+        problems.push("The folder doesn't exist.".to_owned());
+    }
+    if status.file_exists && !output_options.overwrite && !output_options.split_per_window {
+        problems.push("A file with this name already exists.".to_owned());
+    }
+    if let Some(ext) = &status.expected_extension {
+        problems.push(format!(
+            "The file extension doesn't match the selected format (.{ext})."
+        ));
+    }
+    (!problems.is_empty()).then(|| problems.join(" "))
+}
+
+/// Describe how many tabs are selected, for example
+/// `"1,234 tabs (3 windows), 12 closed tabs (2 windows)"`.
+fn format_tab_counts(counts: &TabCounts) -> String {
+    fn plural(count: u32, singular: &str, plural: &str) -> String {
+        format!(
+            "{} {}",
+            format_count(count),
+            if count == 1 { singular } else { plural }
+        )
+    }
+    let mut text = format!(
+        "{} ({})",
+        plural(counts.open_tabs, "tab", "tabs"),
+        plural(counts.open_windows, "window", "windows")
+    );
+    if counts.closed_windows > 0 {
+        text.push_str(&format!(
+            ", {} ({})",
+            plural(counts.closed_tabs, "closed tab", "closed tabs"),
+            plural(counts.closed_windows, "window", "windows")
+        ));
+    }
+    text
+}
+
+/// Lines that fit on a PDF page, used to estimate the number of pages.
+const LINES_PER_PAGE: usize = 50;
+/// Characters that fit on a line of a PDF page, longer lines wrap.
+const CHARS_PER_LINE: usize = 90;
+
+/// Length of the generated preview text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct PreviewLength {
+    words: usize,
+    characters: usize,
+    /// Lines once long lines have been wrapped to fit on a PDF page.
+    wrapped_lines: usize,
+}
+impl PreviewLength {
+    fn new(preview: &str) -> Self {
+        Self {
+            words: preview.split_whitespace().count(),
+            characters: preview.chars().count(),
+            wrapped_lines: preview
+                .lines()
+                .map(|line| line.chars().count().div_ceil(CHARS_PER_LINE).max(1))
+                .sum(),
+        }
+    }
+    /// A rough estimate of how many pages a PDF with the preview's content
+    /// would have.
+    fn estimated_pages(&self) -> usize {
+        self.wrapped_lines.div_ceil(LINES_PER_PAGE).max(1)
+    }
+}
+
+/// Describe the length of the preview, for example
+/// `"1,234 words, 5,678 characters, ~12 pages"`. The page estimate is only
+/// included for PDF formats.
+fn format_preview_length(length: &PreviewLength, is_pdf: bool) -> String {
+    let count = |count: usize| format_count(count.try_into().unwrap_or(u32::MAX));
+    let mut text = format!(
+        "{} words, {} characters",
+        count(length.words),
+        count(length.characters)
+    );
+    if is_pdf {
+        let pages = length.estimated_pages();
+        text.push_str(&format!(
+            ", ~{} {}",
+            count(pages),
+            if pages == 1 { "page" } else { "pages" }
+        ));
+    }
+    text
+}
+
+/// Describe the size of a loaded file, for example
+/// `"12.3 MiB, 5 windows, 1,234 tabs"`. `None` if nothing is known yet.
+fn format_file_summary(info: &host_commands::FileInfo) -> Option<String> {
+    const MIB: f64 = 1024.0 * 1024.0;
+    let parts = [
+        info.uncompressed_bytes
+            .map(|bytes| format!("{:.1} MiB", bytes as f64 / MIB)),
+        info.window_count.map(|count| {
+            format!("{} {}", format_count(count), if count == 1 { "window" } else { "windows" })
+        }),
+        info.tab_count.map(|count| {
+            format!("{} {}", format_count(count), if count == 1 { "tab" } else { "tabs" })
+        }),
+    ];
+    let parts = parts.into_iter().flatten().collect::<Vec<_>>();
+    (!parts.is_empty()).then(|| parts.join(", "))
+}
+
+/// Describe how long ago a time in milliseconds since the Unix epoch was, for
+/// example `"42 days ago"`.
+fn format_age(timestamp_ms: u64) -> String {
+    #[cfg(target_family = "wasm")]
+    let now_ms = js_sys::Date::now() as u64;
+    #[cfg(not(target_family = "wasm"))]
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
+
+    let hours = now_ms.saturating_sub(timestamp_ms) / (60 * 60 * 1000);
+    match hours {
+        0 => "less than an hour ago".to_owned(),
+        1..24 => format!("{hours} hours ago"),
+        _ => format!("{} days ago", hours / 24),
+    }
+}
+
+/// The text shown for a window in the window list, for example
+/// `"Window 1 (87 tabs)"`.
+fn window_label(group: &host_commands::TabGroup) -> String {
+    format!(
+        "{} ({} {})",
+        group.name,
+        format_count(group.tab_count),
+        if group.tab_count == 1 { "tab" } else { "tabs" }
+    )
+}
+
+/// Describe statistics about a session, for example
+/// `"2 open windows, 1 closed window, 87 tabs, 30 domains, 3 pinned tabs, 0 reader view tabs"`.
+fn format_session_stats(stats: &SessionStats) -> String {
+    [
+        (stats.open_windows, "open window", "open windows"),
+        (stats.closed_windows, "closed window", "closed windows"),
+        (stats.total_tabs, "tab", "tabs"),
+        (stats.unique_domains, "domain", "domains"),
+        (stats.pinned_tabs, "pinned tab", "pinned tabs"),
+        (stats.reader_mode_tabs, "reader view tab", "reader view tabs"),
+    ]
+    .into_iter()
+    .map(|(count, singular, plural)| {
+        format!("{} {}", format_count(count), if count == 1 { singular } else { plural })
+    })
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+/// Describe a Firefox profile in the wizard, for example
+/// `"abc123.default-release (default, modified 2 days ago)"`.
+fn format_profile_label(profile: &FirefoxProfileInfo) -> String {
+    let mut notes = Vec::new();
+    if profile.is_default {
+        notes.push("default".to_owned());
+    }
+    if let Some(modified_at) = profile.modified_at {
+        notes.push(format!("modified {}", format_age(modified_at * 1000)));
+    }
+    if notes.is_empty() {
+        profile.name.clone()
+    } else {
+        format!("{} ({})", profile.name, notes.join(", "))
+    }
+}
+
+/// List tabs with how long ago they were used, the oldest first.
+fn format_oldest_tabs(tabs: &[TabEntry]) -> String {
+    tabs.iter()
+        .map(|tab| {
+            let age = tab
+                .last_accessed
+                .map(format_age)
+                .unwrap_or_else(|| "never used".to_owned());
+            format!(
+                "{age}: {}\n    {}\n    in {}{}\n",
+                tab.title,
+                tab.url,
+                if tab.is_closed { "closed window " } else { "window " },
+                tab.window_name
+            )
+        })
+        .collect()
+}
+
+/// How many tabs are shown when finding the least recently used tabs.
+const OLDEST_TABS_LIMIT: u32 = 50;
+
+/// Bytes in a mebibyte, the unit of the decompression size limit.
+const MIB: u64 = 1024 * 1024;
+
+/// Counts calls to [`State::generate_preview`] so that a preview can check if
+/// a newer preview has been started since it began.
+#[derive(Debug, Default)]
+struct PreviewGeneration(Arc<AtomicU64>);
+impl PreviewGeneration {
+    /// Start a new preview, making all earlier previews outdated.
+    fn start(&self) -> PreviewToken {
+        PreviewToken {
+            generation: self.0.fetch_add(1, Ordering::SeqCst) + 1,
+            latest: self.0.clone(),
+        }
+    }
+}
+
+/// Identifies a preview that was started by [`PreviewGeneration::start`].
+#[derive(Debug, Clone)]
+struct PreviewToken {
+    generation: u64,
+    latest: Arc<AtomicU64>,
+}
+impl PreviewToken {
+    /// No newer preview has been started.
+    fn is_current(&self) -> bool {
+        self.latest.load(Ordering::SeqCst) == self.generation
+    }
+}
+
+/// How many changes can be undone.
+const UNDO_LIMIT: usize = 50;
+
+/// Script that returns `true` if the focused element is edited as text, such
+/// elements have their own undo history. Checkboxes and lists keep the undo
+/// shortcuts since they are how options are changed.
+const IS_EDITING_TEXT_SCRIPT: &str = r#"
+    const element = document.activeElement;
+    if (!element) return false;
+    if (element.isContentEditable || element.tagName === "TEXTAREA") return true;
+    const notText = ["checkbox", "radio", "button", "submit", "reset", "range", "color", "file"];
+    return element.tagName === "INPUT" && !notText.includes(element.type);
+"#;
+
+/// The options and window selection that the user changed, see
+/// [`State::undo_history`]. Domain filters and the search filter aren't
+/// included since they are edited as text.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct UndoSnapshot {
+    output_options: OutputOptions,
+    generate_options: GenerateOptions,
+    selected_open_window_groups: Vec<u32>,
+    selected_closed_window_groups: Vec<u32>,
+}
+
+/// The steps that [`State::generate_preview`] goes through, in order. Steps
+/// are skipped if they were already done for the loaded data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PreviewStage {
+    Reading,
+    Decompressing,
+    Parsing,
+    Generating,
+}
+impl PreviewStage {
+    pub fn all() -> &'static [Self] {
+        &[
+            Self::Reading,
+            Self::Decompressing,
+            Self::Parsing,
+            Self::Generating,
+        ]
+    }
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Reading => "Reading",
+            Self::Decompressing => "Decompressing",
+            Self::Parsing => "Parsing",
+            Self::Generating => "Generating",
+        }
+    }
+}
+
+/// How many status messages are shown as notifications at the same time.
+const NOTIFICATION_LIMIT: usize = 5;
+/// Notifications that aren't errors are dismissed after this long.
+const NOTIFICATION_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(8);
+
+/// A status message that is shown in the notification area, see
+/// [`State::set_status`].
+#[derive(Debug, Clone, PartialEq)]
+struct Notification {
+    id: u64,
+    text: String,
+    /// Local time when the status was set, for example `"14:05:09"`.
+    time: String,
+    /// Errors are kept until the user dismisses them.
+    is_error: bool,
+}
+
+/// Limit `generate_options` to the selected windows. All windows are included
+/// if none are selected.
+fn with_selected_windows(
+    generate_options: GenerateOptions,
+    open_group_indexes: Vec<u32>,
+    closed_group_indexes: Vec<u32>,
+) -> GenerateOptions {
+    let has_any_filter = !open_group_indexes.is_empty() || !closed_group_indexes.is_empty();
+    GenerateOptions {
+        open_group_indexes: Some(open_group_indexes).filter(|_| has_any_filter),
+        closed_group_indexes: Some(closed_group_indexes),
+        ..generate_options
+    }
+}
+
+/// Wait without blocking the UI, Tokio can't be used for this on the web.
+async fn sleep(duration: std::time::Duration) {
+    #[cfg(target_family = "wasm")]
+    {
+        let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+            if let Some(win) = web_sys::window() {
+                let _ = win.set_timeout_with_callback_and_timeout_and_arguments_0(
+                    &resolve,
+                    duration.as_millis() as i32,
+                );
+            }
+        });
+        let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+    }
+    #[cfg(not(target_family = "wasm"))]
+    tokio::time::sleep(duration).await;
+}
+
+#[cfg(target_family = "wasm")]
+thread_local! {
+    /// The tab opened by [`open_pending_tab`] or why it couldn't be opened.
+    static PENDING_TAB: std::cell::RefCell<Option<Result<web_sys::Window, String>>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+/// Open an empty tab that a generated document can be shown in later. Popup
+/// blockers only allow this while a click is handled, so it can't wait until
+/// the document has been generated.
+#[cfg(target_family = "wasm")]
+fn open_pending_tab() {
+    let tab = web_sys::window()
+        .ok_or_else(|| "no browser window".to_owned())
+        .and_then(|window| {
+            window
+                .open_with_url_and_target("", "_blank")
+                .map_err(|e| format!("{e:?}"))
+        })
+        .and_then(|tab| tab.ok_or_else(|| "the browser blocked the new tab".to_owned()));
+    PENDING_TAB.with_borrow_mut(|pending| *pending = Some(tab));
+}
+
+/// Take the tab opened by the latest call to [`open_pending_tab`].
+#[cfg(target_family = "wasm")]
+fn take_pending_tab() -> Result<web_sys::Window, String> {
+    PENDING_TAB
+        .with_borrow_mut(Option::take)
+        .unwrap_or_else(|| Err("no tab was opened".to_owned()))
+}
+
+/// Show the document in a `data:` URL in a tab. Browsers don't allow
+/// navigating to `data:` URLs so the document is shown using a `blob:` URL.
+#[cfg(target_family = "wasm")]
+fn show_in_tab(tab: &web_sys::Window, data_url: &str) -> Result<(), String> {
+    use base64::Engine;
+
+    let (mime_type, data) = data_url
+        .strip_prefix("data:")
+        .and_then(|url| url.split_once(";base64,"))
+        .ok_or("invalid data URL")?;
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .map_err(|e| format!("invalid data URL: {e}"))?;
+
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type(mime_type);
+    let blob = web_sys::Blob::new_with_u8_array_sequence_and_options(
+        &js_sys::Array::of1(&js_sys::Uint8Array::from(data.as_slice())),
+        &options,
+    )
+    .map_err(|e| format!("failed to create blob: {e:?}"))?;
+    // Not revoked since the tab loads it asynchronously, it is released when
+    // this page is closed:
+    let url = web_sys::Url::create_object_url_with_blob(&blob)
+        .map_err(|e| format!("failed to create blob URL: {e:?}"))?;
+    tab.location()
+        .set_href(&url)
+        .map_err(|e| format!("failed to navigate the tab: {e:?}"))
 }
 
 #[derive(Debug)]
@@ -773,16 +2866,78 @@ pub struct State {
     loaded_path: String,
     loaded_path_id: PathId,
     preview: String,
+    /// A `data:` URL with an image of the first page of a generated PDF
+    /// document that is shown instead of the text preview.
+    pdf_preview: Option<String>,
+    /// Number of tabs in the selected windows, shown next to the preview.
+    tab_counts: Option<TabCounts>,
+    /// Length of `preview`, shown in the status bar.
+    preview_length: Option<PreviewLength>,
+    save_path_status: Option<SavePathStatus>,
+    /// Links for a single window generated with each tree source, shown
+    /// instead of the text preview.
+    tree_source_comparison: Vec<(TreeSource, String)>,
+    /// Index into `tree_source_comparison` that is being shown.
+    selected_tree_source: usize,
+    /// The least recently used tabs, shown instead of the text preview.
+    oldest_tabs: Vec<TabEntry>,
     save_path: String,
     output_options: OutputOptions,
+    /// Options used when generating links. The window indexes are taken from
+    /// the selected window groups instead.
+    generate_options: GenerateOptions,
     open_window_groups: Vec<String>,
     closed_window_groups: Vec<String>,
+    /// The data and naming strategy that the window lists were made for, the
+    /// lists don't need to be fetched again while these stay the same.
+    window_groups_source: Option<(DataId, DuplicateNameStrategy)>,
     selected_open_window_groups: Vec<u32>,
     selected_closed_window_groups: Vec<u32>,
+    /// Select windows with checkboxes instead of a multiple selection list.
+    window_checkbox_list: bool,
+    theme: Theme,
     status: String,
+    /// The latest status messages, newest last. Has at most
+    /// [`NOTIFICATION_LIMIT`] items.
+    notifications: VecDeque<Notification>,
+    next_notification_id: u64,
+    /// The step that the latest preview is working on, `None` once it is
+    /// done.
+    preview_stage: Option<PreviewStage>,
+    /// Data that failed to parse, windows can be recovered from it if it was
+    /// cut off.
+    damaged_data: Option<DataId>,
     format_info: Vec<(OutputFormat, String)>,
+    typst_fonts: Vec<String>,
+    /// Show how long each step of loading a file took in the status.
+    record_timings: bool,
+    /// Larger files fail to decompress, in MiB.
+    max_decompressed_mib: u64,
     wizard: bool,
     wizard_profiles: Vec<FirefoxProfileInfo>,
+    /// The UI state that was last saved between restarts. `None` until the
+    /// previously saved state has been restored so that it isn't overwritten
+    /// with default values.
+    saved_ui_state: Option<SavedUiState>,
+    /// The options were reset to their defaults and the defaults shouldn't be
+    /// saved until the user changes something else or saves links.
+    unsaved_reset: bool,
+    /// Files that were recently opened, the most recent first.
+    recent_paths: Vec<String>,
+    /// Used to discard previews that finish after a newer preview was
+    /// started.
+    preview_generation: PreviewGeneration,
+    /// Windows that were selected when the UI state was saved. Selected once
+    /// the restored file has been loaded.
+    restored_selection: Option<(Vec<u32>, Vec<u32>)>,
+    /// Options and selections before each change the user made, the most
+    /// recent last.
+    undo_history: Vec<UndoSnapshot>,
+    /// Changes that were undone, the most recently undone last.
+    redo_history: Vec<UndoSnapshot>,
+    /// The options and selection after the latest message was handled, used
+    /// to detect changes.
+    undo_baseline: UndoSnapshot,
 }
 impl State {
     pub fn init(mut sender: ElmChannel<Message>) -> Self {
@@ -793,8 +2948,15 @@ impl State {
                 sender.send(Message::SyncOutputPath(save_path));
             }
 
-            let info_new = Commands.get_info_for_slot(ui_state(), FileSlot::New).await;
+            let mut infos = Commands.all_file_infos(ui_state()).await.into_iter();
+            let info_new = infos.next().unwrap_or_default();
+            let current = infos.next().unwrap_or_default();
             log::info!("Input/New path id at startup: {:?}", info_new.path_id);
+            // A file that was selected before anything was loaded, for example
+            // one that was passed on the command line:
+            let open_new_file = info_new.path_id != PathId::null()
+                && info_new.data_id == DataId::null()
+                && current.path_id == PathId::null();
             if info_new.path_id != PathId::null() {
                 if info_new.data_id != DataId::null() {
                     log::info!(
@@ -807,10 +2969,11 @@ impl State {
                     info_new.path_id,
                 ));
             }
+            if open_new_file {
+                log::info!("Loading input/new file selected at startup");
+                sender.send(Message::LoadNewData);
+            }
 
-            let current = Commands
-                .get_info_for_slot(ui_state(), FileSlot::Current)
-                .await;
             log::info!("Loaded/Current path id at startup: {:?}", current.path_id);
             if current.path_id != PathId::null() {
                 sender.send(Message::SyncLoadedPath(
@@ -821,14 +2984,37 @@ impl State {
                 sender.send(Message::SetSelectedTabGroups {
                     open: vec![],
                     closed: vec![],
+                    user_change: false,
                 });
             }
+
+            let saved = Commands.restore_session_state().await.unwrap_or_else(|e| {
+                log::error!("Failed to restore UI state: {e}");
+                None
+            });
+            sender.send(Message::RestoreUiState(saved));
+        });
+        spawn(async move {
+            sender.send(Message::SetRecentPaths(
+                Commands.get_recent_paths(ui_state()).await,
+            ));
+        });
+        spawn(async move {
+            sender.send(Message::FetchedMaxDecompressedSize(
+                Commands.get_max_decompressed_size(ui_state()).await / MIB,
+            ));
         });
         spawn(async move {
             sender.send(Message::FetchedOutputFormatInfo(
                 Commands.format_descriptions().await,
             ));
         });
+        spawn(async move {
+            match Commands.load_domain_filters().await {
+                Ok(filters) => sender.send(Message::SetDomainFilters(filters)),
+                Err(e) => log::error!("Failed to load domain filters: {e}"),
+            }
+        });
 
         Self {
             input_path: String::new(),
@@ -836,30 +3022,195 @@ impl State {
             loaded_path: Default::default(),
             loaded_path_id: Default::default(),
             preview: String::new(),
+            pdf_preview: None,
+            tab_counts: None,
+            preview_length: None,
+            save_path_status: None,
+            tree_source_comparison: Vec::new(),
+            selected_tree_source: 0,
+            oldest_tabs: Vec::new(),
             save_path: String::new(),
             output_options: Default::default(),
+            generate_options: Default::default(),
             open_window_groups: Vec::new(),
             closed_window_groups: Vec::new(),
+            window_groups_source: None,
             selected_open_window_groups: Vec::new(),
             selected_closed_window_groups: Vec::new(),
+            window_checkbox_list: false,
+            theme: Theme::System,
             status: String::new(),
+            notifications: VecDeque::new(),
+            next_notification_id: 0,
+            preview_stage: None,
+            damaged_data: None,
             format_info: OutputFormat::all()
                 .iter()
                 .map(|&f| (f, String::new()))
                 .collect(),
+            typst_fonts: Vec::new(),
+            record_timings: false,
+            max_decompressed_mib: 1024,
             wizard: false,
             wizard_profiles: Vec::new(),
+            saved_ui_state: None,
+            unsaved_reset: false,
+            recent_paths: Vec::new(),
+            preview_generation: PreviewGeneration::default(),
+            restored_selection: None,
+            undo_history: Vec::new(),
+            redo_history: Vec::new(),
+            undo_baseline: UndoSnapshot::default(),
+        }
+    }
+    /// The part of the state that should be remembered between restarts.
+    fn ui_state_to_save(&self) -> SavedUiState {
+        let (selected_open_windows, selected_closed_windows) =
+            self.restored_selection.clone().unwrap_or_else(|| {
+                (
+                    self.selected_open_window_groups.clone(),
+                    self.selected_closed_window_groups.clone(),
+                )
+            });
+        SavedUiState {
+            loaded_path: self.loaded_path.clone(),
+            output_options: self.output_options.clone(),
+            generate_options: GenerateOptions {
+                open_group_indexes: None,
+                closed_group_indexes: None,
+                domain_allowlist: None,
+                domain_blocklist: None,
+                ..self.generate_options.clone()
+            },
+            selected_open_windows,
+            selected_closed_windows,
+            window_checkbox_list: self.window_checkbox_list,
+            theme: self.theme,
+        }
+    }
+    fn undo_snapshot(&self) -> UndoSnapshot {
+        UndoSnapshot {
+            output_options: self.output_options.clone(),
+            generate_options: GenerateOptions {
+                title_url_filter: None,
+                domain_allowlist: None,
+                domain_blocklist: None,
+                ..self.generate_options.clone()
+            },
+            selected_open_window_groups: self.selected_open_window_groups.clone(),
+            selected_closed_window_groups: self.selected_closed_window_groups.clone(),
+        }
+    }
+    /// Remember the previous options if the user changed them. Changes that
+    /// weren't made by the user only update the baseline.
+    fn record_undo_history(&mut self, user_change: bool) {
+        let current = self.undo_snapshot();
+        if current == self.undo_baseline {
+            return;
+        }
+        let previous = std::mem::replace(&mut self.undo_baseline, current);
+        if user_change {
+            if self.undo_history.len() >= UNDO_LIMIT {
+                self.undo_history.remove(0);
+            }
+            self.undo_history.push(previous);
+            self.redo_history.clear();
+        }
+    }
+    /// Restore options from the undo or redo history and return the options
+    /// that were replaced.
+    fn apply_undo_snapshot(
+        &mut self,
+        snapshot: UndoSnapshot,
+        sender: ElmChannel<Message>,
+    ) -> UndoSnapshot {
+        let replaced = self.undo_snapshot();
+        let format_changed = snapshot.output_options.format != self.output_options.format;
+        self.output_options = snapshot.output_options.clone();
+        self.generate_options = GenerateOptions {
+            title_url_filter: self.generate_options.title_url_filter.take(),
+            domain_allowlist: self.generate_options.domain_allowlist.take(),
+            domain_blocklist: self.generate_options.domain_blocklist.take(),
+            ..snapshot.generate_options.clone()
+        };
+        self.selected_open_window_groups = snapshot.selected_open_window_groups.clone();
+        self.selected_closed_window_groups = snapshot.selected_closed_window_groups.clone();
+        self.undo_baseline = snapshot;
+        if format_changed {
+            // Fetch fonts and check the save path's extension:
+            self.handle_message(Message::SetOutputFormat(self.output_options.format), sender);
+        }
+        spawn(self.generate_preview(sender));
+        replaced
+    }
+    /// Save the UI state if it changed since it was last saved.
+    fn save_ui_state_if_changed(&mut self) {
+        let Some(saved) = &self.saved_ui_state else {
+            return;
+        };
+        let current = self.ui_state_to_save();
+        if *saved == current {
+            return;
+        }
+        self.saved_ui_state = Some(current.clone());
+        spawn(async move {
+            if let Err(e) = Commands.save_session_state(current).await {
+                log::error!("Failed to save UI state: {e}");
+            }
+        });
+    }
+    /// Check the current save path for problems so that they can be shown
+    /// before saving.
+    fn validate_save_path(&self, mut sender: ElmChannel<Message>) -> impl Future<Output = ()> {
+        let save_path = self.save_path.clone();
+        let format = self.output_options.format;
+        async move {
+            let status = Commands
+                .validate_save_path(ui_state(), save_path.clone(), format)
+                .await;
+            sender.send(Message::SetSavePathStatus(save_path, status));
         }
     }
+
     fn generate_preview(&self, mut sender: ElmChannel<Message>) -> impl Future<Output = ()> {
         log::trace!("Creating preview future");
 
         let loaded_path_id = self.loaded_path_id;
         let mut open_window_groups = self.open_window_groups.clone();
         let mut closed_window_groups = self.closed_window_groups.clone();
+        let mut window_groups_source = self.window_groups_source;
         let mut selected_open_window_groups = self.selected_open_window_groups.clone();
         let mut selected_closed_window_groups = self.selected_closed_window_groups.clone();
+        let generate_options = self.generate_options.clone();
+        let record_timings = self.record_timings;
+        let token = self.preview_generation.start();
+
+        // Status from an older preview shouldn't replace the status of a
+        // newer one:
+        let set_status = {
+            let token = token.clone();
+            move |status: String, is_error: bool| {
+                if token.is_current() {
+                    let mut sender = sender;
+                    sender.send(if is_error {
+                        Message::SetError(status)
+                    } else {
+                        Message::SetStatus(status)
+                    });
+                }
+            }
+        };
+        let set_stage = {
+            let token = token.clone();
+            move |stage: PreviewStage| {
+                if token.is_current() {
+                    let mut sender = sender;
+                    sender.send(Message::SetStage(Some(stage)));
+                }
+            }
+        };
 
+        let counts_token = token.clone();
         let fut = async move {
             log::trace!("Generating preview!");
             if loaded_path_id == PathId::null() {
@@ -887,11 +3238,45 @@ impl State {
                 .await
                 .ok_or("file id has expired")?;
 
+            // Only checked when a file is loaded, not for every preview:
+            let mut stale_warning = None;
             let id = if info.data_id == DataId::null() {
                 log::trace!("Generating preview -> Reading file data");
-                sender.send(Message::SetStatus("Reading input data".to_owned()));
+                set_stage(PreviewStage::Reading);
                 let id = if host_commands::has_host_access() {
-                    Commands.load_data(ui_state(), id).await?
+                    let done = Arc::new(AtomicBool::new(false));
+                    // Can't sleep without Tokio:
+                    #[cfg(not(target_family = "wasm"))]
+                    spawn({
+                        let done = done.clone();
+                        let token = counts_token.clone();
+                        async move {
+                            loop {
+                                tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+                                if done.load(Ordering::Acquire) {
+                                    break;
+                                }
+                                let Some(progress) = Commands.load_progress(ui_state(), id).await
+                                else {
+                                    break;
+                                };
+                                if progress.total_bytes > 0
+                                    && !done.load(Ordering::Acquire)
+                                    && token.is_current()
+                                {
+                                    sender.send(Message::SetProgress(format!(
+                                        "Reading input data ({})",
+                                        format_load_progress(progress)
+                                    )));
+                                }
+                            }
+                        }
+                    });
+                    let result = Commands.load_data(ui_state(), id).await;
+                    done.store(true, Ordering::Release);
+                    // Loaded files are added to the recent files:
+                    sender.send(Message::RefreshRecentPaths);
+                    result?
                 } else {
                     log::trace!("Generating preview -> Using old input data (no host access)");
                     Commands
@@ -903,6 +3288,7 @@ impl State {
 
                 open_window_groups.clear();
                 closed_window_groups.clear();
+                window_groups_source = None;
                 selected_open_window_groups.clear();
                 selected_closed_window_groups.clear();
 
@@ -911,76 +3297,129 @@ impl State {
                     closed: Vec::new(),
                     open_selected: Vec::new(),
                     closed_selected: Vec::new(),
+                    source: None,
                 });
 
                 info = Commands
                     .get_info_for_data_id(ui_state(), id)
                     .await
                     .ok_or("file id has expired")?;
+
+                stale_warning = Commands
+                    .newer_session_file_warning(ui_state(), id)
+                    .await
+                    .unwrap_or_else(|e| {
+                        log::warn!("Failed to check for newer session files: {e}");
+                        None
+                    });
                 id
             } else {
                 info.data_id
             };
             if matches!(info.status, FileStatus::Compressed) {
-                sender.send(Message::SetStatus("Decompressing".to_owned()));
+                set_stage(PreviewStage::Decompressing);
                 Commands.decompress_data(ui_state(), id).await?;
             }
             if !matches!(info.status, FileStatus::Parsed) {
-                sender.send(Message::SetStatus("Parsing".to_owned()));
-                Commands.parse_session_data(ui_state(), id).await?;
+                set_stage(PreviewStage::Parsing);
+                if let Err(e) = Commands.parse_session_data(ui_state(), id).await {
+                    if counts_token.is_current() {
+                        sender.send(Message::SetDamagedData(Some(id)));
+                    }
+                    return Err(e);
+                }
             }
 
-            let groups = Commands
-                .get_groups_from_session(ui_state(), id, true)
-                .await?;
+            // The window lists won't change if we have the same DataId, so
+            // only list them again if the data or the naming changed:
+            let source = (id, generate_options.duplicate_names);
+            if window_groups_source != Some(source) {
+                let groups = Commands
+                    .get_groups_from_session(
+                        ui_state(),
+                        id,
+                        true,
+                        generate_options.duplicate_names,
+                    )
+                    .await?;
 
-            log::info!("Groups in loaded session {groups:#?}");
+                log::info!("Groups in loaded session {groups:#?}");
 
-            // These won't change if we have the same DataId:
-            let open_windows: Vec<_> = groups.open.iter().map(|g| g.name.clone()).collect();
-            let closed_windows: Vec<_> = groups.closed.iter().map(|g| g.name.clone()).collect();
-            if open_windows != open_window_groups || closed_windows != closed_window_groups {
-                selected_open_window_groups.clear(); // = (0..open_windows.len() as u32).collect();
-                selected_closed_window_groups.clear();
-                sender.send(Message::SetTabGroups {
-                    open: open_windows,
-                    closed: closed_windows,
-                    open_selected: selected_open_window_groups.clone(),
-                    closed_selected: Vec::new(),
-                });
+                let open_windows: Vec<_> = groups.open.iter().map(window_label).collect();
+                let closed_windows: Vec<_> = groups.closed.iter().map(window_label).collect();
+                // The same windows are only renamed when the naming changed:
+                let same_data = window_groups_source.is_some_and(|(data_id, _)| data_id == id);
+                let labels_changed =
+                    open_windows != open_window_groups || closed_windows != closed_window_groups;
+                if !same_data && labels_changed {
+                    selected_open_window_groups.clear(); // = (0..open_windows.len() as u32).collect();
+                    selected_closed_window_groups.clear();
+                }
+                // A newer preview might have changed the selection:
+                if counts_token.is_current() {
+                    sender.send(Message::SetTabGroups {
+                        open: open_windows,
+                        closed: closed_windows,
+                        open_selected: selected_open_window_groups.clone(),
+                        closed_selected: selected_closed_window_groups.clone(),
+                        source: Some(source),
+                    });
+                }
             }
 
-            sender.send(Message::SetStatus("Generating output".to_owned()));
-
-            let has_any_filter = !selected_open_window_groups.is_empty()
-                || !selected_closed_window_groups.is_empty();
+            set_stage(PreviewStage::Generating);
 
+            let generate_options = with_selected_windows(
+                generate_options,
+                selected_open_window_groups,
+                selected_closed_window_groups,
+            );
             let links = Commands
-                .to_text_links(
-                    ui_state(),
-                    id,
-                    GenerateOptions {
-                        open_group_indexes: Some(selected_open_window_groups)
-                            .filter(|_| has_any_filter),
-                        closed_group_indexes: Some(selected_closed_window_groups),
-                        ..Default::default()
-                    },
-                )
+                .to_text_links(ui_state(), id, generate_options.clone())
                 .await?;
 
-            sender.send(Message::SetStatus(
-                "Successfully loaded session data!".to_owned(),
-            ));
+            let counts = Commands
+                .count_tabs(ui_state(), id, generate_options)
+                .await
+                .inspect_err(|e| log::warn!("Failed to count tabs: {e}"))
+                .ok();
+            if counts_token.is_current() {
+                sender.send(Message::SetTabCounts(counts));
+            }
+
+            let mut status = "Successfully loaded session data!".to_owned();
+            let mut has_warning = false;
+            let info = Commands.get_info_for_data_id(ui_state(), id).await;
+            if let Some(summary) = info.as_ref().and_then(format_file_summary) {
+                status.push_str(&format!(" ({summary})"));
+            }
+            if let Some(warning) = info.and_then(|info| info.parse_warning) {
+                status.push_str(&format!(" Warning: {warning}"));
+                has_warning = true;
+            }
+            if record_timings {
+                let timings = Commands.get_timings(ui_state(), id).await.unwrap_or_default();
+                log::info!("Timings for loaded session: {timings:?}");
+                status.push_str(&format!(" ({})", format_timings(&timings)));
+            }
+            if let Some(warning) = stale_warning {
+                status.push_str(&format!(" Warning: {warning}"));
+                has_warning = true;
+            }
+            set_status(status, has_warning);
             Ok(Some(links))
         };
 
-        struct StatusGuard(Option<ElmChannel<Message>>);
+        struct StatusGuard(Option<(ElmChannel<Message>, PreviewToken)>);
         impl Drop for StatusGuard {
             fn drop(&mut self) {
-                if let Some(channel) = &mut self.0 {
-                    channel.send(Message::SetStatus(
-                        "Background work was cancelled unexpectedly".to_string(),
-                    ));
+                if let Some((channel, token)) = &mut self.0 {
+                    if token.is_current() {
+                        channel.send(Message::SetStage(None));
+                        channel.send(Message::SetError(
+                            "Background work was cancelled unexpectedly".to_string(),
+                        ));
+                    }
                 }
             }
         }
@@ -988,22 +3427,87 @@ impl State {
         // Wrap the above future to handle errors:
         async move {
             // Set status if canceled:
-            let mut guard = StatusGuard(Some(sender));
+            let mut guard = StatusGuard(Some((sender, token.clone())));
 
-            sender.send(Message::SetPreview("".to_string()));
-            match fut.await {
-                Ok(Some(v)) => sender.send(Message::SetPreview(v)),
-                Ok(None) => {}
-                Err(e) => {
-                    sender.send(Message::SetStatus(format!("Error: {e}")));
+            if token.is_current() {
+                sender.send(Message::SetPreview("".to_string()));
+            }
+            let result = fut.await;
+            if token.is_current() {
+                sender.send(Message::SetStage(None));
+                match result {
+                    Ok(Some(v)) => sender.send(Message::SetPreview(v)),
+                    Ok(None) => {}
+                    Err(e) => {
+                        sender.send(Message::SetError(format!("Error: {e}")));
+                    }
                 }
+            } else {
+                log::debug!("Discarded preview since a newer preview was started");
             }
             guard.0.take();
         }
     }
-    pub fn update(&mut self, msg: Message, mut sender: ElmChannel<Message>) {
+
+    /// The options for generating links from the selected windows.
+    fn selected_generate_options(&self) -> GenerateOptions {
+        with_selected_windows(
+            self.generate_options.clone(),
+            self.selected_open_window_groups.clone(),
+            self.selected_closed_window_groups.clone(),
+        )
+    }
+
+    /// Show a status message in the status bar and as a notification. Errors
+    /// are kept until they are dismissed, other notifications are dismissed
+    /// after [`NOTIFICATION_TIMEOUT`].
+    fn set_status(&mut self, status: String, is_error: bool, mut sender: ElmChannel<Message>) {
+        self.status = status.clone();
+        if status.is_empty() {
+            return;
+        }
+        let id = self.next_notification_id;
+        self.next_notification_id += 1;
+        if self.notifications.len() >= NOTIFICATION_LIMIT {
+            self.notifications.pop_front();
+        }
+        self.notifications.push_back(Notification {
+            id,
+            text: status,
+            time: chrono::Local::now().format("%H:%M:%S").to_string(),
+            is_error,
+        });
+        if !is_error {
+            spawn(async move {
+                sleep(NOTIFICATION_TIMEOUT).await;
+                sender.send(Message::DismissNotification(id));
+            });
+        }
+    }
+
+    pub fn update(&mut self, msg: Message, sender: ElmChannel<Message>) {
+        let user_change = msg.is_undoable();
+        match msg {
+            // Don't remember the defaults just because the options were reset:
+            Message::ResetOptions => self.unsaved_reset = true,
+            Message::CopyLinksToClipboard
+            | Message::WriteLinksToFile
+            | Message::ExportCombinedUrls
+            | Message::ExportNewSinceLast => self.unsaved_reset = false,
+            _ if user_change => self.unsaved_reset = false,
+            _ => {}
+        }
+        self.handle_message(msg, sender);
+        self.record_undo_history(user_change);
+        if !self.unsaved_reset {
+            self.save_ui_state_if_changed();
+        }
+    }
+    fn handle_message(&mut self, msg: Message, mut sender: ElmChannel<Message>) {
         match msg {
             Message::SetInputPath(new_path) => {
+                // Another file was selected before the restored file loaded:
+                self.restored_selection = None;
                 self.input_path.clone_from(&new_path);
                 spawn(async move {
                     let new_id = Commands
@@ -1012,7 +3516,59 @@ impl State {
                     sender.send(Message::SyncInputPath(new_path, new_id));
                 });
             }
+            Message::PasteInputPath => {
+                self.restored_selection = None;
+                spawn(async move {
+                    let text = match read_text_from_clipboard().await {
+                        Ok(text) if !text.trim().is_empty() => text,
+                        Ok(_) => {
+                            sender.send(Message::SetStatus(
+                                "The clipboard doesn't contain a file path".to_owned(),
+                            ));
+                            return;
+                        }
+                        Err(e) => {
+                            sender.send(Message::SetError(format!(
+                                "Failed to read the clipboard: {e}"
+                            )));
+                            return;
+                        }
+                    };
+                    let new_id = Commands
+                        .set_open_path(ui_state(), FileSlot::New, text.clone())
+                        .await;
+                    // Show the path that a pasted URL was converted to:
+                    let path = Commands
+                        .get_info_for_path_id(ui_state(), new_id)
+                        .await
+                        .and_then(|info| info.file_path)
+                        .unwrap_or(text);
+                    sender.send(Message::SyncInputPath(path, new_id));
+                });
+            }
+            Message::RefreshRecentPaths => {
+                spawn(async move {
+                    sender.send(Message::SetRecentPaths(
+                        Commands.get_recent_paths(ui_state()).await,
+                    ));
+                });
+            }
+            Message::SetRecentPaths(recent_paths) => {
+                self.recent_paths = recent_paths;
+            }
+            Message::ClearRecentPaths => {
+                self.recent_paths.clear();
+                spawn(async move {
+                    if let Err(e) = Commands.clear_recent_paths(ui_state()).await {
+                        sender.send(Message::SetError(format!(
+                            "Failed to clear recent files: {e}"
+                        )));
+                    }
+                });
+            }
             Message::UpdateInputPath(id) => {
+                // Browsing for a file adds it to the recent files:
+                sender.send(Message::RefreshRecentPaths);
                 spawn(async move {
                     if let Some(info) = Commands.get_info_for_path_id(ui_state(), id).await {
                         sender.send(Message::SyncInputPath(
@@ -1026,13 +3582,23 @@ impl State {
                 self.input_path = input_path;
                 self.input_path_id = path_id;
             }
+            Message::RegisterFileTypes => {
+                spawn(async move {
+                    sender.send(match Commands.register_file_associations().await {
+                        Ok(()) => Message::SetStatus(
+                            "Sessionstore files will now be opened with this program".to_owned(),
+                        ),
+                        Err(e) => Message::SetError(format!("Failed to register file types: {e}")),
+                    });
+                });
+            }
             Message::OpenWizard => {
                 self.wizard = true;
                 spawn(async move {
                     match Commands.find_firefox_profiles().await {
                         Ok(profiles) => sender.send(Message::FetchedFirefoxProfiles(profiles)),
                         Err(e) => {
-                            sender.send(Message::SetStatus(format!(
+                            sender.send(Message::SetError(format!(
                                 "Failed to gather info about firefox profiles: {e}"
                             )));
                             sender.send(Message::CloseWizard);
@@ -1051,11 +3617,97 @@ impl State {
                 self.loaded_path_id = path_id;
             }
             Message::LoadNewData => {
+                self.damaged_data = None;
                 self.loaded_path_id = self.input_path_id;
                 self.loaded_path.clone_from(&self.input_path);
-                // TODO: cancellation
                 spawn(self.generate_preview(sender));
             }
+            Message::ReloadLoadedFile => {
+                let path_id = self.loaded_path_id;
+                if path_id == PathId::null() || !host_commands::has_host_access() {
+                    return;
+                }
+                self.damaged_data = None;
+                // Select the same windows once the file has been read:
+                if !self.selected_open_window_groups.is_empty()
+                    || !self.selected_closed_window_groups.is_empty()
+                {
+                    self.restored_selection = Some((
+                        self.selected_open_window_groups.clone(),
+                        self.selected_closed_window_groups.clone(),
+                    ));
+                }
+                spawn(async move {
+                    if let Some(info) = Commands.get_info_for_path_id(ui_state(), path_id).await {
+                        if info.data_id != DataId::null() {
+                            Commands.forget_data(ui_state(), info.data_id).await;
+                        }
+                    }
+                    // Regenerate preview:
+                    sender.send(Message::SetSelectedTabGroups {
+                        open: vec![],
+                        closed: vec![],
+                        user_change: false,
+                    });
+                });
+            }
+            Message::ClearLoadedData => {
+                // Discard previews that are still being generated:
+                self.preview_generation.start();
+                self.input_path.clear();
+                self.input_path_id = Default::default();
+                self.loaded_path.clear();
+                self.loaded_path_id = Default::default();
+                self.preview.clear();
+                self.preview_length = None;
+                self.preview_stage = None;
+                self.pdf_preview = None;
+                self.tab_counts = None;
+                self.tree_source_comparison.clear();
+                self.oldest_tabs.clear();
+                self.open_window_groups.clear();
+                self.closed_window_groups.clear();
+                self.window_groups_source = None;
+                self.selected_open_window_groups.clear();
+                self.selected_closed_window_groups.clear();
+                self.damaged_data = None;
+                self.restored_selection = None;
+                self.undo_history.clear();
+                self.redo_history.clear();
+                self.set_status("Cleared all loaded data".to_owned(), false, sender);
+                spawn(async move {
+                    Commands.forget_all(ui_state()).await;
+                });
+            }
+            Message::CopyRawJson => {
+                let path_id = self.loaded_path_id;
+                if path_id == PathId::null() {
+                    return;
+                }
+                spawn(async move {
+                    let data_id = Commands
+                        .get_info_for_path_id(ui_state(), path_id)
+                        .await
+                        .map(|info| info.data_id)
+                        .filter(|&id| id != DataId::null());
+                    let result = match data_id {
+                        Some(id) => Commands.get_raw_json(ui_state(), id).await,
+                        None => Err("no data has been loaded".to_owned()),
+                    };
+                    sender.send(match result {
+                        Ok(json) => match write_text_to_clipboard(&json).await {
+                            Ok(()) => Message::SetStatus(format!(
+                                "Copied the raw JSON ({:.1} MiB) to the clipboard",
+                                json.len() as f64 / MIB as f64
+                            )),
+                            Err(e) => {
+                                Message::SetError(format!("Failed to copy the raw JSON: {e}"))
+                            }
+                        },
+                        Err(e) => Message::SetError(format!("Failed to read the raw JSON: {e}")),
+                    });
+                });
+            }
             Message::LoadInputPath(new_path) => {
                 self.input_path.clone_from(&new_path);
                 spawn(async move {
@@ -1067,16 +3719,35 @@ impl State {
                 });
             }
             Message::SetPreview(preview) => {
+                self.preview_length = (!preview.is_empty()).then(|| PreviewLength::new(&preview));
                 self.preview = preview;
+                // Any PDF preview was generated with old options:
+                self.pdf_preview = None;
+            }
+            Message::SetTabCounts(counts) => {
+                self.tab_counts = counts;
             }
             Message::SetOutputPath(save_path) => {
                 self.save_path.clone_from(&save_path);
+                spawn(self.validate_save_path(sender));
                 spawn(async move {
                     Commands.set_save_path(ui_state(), save_path).await;
                 });
             }
             Message::SyncOutputPath(save_path) => {
                 self.save_path = save_path;
+                spawn(self.validate_save_path(sender));
+            }
+            Message::SetTheme(theme) => {
+                self.theme = theme;
+            }
+            Message::ValidateSavePath => {
+                spawn(self.validate_save_path(sender));
+            }
+            Message::SetSavePathStatus(save_path, status) => {
+                if save_path == self.save_path {
+                    self.save_path_status = Some(status);
+                }
             }
             Message::SetOverwrite(overwrite) => {
                 self.output_options.overwrite = overwrite;
@@ -1084,100 +3755,735 @@ impl State {
             Message::SetCreateFolder(create_folder) => {
                 self.output_options.create_folder = create_folder;
             }
+            Message::SetGenerationTimeout(timeout) => {
+                self.output_options.generation_timeout_secs = timeout;
+            }
+            Message::SetIncludeExportId(include_export_id) => {
+                self.output_options.include_export_id = include_export_id;
+            }
+            Message::SetRestoreBundle(restore_bundle) => {
+                self.output_options.restore_bundle = restore_bundle;
+            }
+            Message::SetSplitPerWindow(split_per_window) => {
+                self.output_options.split_per_window = split_per_window;
+            }
+            Message::SetRevealAfterSave(reveal_after_save) => {
+                self.output_options.reveal_after_save = reveal_after_save;
+            }
+            Message::SetSavePreview(save_preview) => {
+                self.output_options.save_preview = save_preview;
+            }
+            Message::SetTextEncoding(text_encoding) => {
+                self.output_options.text_encoding = text_encoding;
+            }
+            Message::SetPageSize(page_size) => {
+                self.output_options.page_size = page_size;
+            }
+            Message::SetLandscape(landscape) => {
+                self.output_options.landscape = landscape;
+            }
+            Message::SetMargins(margins_mm) => {
+                self.output_options.margins_mm = margins_mm;
+            }
+            Message::SetPdfRenderRetries(retries) => {
+                self.output_options.pdf_render_retries = retries;
+            }
             Message::SetOutputFormat(format) => {
                 self.output_options.format = format;
+                spawn(self.validate_save_path(sender));
+                if format.is_typst() && self.typst_fonts.is_empty() {
+                    spawn(async move {
+                        sender.send(Message::FetchedTypstFonts(
+                            Commands.typst_available_fonts().await,
+                        ));
+                    });
+                }
+            }
+            Message::SetMarkdownStyle(markdown_style) => {
+                let was_table = self.generate_options.markdown_style == MarkdownStyle::Table;
+                self.generate_options.markdown_style = markdown_style;
+                // The preview shows the table:
+                if was_table || markdown_style == MarkdownStyle::Table {
+                    spawn(self.generate_preview(sender));
+                }
+            }
+            Message::SetMarkdownTocLinks(markdown_toc_links) => {
+                self.generate_options.markdown_toc_links = markdown_toc_links;
+            }
+            Message::SetUseTst(use_tst) => {
+                self.generate_options.tree_style_tab_trees = use_tst;
+                spawn(self.generate_preview(sender));
+            }
+            Message::SetUseSidebery(use_sidebery) => {
+                self.generate_options.sidebery_trees = use_sidebery;
+                spawn(self.generate_preview(sender));
+            }
+            Message::SetFlatLinks(flat_links) => {
+                self.generate_options.flat_links = flat_links;
+                spawn(self.generate_preview(sender));
+            }
+            Message::SetMarkCollapsedTabs(mark_collapsed_tabs) => {
+                self.generate_options.mark_collapsed_tabs = mark_collapsed_tabs;
+                spawn(self.generate_preview(sender));
+            }
+            Message::SetIncludeCounts(include_counts) => {
+                self.generate_options.include_counts = include_counts;
+                spawn(self.generate_preview(sender));
+            }
+            Message::SetMarkClosedWindows(mark_closed_windows) => {
+                self.generate_options.mark_closed_windows = mark_closed_windows;
+                spawn(self.generate_preview(sender));
+            }
+            Message::SetDeduplicateLinks(deduplicate_links) => {
+                self.generate_options.deduplicate_links = deduplicate_links;
+                spawn(self.generate_preview(sender));
+            }
+            Message::SetDeduplicateGlobally(deduplicate_globally) => {
+                self.generate_options.deduplicate_globally = deduplicate_globally;
+                spawn(self.generate_preview(sender));
+            }
+            Message::SetAnnotateLanguages(annotate_languages) => {
+                self.generate_options.annotate_languages = annotate_languages;
+                spawn(self.generate_preview(sender));
+            }
+            Message::SetIncludeTimestamps(include_timestamps) => {
+                self.generate_options.include_timestamps = include_timestamps;
+                spawn(self.generate_preview(sender));
+            }
+            Message::SetPinnedOnly(pinned_only) => {
+                self.generate_options.pinned_only = pinned_only;
+                spawn(self.generate_preview(sender));
+            }
+            Message::SetIncludeClosedTabs(include_closed_tabs) => {
+                self.generate_options.include_closed_tabs = include_closed_tabs;
+                spawn(self.generate_preview(sender));
+            }
+            Message::SetIncludeContainer(include_container) => {
+                self.generate_options.include_container = include_container;
+                spawn(self.generate_preview(sender));
+            }
+            Message::SetReproducible(reproducible) => {
+                self.generate_options.reproducible = reproducible;
+                spawn(self.generate_preview(sender));
+            }
+            Message::SetDomainFilters(filters) => {
+                let had_filters = self.generate_options.domain_allowlist.is_some()
+                    || self.generate_options.domain_blocklist.is_some();
+                self.generate_options.domain_allowlist =
+                    Some(filters.allowlist).filter(|list| !list.is_empty());
+                self.generate_options.domain_blocklist =
+                    Some(filters.blocklist).filter(|list| !list.is_empty());
+                let has_filters = self.generate_options.domain_allowlist.is_some()
+                    || self.generate_options.domain_blocklist.is_some();
+                if had_filters || has_filters {
+                    spawn(self.generate_preview(sender));
+                }
+            }
+            Message::EditDomainFilters(filters) => {
+                spawn({
+                    let filters = filters.clone();
+                    async move {
+                        if let Err(e) = Commands.save_domain_filters(filters).await {
+                            sender.send(Message::SetError(format!(
+                                "Failed to save domain filters: {e}"
+                            )));
+                        }
+                    }
+                });
+                self.update(Message::SetDomainFilters(filters), sender);
+            }
+            Message::SetSearchFilter(search) => {
+                self.generate_options.title_url_filter = Some(search).filter(|s| !s.is_empty());
+                spawn(self.generate_preview(sender));
+            }
+            Message::SetDuplicateNames(duplicate_names) => {
+                self.generate_options.duplicate_names = duplicate_names;
+                // Updates the names in the window list as well:
+                spawn(self.generate_preview(sender));
+            }
+            Message::SetTabSort(tab_sort) => {
+                self.generate_options.tab_sort = tab_sort;
+                spawn(self.generate_preview(sender));
+            }
+            Message::SetGroupBy(group_by) => {
+                self.generate_options.group_by = group_by;
+                spawn(self.generate_preview(sender));
+            }
+            Message::SetHtmlWindowFilter(html_window_filter) => {
+                self.generate_options.html_window_filter = html_window_filter;
+            }
+            Message::SetTypstFont(font) => {
+                self.generate_options.typst_font = font;
+            }
+            Message::SetPdfOutline(pdf_outline) => {
+                self.generate_options.pdf_outline = pdf_outline;
+            }
+            Message::SetTypstTemplate(template) => {
+                self.generate_options.typst_template = template;
+            }
+            Message::SetWindowSeparator(window_separator) => {
+                self.generate_options.window_separator = window_separator;
+                spawn(self.generate_preview(sender));
+            }
+            Message::SetCustomPageBreak(page_break) => {
+                self.generate_options.custom_page_break = page_break;
+                // Used by the preview if there is no window separator:
+                if self.generate_options.window_separator.is_none() {
+                    spawn(self.generate_preview(sender));
+                }
+            }
+            Message::FetchedTypstFonts(fonts) => {
+                self.typst_fonts = fonts;
+            }
+            Message::SetRecordTimings(record_timings) => {
+                self.record_timings = record_timings;
+                spawn(async move {
+                    Commands
+                        .set_record_timings(ui_state(), record_timings)
+                        .await;
+                });
+            }
+            Message::SetMaxDecompressedSize(max_mib) => {
+                self.max_decompressed_mib = max_mib;
+                spawn(async move {
+                    if let Err(e) = Commands
+                        .set_max_decompressed_size(ui_state(), max_mib.saturating_mul(MIB))
+                        .await
+                    {
+                        sender.send(Message::SetError(format!(
+                            "Failed to change the size limit: {e}"
+                        )));
+                    }
+                });
+            }
+            Message::FetchedMaxDecompressedSize(max_mib) => {
+                self.max_decompressed_mib = max_mib;
+            }
+            Message::ResetOptions => {
+                self.output_options = Default::default();
+                // Domain filters are edited separately and remembered between
+                // restarts so keep them:
+                let generate_options = GenerateOptions {
+                    domain_allowlist: self.generate_options.domain_allowlist.clone(),
+                    domain_blocklist: self.generate_options.domain_blocklist.clone(),
+                    ..Default::default()
+                };
+                let had_generate_changes = self.generate_options != generate_options;
+                self.generate_options = generate_options;
+                if had_generate_changes {
+                    spawn(self.generate_preview(sender));
+                }
             }
             Message::SetTabGroups {
                 open,
                 closed,
                 open_selected,
                 closed_selected,
+                source,
             } => {
+                self.window_groups_source = source;
+                if open != self.open_window_groups || closed != self.closed_window_groups {
+                    // Selections of other windows can't be undone:
+                    self.undo_history.clear();
+                    self.redo_history.clear();
+                }
                 self.open_window_groups = open;
                 self.closed_window_groups = closed;
                 self.selected_open_window_groups = open_selected;
                 self.selected_closed_window_groups = closed_selected;
+
+                let has_windows =
+                    !self.open_window_groups.is_empty() || !self.closed_window_groups.is_empty();
+                if let Some((open, closed)) = self.restored_selection.take_if(|_| has_windows) {
+                    let open_count = self.open_window_groups.len();
+                    let closed_count = self.closed_window_groups.len();
+                    sender.send(Message::SetSelectedTabGroups {
+                        open: open.into_iter().filter(|&ix| (ix as usize) < open_count).collect(),
+                        closed: closed
+                            .into_iter()
+                            .filter(|&ix| (ix as usize) < closed_count)
+                            .collect(),
+                        user_change: false,
+                    });
+                }
+            }
+            Message::RestoreUiState(saved) => {
+                let Some(saved) = saved else {
+                    self.saved_ui_state = Some(self.ui_state_to_save());
+                    return;
+                };
+                log::info!("Restoring UI state: {saved:?}");
+                self.output_options = saved.output_options.clone();
+                // Domain filters are remembered separately:
+                self.generate_options = GenerateOptions {
+                    domain_allowlist: self.generate_options.domain_allowlist.clone(),
+                    domain_blocklist: self.generate_options.domain_blocklist.clone(),
+                    ..saved.generate_options.clone()
+                };
+                self.window_checkbox_list = saved.window_checkbox_list;
+                self.theme = saved.theme;
+                // Fetch fonts if needed:
+                sender.send(Message::SetOutputFormat(saved.output_options.format));
+
+                if self.loaded_path_id != PathId::null() {
+                    spawn(self.generate_preview(sender));
+                } else if !saved.loaded_path.is_empty() && host_commands::has_host_access() {
+                    self.restored_selection = Some((
+                        saved.selected_open_windows.clone(),
+                        saved.selected_closed_windows.clone(),
+                    ));
+                    sender.send(Message::LoadInputPath(saved.loaded_path.clone()));
+                }
+                self.saved_ui_state = Some(saved);
             }
-            Message::SetSelectedTabGroups { open, closed } => {
+            Message::SetSelectedTabGroups { open, closed, .. } => {
                 self.selected_open_window_groups = open;
                 self.selected_closed_window_groups = closed;
-                // TODO: cancellation
                 spawn(self.generate_preview(sender));
             }
-            Message::SetStatus(status) => {
+            Message::Undo => {
+                let Some(snapshot) = self.undo_history.pop() else {
+                    return;
+                };
+                let replaced = self.apply_undo_snapshot(snapshot, sender);
+                self.redo_history.push(replaced);
+            }
+            Message::Redo => {
+                let Some(snapshot) = self.redo_history.pop() else {
+                    return;
+                };
+                let replaced = self.apply_undo_snapshot(snapshot, sender);
+                self.undo_history.push(replaced);
+            }
+            Message::SetStatus(status) => {
+                self.set_status(status, false, sender);
+            }
+            Message::SetError(error) => {
+                self.set_status(error, true, sender);
+            }
+            Message::SetProgress(status) => {
                 self.status = status;
             }
+            Message::DismissNotification(id) => {
+                self.notifications.retain(|notification| notification.id != id);
+            }
+            Message::SetStage(stage) => {
+                self.preview_stage = stage;
+            }
+            Message::SetDamagedData(id) => {
+                self.damaged_data = id;
+            }
+            Message::RecoverWindows => {
+                let Some(id) = self.damaged_data.take() else {
+                    return;
+                };
+                self.set_status("Recovering windows from damaged data".to_owned(), false, sender);
+                spawn(async move {
+                    match Commands.parse_session_data_lenient(ui_state(), id).await {
+                        // Regenerate preview:
+                        Ok(()) => sender.send(Message::SetSelectedTabGroups {
+                            open: vec![],
+                            closed: vec![],
+                            user_change: false,
+                        }),
+                        Err(e) => sender.send(Message::SetError(format!("Error: {e}"))),
+                    }
+                });
+            }
             Message::FetchedOutputFormatInfo(info) => {
                 self.format_info = info;
             }
             Message::CopyLinksToClipboard => {
                 let preview = self.preview.clone();
+                let format = self.output_options.format;
+                let generate_options = self.selected_generate_options();
                 spawn(async move {
-                    if let Err(e) = write_text_to_clipboard(&preview).await {
-                        sender.send(Message::SetStatus(format!(
+                    // The preview already has the links as plain text and
+                    // PDFs can't be pasted as text:
+                    let result = if format == OutputFormat::TEXT || format.is_pdf() {
+                        write_text_to_clipboard(&preview).await
+                    } else {
+                        let current = Commands
+                            .get_info_for_slot(ui_state(), FileSlot::Current)
+                            .await;
+                        match Commands
+                            .generate_output(ui_state(), current.data_id, generate_options, format)
+                            .await
+                            .and_then(|data| {
+                                String::from_utf8(data)
+                                    .map_err(|e| format!("output isn't valid UTF-8: {e}"))
+                            }) {
+                            Ok(text) if format == OutputFormat::HTML => {
+                                write_html_to_clipboard(&text, &preview).await
+                            }
+                            Ok(text) => write_text_to_clipboard(&text).await,
+                            Err(e) => Err(e),
+                        }
+                    };
+                    match result {
+                        Ok(()) => sender.send(Message::SetStatus(format!(
+                            "Copied links as {} to clipboard",
+                            if format.is_pdf() {
+                                OutputFormat::TEXT.as_str()
+                            } else {
+                                format.as_str()
+                            }
+                        ))),
+                        Err(e) => sender.send(Message::SetError(format!(
                             "Failed to copy links to clipboard: {e}"
-                        )));
+                        ))),
                     }
                 });
             }
             Message::WriteLinksToFile => {
+                // Saved instead of generating a document:
+                let preview = self
+                    .output_options
+                    .save_preview
+                    .then(|| self.preview.clone());
+                if preview.as_ref().is_some_and(|preview| preview.is_empty()) {
+                    self.set_status(
+                        "Failed to save links: there is no preview to save".to_owned(),
+                        true,
+                        sender,
+                    );
+                    return;
+                }
                 let options = self.output_options.clone();
-                let open_group_indexes = self.selected_open_window_groups.clone();
-                let closed_group_indexes = self.selected_closed_window_groups.clone();
-                let has_any_filter =
-                    !open_group_indexes.is_empty() || !closed_group_indexes.is_empty();
+                let generate_options = self.selected_generate_options();
                 log::info!("Saving links with {options:?}");
                 spawn(async move {
                     sender.send(Message::SetStatus("Saving links".to_owned()));
-                    let save_path = if cfg!(any(
-                        not(target_family = "wasm"),
-                        not(feature = "wasm-standalone")
-                    )) {
+                    if cfg!(any(not(target_family = "wasm"), not(feature = "wasm-standalone"))) {
                         // Use specified save path for native and Tauri frontend:
 
                         let Some(save_path) = Commands.get_save_path(ui_state()).await else {
-                            sender.send(Message::SetStatus(
+                            sender.send(Message::SetError(
                                 "Failed to save links: no save path selected".to_owned(),
                             ));
                             return;
                         };
                         sender.send(Message::SetStatus(format!("Saving links to {}", save_path)));
-                        save_path
-                    } else {
-                        String::new()
-                    };
+                    }
+
+                    #[cfg(all(target_family = "wasm", feature = "wasm-standalone"))]
+                    {
+                        // Stream large files to disk instead of downloading a
+                        // single blob:
+                        if let Err(e) = file_picker::open_save_stream().await {
+                            log::warn!("Failed to open save file stream, will download file instead: {e:?}");
+                        }
+                    }
+
+                    if let Some(preview) = preview {
+                        let result = Commands.save_text(ui_state(), preview, options).await;
+                        // The file exists now:
+                        sender.send(Message::ValidateSavePath);
+                        sender.send(match result {
+                            // The extension is always changed to ".txt":
+                            Ok(saved) => {
+                                let location = match saved.paths.first() {
+                                    Some(path) => format!(" at: {path}"),
+                                    None => String::new(),
+                                };
+                                match saved.reveal_error {
+                                    Some(e) => Message::SetError(format!(
+                                        "Saved the preview as a text file{location} but {e}"
+                                    )),
+                                    None => Message::SetStatus(format!(
+                                        "Successfully saved the preview as a text file{location}"
+                                    )),
+                                }
+                            }
+                            Err(e) => Message::SetError(format!(
+                                "Failed to save the preview to file: {e}"
+                            )),
+                        });
+                        return;
+                    }
 
                     let current = Commands
                         .get_info_for_slot(ui_state(), FileSlot::Current)
                         .await;
-                    if let Err(e) = Commands
+                    let font_warning = if options.format.is_typst() {
+                        Commands
+                            .typst_font_warning(ui_state(), current.data_id, generate_options.clone())
+                            .await
+                            .ok()
+                            .flatten()
+                    } else {
+                        None
+                    };
+                    let text_encoding = options.text_encoding;
+                    let result = Commands
                         .save_links(
                             ui_state(),
                             current.data_id,
-                            GenerateOptions {
-                                open_group_indexes: Some(open_group_indexes)
-                                    .filter(|_| has_any_filter),
-                                closed_group_indexes: Some(closed_group_indexes),
-                                ..Default::default()
-                            },
+                            generate_options,
                             options,
                         )
-                        .await
-                    {
-                        sender.send(Message::SetStatus(format!(
-                            "Failed to save links to file: {e}"
-                        )));
-                    } else if save_path.is_empty() {
-                        sender.send(Message::SetStatus(
-                            "Successfully saved links to a file".to_owned(),
+                        .await;
+                    let saved = match result {
+                        Ok(saved) => saved,
+                        Err(e) => {
+                            sender.send(Message::SetError(format!(
+                                "Failed to save links to file: {e}"
+                            )));
+                            return;
+                        }
+                    };
+                    let files = match saved.file_count {
+                        1 => "a file".to_owned(),
+                        count => format!("{count} files"),
+                    };
+                    let mut warnings = font_warning.into_iter().collect::<Vec<_>>();
+                    if saved.replaced_characters > 0 {
+                        warnings.push(format!(
+                            "{} characters that can't be represented in {} were replaced",
+                            saved.replaced_characters,
+                            text_encoding.as_str()
                         ));
+                    }
+                    warnings.extend(saved.reveal_error);
+                    // The file exists now:
+                    sender.send(Message::ValidateSavePath);
+                    if !warnings.is_empty() {
+                        sender.send(Message::SetError(format!(
+                            "Saved links to {files} but {}",
+                            warnings.join(" and ")
+                        )));
                     } else {
+                        let location = match saved.paths.as_slice() {
+                            [] => String::new(),
+                            [path] => format!(" at: {path}"),
+                            [first, .., last] => format!(", from {first} to {last}"),
+                        };
                         sender.send(Message::SetStatus(format!(
-                            "Successfully saved links to a file at: {save_path}"
+                            "Successfully saved links to {files}{location}"
                         )));
                     }
                 });
             }
+            Message::PreviewPdf => {
+                let output_options = self.output_options.clone();
+                let generate_options = self.selected_generate_options();
+                spawn(async move {
+                    use base64::Engine;
+
+                    sender.send(Message::SetStatus("Generating PDF preview".to_owned()));
+                    let current = Commands
+                        .get_info_for_slot(ui_state(), FileSlot::Current)
+                        .await;
+                    match Commands
+                        .render_pdf_preview(
+                            ui_state(),
+                            current.data_id,
+                            generate_options,
+                            output_options,
+                        )
+                        .await
+                    {
+                        Ok(png) => {
+                            let url = format!(
+                                "data:image/png;base64,{}",
+                                base64::engine::general_purpose::STANDARD.encode(png)
+                            );
+                            sender.send(Message::SetPdfPreview(Some(url)));
+                            sender.send(Message::SetStatus("Showing PDF preview".to_owned()));
+                        }
+                        Err(e) => sender.send(Message::SetError(format!(
+                            "Failed to generate PDF preview: {e}"
+                        ))),
+                    }
+                });
+            }
+            #[cfg(not(target_family = "wasm"))]
+            Message::OpenInNewTab => {}
+            #[cfg(target_family = "wasm")]
+            Message::OpenInNewTab => {
+                let tab = match take_pending_tab() {
+                    Ok(tab) => tab,
+                    Err(e) => {
+                        sender.send(Message::SetError(format!("Failed to open a new tab: {e}")));
+                        return;
+                    }
+                };
+                let output_options = self.output_options.clone();
+                let generate_options = self.selected_generate_options();
+                spawn(async move {
+                    sender.send(Message::SetStatus("Generating document".to_owned()));
+                    let current = Commands
+                        .get_info_for_slot(ui_state(), FileSlot::Current)
+                        .await;
+                    let result = Commands
+                        .generate_data_url(ui_state(), current.data_id, generate_options, output_options)
+                        .await
+                        .map_err(|e| format!("Failed to generate document: {e}"))
+                        .and_then(|url| {
+                            show_in_tab(&tab, &url)
+                                .map_err(|e| format!("Failed to open document in new tab: {e}"))
+                        });
+                    match result {
+                        Ok(()) => {
+                            sender.send(Message::SetStatus("Opened document in new tab".to_owned()))
+                        }
+                        Err(e) => {
+                            let _ = tab.close();
+                            sender.send(Message::SetError(e));
+                        }
+                    }
+                });
+            }
+            Message::SetPdfPreview(url) => {
+                self.pdf_preview = url;
+            }
+            Message::CompareTreeSources => {
+                // Compare the first selected window:
+                let (is_closed, group_index) = match (
+                    self.selected_open_window_groups.first(),
+                    self.selected_closed_window_groups.first(),
+                ) {
+                    (Some(&ix), _) => (false, ix),
+                    (None, Some(&ix)) => (true, ix),
+                    (None, None) => (false, 0),
+                };
+                spawn(async move {
+                    sender.send(Message::SetStatus("Comparing tree sources".to_owned()));
+                    let current = Commands
+                        .get_info_for_slot(ui_state(), FileSlot::Current)
+                        .await;
+                    match Commands
+                        .compare_tree_sources(ui_state(), current.data_id, is_closed, group_index)
+                        .await
+                    {
+                        Ok(comparison) => {
+                            sender.send(Message::SetTreeSourceComparison(comparison));
+                            sender.send(Message::SetStatus(format!(
+                                "Comparing tree sources for {} window {}",
+                                if is_closed { "closed" } else { "open" },
+                                group_index + 1
+                            )));
+                        }
+                        Err(e) => sender.send(Message::SetError(format!(
+                            "Failed to compare tree sources: {e}"
+                        ))),
+                    }
+                });
+            }
+            Message::SetTreeSourceComparison(comparison) => {
+                self.tree_source_comparison = comparison;
+                self.selected_tree_source = 0;
+            }
+            Message::SelectTreeSourceComparison(index) => {
+                self.selected_tree_source = index;
+            }
+            Message::FindOldestTabs => {
+                spawn(async move {
+                    sender.send(Message::SetStatus("Finding oldest tabs".to_owned()));
+                    let current = Commands
+                        .get_info_for_slot(ui_state(), FileSlot::Current)
+                        .await;
+                    match Commands
+                        .oldest_tabs(ui_state(), current.data_id, OLDEST_TABS_LIMIT)
+                        .await
+                    {
+                        Ok(tabs) => {
+                            sender.send(Message::SetStatus(format!(
+                                "Showing the {} least recently used tabs",
+                                tabs.len()
+                            )));
+                            sender.send(Message::SetOldestTabs(tabs));
+                        }
+                        Err(e) => sender.send(Message::SetError(format!(
+                            "Failed to find oldest tabs: {e}"
+                        ))),
+                    }
+                });
+            }
+            Message::FindExtensions => {
+                spawn(async move {
+                    let current = Commands
+                        .get_info_for_slot(ui_state(), FileSlot::Current)
+                        .await;
+                    match Commands.referenced_extensions(ui_state(), current.data_id).await {
+                        Ok(extensions) if extensions.is_empty() => sender.send(Message::SetStatus(
+                            "No tabs belong to extensions".to_owned(),
+                        )),
+                        Ok(extensions) => sender.send(Message::SetStatus(format!(
+                            "Tabs from extensions: {}",
+                            extensions.join(", ")
+                        ))),
+                        Err(e) => sender.send(Message::SetError(format!(
+                            "Failed to find extensions: {e}"
+                        ))),
+                    }
+                });
+            }
+            Message::ShowSessionStats => {
+                spawn(async move {
+                    let current = Commands
+                        .get_info_for_slot(ui_state(), FileSlot::Current)
+                        .await;
+                    match Commands.get_session_stats(ui_state(), current.data_id).await {
+                        Ok(stats) => sender.send(Message::SetStatus(format!(
+                            "Session statistics: {}",
+                            format_session_stats(&stats)
+                        ))),
+                        Err(e) => sender.send(Message::SetError(format!(
+                            "Failed to collect statistics: {e}"
+                        ))),
+                    }
+                });
+            }
+            Message::SetOldestTabs(tabs) => {
+                self.oldest_tabs = tabs;
+            }
+            Message::SetWindowCheckboxList(checkbox_list) => {
+                self.window_checkbox_list = checkbox_list;
+            }
+            Message::ExportCombinedUrls => {
+                let options = self.output_options.clone();
+                spawn(async move {
+                    sender.send(Message::SetStatus("Saving combined URLs".to_owned()));
+                    match Commands.export_combined_urls(ui_state(), options).await {
+                        Ok(summary) => sender.send(Message::SetStatus(format!(
+                            "Saved {} unique URLs ({} duplicates removed)",
+                            summary.unique_urls, summary.duplicates_removed
+                        ))),
+                        Err(e) => sender.send(Message::SetError(format!(
+                            "Failed to save combined URLs: {e}"
+                        ))),
+                    }
+                });
+            }
+            Message::ExportNewSinceLast => {
+                let options = self.output_options.clone();
+                let generate_options = self.selected_generate_options();
+                spawn(async move {
+                    sender.send(Message::SetStatus("Saving new tabs".to_owned()));
+                    let current = Commands
+                        .get_info_for_slot(ui_state(), FileSlot::Current)
+                        .await;
+                    match Commands
+                        .export_new_since_last(ui_state(), current.data_id, generate_options, options)
+                        .await
+                    {
+                        Ok(summary) if summary.first_export => {
+                            sender.send(Message::SetStatus(format!(
+                                "Saved all {} tabs since this file hadn't been saved before",
+                                summary.total_tabs
+                            )))
+                        }
+                        Ok(summary) => sender.send(Message::SetStatus(format!(
+                            "Saved {} new tabs out of {} tabs",
+                            summary.new_tabs, summary.total_tabs
+                        ))),
+                        Err(e) => sender.send(Message::SetError(format!(
+                            "Failed to save new tabs: {e}"
+                        ))),
+                    }
+                });
+            }
         }
     }
 }
@@ -1207,6 +4513,19 @@ fn App() -> Element {
         }
     }
 
+    let mut prev_theme = use_signal(|| None);
+    if prev_theme() != Some(state.theme) {
+        prev_theme.set(Some(state.theme));
+
+        // The stylesheet picks colors based on this attribute:
+        let script = match state.theme {
+            Theme::System => "document.documentElement.removeAttribute('data-theme');",
+            Theme::Light => "document.documentElement.setAttribute('data-theme', 'light');",
+            Theme::Dark => "document.documentElement.setAttribute('data-theme', 'dark');",
+        };
+        dioxus::document::eval(script);
+    }
+
     rsx! {
         StyleRef {}
         dialog {
@@ -1239,10 +4558,29 @@ fn App() -> Element {
                                 .map(|v| v.file_path.as_str())
                                 .unwrap_or(profile.file_path.as_str()),
                             value: profile.session_files.first().map(|v| v.file_path.as_str()).unwrap_or_default(),
-                            "{profile.name}"
+                            selected: Some(profile.is_default),
+                            "{format_profile_label(profile)}"
                         }
                     }
                 }
+                // Changing the selection loads a profile, so this is only
+                // needed for the pre-selected default profile:
+                if let Some(file_path) = state
+                    .wizard_profiles
+                    .iter()
+                    .find(|profile| profile.is_default)
+                    .and_then(|profile| profile.session_files.first())
+                    .map(|file| file.file_path.clone())
+                {
+                    button {
+                        title: "Load the session of the selected default profile.",
+                        onclick: move |_| {
+                            sender.send(Message::LoadInputPath(file_path.clone()));
+                            sender.send(Message::CloseWizard);
+                        },
+                        "Open"
+                    }
+                }
                 button {
                     onclick: move |_| {
                         sender.send(Message::CloseWizard);
@@ -1252,18 +4590,52 @@ fn App() -> Element {
             }
         }
         main { class: "contains-columns",
+            onkeydown: move |evt| {
+                let modifiers = evt.modifiers();
+                if !modifiers.ctrl() {
+                    return;
+                }
+                let message = match evt.key() {
+                    Key::Character(c) if c.eq_ignore_ascii_case("z") && !modifiers.shift() => {
+                        Message::Undo
+                    }
+                    Key::Character(c)
+                        if c.eq_ignore_ascii_case("y") || c.eq_ignore_ascii_case("z") =>
+                    {
+                        Message::Redo
+                    }
+                    _ => return,
+                };
+                spawn(async move {
+                    // Leave the shortcut to text fields so that typing can be undone:
+                    let is_editing_text = dioxus::document::eval(IS_EDITING_TEXT_SCRIPT)
+                        .join::<bool>()
+                        .await
+                        .inspect_err(|e| log::warn!("Failed to find the focused element: {e}"))
+                        .unwrap_or(false);
+                    if !is_editing_text {
+                        let mut sender = sender;
+                        sender.send(message);
+                    }
+                });
+            },
             WindowSelect {
                 open_windows: state.open_window_groups.clone(),
                 closed_windows: state.closed_window_groups.clone(),
                 selected_open_windows: state.selected_open_window_groups.clone(),
                 selected_closed_windows: state.selected_closed_window_groups.clone(),
+                checkbox_list: state.window_checkbox_list,
                 on_change: move |(open, closed)| {
                     sender
                         .send(Message::SetSelectedTabGroups {
                             open,
                             closed,
+                            user_change: true,
                         });
                 },
+                on_checkbox_list_change: move |checkbox_list| {
+                    sender.send(Message::SetWindowCheckboxList(checkbox_list));
+                },
             }
             div { class: "contains-rows", style: "flex: 1 1 auto;",
                 InputPanel {
@@ -1272,39 +4644,325 @@ fn App() -> Element {
                     on_input_path_edit: move |path| {
                         sender.send(Message::SetInputPath(path));
                     },
+                    on_paste_input_path: move |()| {
+                        sender.send(Message::PasteInputPath);
+                    },
                     on_input_path_changed: move |id| {
                         sender.send(Message::UpdateInputPath(id));
                     },
                     on_load_new_data: move |()| {
                         sender.send(Message::LoadNewData);
                     },
+                    on_reload: move |()| {
+                        sender.send(Message::ReloadLoadedFile);
+                    },
+                    on_copy_raw_json: move |()| {
+                        sender.send(Message::CopyRawJson);
+                    },
+                    on_clear: move |()| {
+                        sender.send(Message::ClearLoadedData);
+                    },
                     on_open_wizard: move |()| {
                         sender.send(Message::OpenWizard);
                     },
+                    on_register_file_types: move |()| {
+                        sender.send(Message::RegisterFileTypes);
+                    },
+                    recent_paths: state.recent_paths.clone(),
+                    on_recent_path_selected: move |path| {
+                        sender.send(Message::LoadInputPath(path));
+                    },
+                    on_clear_recent_paths: move |()| {
+                        sender.send(Message::ClearRecentPaths);
+                    },
+                    search_filter: state.generate_options.title_url_filter.clone().unwrap_or_default(),
+                    on_search_filter_edit: move |search| {
+                        sender.send(Message::SetSearchFilter(search));
+                    },
                 }
                 div { class: "contains-rows", style: "flex: 1 1 auto;",
-                    label { "Tabs as links:" }
-                    textarea {
-                        id: "preview",
-                        style: "flex: 1 1 auto; resize: none;",
-                        readonly: true,
-                        disabled: true,
-                        value: state.preview.clone(),
+                    if let Some(pdf_preview) = state.pdf_preview.clone() {
+                        div { class: "contains-columns",
+                            label { class: "vertically-centered-text", "PDF preview (first page):" }
+                            div { class: "spacer", style: "flex: 1 1 auto;" }
+                            button {
+                                onclick: move |_| {
+                                    sender.send(Message::SetPdfPreview(None));
+                                },
+                                "Close preview"
+                            }
+                        }
+                        div { style: "flex: 1 1 auto; overflow: auto;",
+                            img {
+                                id: "pdf-preview",
+                                style: "max-width: 100%;",
+                                alt: "First page of the PDF",
+                                src: pdf_preview,
+                            }
+                        }
+                    } else if !state.tree_source_comparison.is_empty() {
+                        div { class: "contains-columns",
+                            label { class: "vertically-centered-text", "Tree source:" }
+                            for (index, (source, _)) in state.tree_source_comparison.iter().enumerate() {
+                                button {
+                                    style: "margin-left: 5px;",
+                                    disabled: Some(true).filter(|_| index == state.selected_tree_source),
+                                    onclick: move |_| {
+                                        sender.send(Message::SelectTreeSourceComparison(index));
+                                    },
+                                    "{source.as_str()}"
+                                }
+                            }
+                            div { class: "spacer", style: "flex: 1 1 auto;" }
+                            button {
+                                onclick: move |_| {
+                                    sender.send(Message::SetTreeSourceComparison(Vec::new()));
+                                },
+                                "Close comparison"
+                            }
+                        }
+                        textarea {
+                            id: "tree-source-comparison",
+                            style: "flex: 1 1 auto; resize: none;",
+                            readonly: true,
+                            disabled: true,
+                            value: state
+                                .tree_source_comparison
+                                .get(state.selected_tree_source)
+                                .map(|(_, links)| links.clone())
+                                .unwrap_or_default(),
+                        }
+                    } else if !state.oldest_tabs.is_empty() {
+                        div { class: "contains-columns",
+                            label { class: "vertically-centered-text", "Least recently used tabs:" }
+                            div { class: "spacer", style: "flex: 1 1 auto;" }
+                            button {
+                                onclick: move |_| {
+                                    sender.send(Message::SetOldestTabs(Vec::new()));
+                                },
+                                "Close list"
+                            }
+                        }
+                        textarea {
+                            id: "oldest-tabs",
+                            style: "flex: 1 1 auto; resize: none;",
+                            readonly: true,
+                            disabled: true,
+                            value: format_oldest_tabs(&state.oldest_tabs),
+                        }
+                    } else {
+                        div { class: "contains-columns",
+                            label { class: "vertically-centered-text", "Tabs as links:" }
+                            if let Some(counts) = &state.tab_counts {
+                                label {
+                                    class: "vertically-centered-text",
+                                    style: "margin-left: 10px;",
+                                    title: "Number of tabs in the selected windows.",
+                                    "{format_tab_counts(counts)}"
+                                }
+                            }
+                            div { class: "spacer", style: "flex: 1 1 auto;" }
+                            button {
+                                title: "Show the links of the first selected window with each source of tree data to see how they differ.",
+                                style: "margin-right: 10px;",
+                                onclick: move |_| {
+                                    sender.send(Message::CompareTreeSources);
+                                },
+                                "Compare tree sources"
+                            }
+                            button {
+                                title: "List the tabs that haven't been used for the longest time.",
+                                style: "margin-right: 10px;",
+                                onclick: move |_| {
+                                    sender.send(Message::FindOldestTabs);
+                                },
+                                "Oldest tabs"
+                            }
+                            button {
+                                title: "Count the windows, tabs, domains, pinned tabs and reader view tabs in the whole session.",
+                                style: "margin-right: 10px;",
+                                onclick: move |_| {
+                                    sender.send(Message::ShowSessionStats);
+                                },
+                                "Statistics"
+                            }
+                            button {
+                                title: "List the extensions that tabs with moz-extension:// URLs belong to.",
+                                style: "margin-right: 10px;",
+                                onclick: move |_| {
+                                    sender.send(Message::FindExtensions);
+                                },
+                                "Extensions"
+                            }
+                            input {
+                                r#type: "checkbox",
+                                id: "record-timings",
+                                checked: "{state.record_timings}",
+                                onchange: move |e| {
+                                    log::trace!("Clicked on record timings checkbox {e:?}");
+                                    sender.send(Message::SetRecordTimings(e.checked()));
+                                },
+                            }
+                            label {
+                                r#for: "record-timings",
+                                title: "Show how long it took to load, decompress, parse and generate links for the next loaded file.",
+                                "Show timings"
+                            }
+                            label {
+                                class: "vertically-centered-text",
+                                r#for: "max-decompressed-size",
+                                title: "Files that are larger than this once decompressed aren't loaded, so that a damaged file can't use up all memory.",
+                                style: "margin-left: 10px;",
+                                "Size limit (MiB):"
+                            }
+                            input {
+                                r#type: "number",
+                                id: "max-decompressed-size",
+                                min: "1",
+                                style: "width: 6em; margin-left: 5px;",
+                                value: "{state.max_decompressed_mib}",
+                                onchange: move |e| {
+                                    let value = e.value();
+                                    log::trace!("Changed decompression size limit to {value:?}");
+                                    if let Ok(max_mib) = value.trim().parse::<u64>() {
+                                        sender.send(Message::SetMaxDecompressedSize(max_mib.max(1)));
+                                    }
+                                },
+                            }
+                        }
+                        textarea {
+                            id: "preview",
+                            style: "flex: 1 1 auto; resize: none;",
+                            readonly: true,
+                            disabled: true,
+                            value: state.preview.clone(),
+                        }
                     }
                 }
                 OutputPanel {
                     output_options: state.output_options.clone(),
+                    generate_options: state.generate_options.clone(),
                     format_info: state.format_info.clone(),
+                    typst_fonts: state.typst_fonts.clone(),
                     output_path: state.save_path.clone(),
+                    save_path_status: state.save_path_status.clone(),
                     on_overwrite_change: move |overwrite| {
                         sender.send(Message::SetOverwrite(overwrite));
                     },
                     on_create_folder_change: move |create_folder| {
                         sender.send(Message::SetCreateFolder(create_folder));
                     },
+                    on_generation_timeout_change: move |timeout| {
+                        sender.send(Message::SetGenerationTimeout(timeout));
+                    },
+                    on_include_export_id_change: move |include_export_id| {
+                        sender.send(Message::SetIncludeExportId(include_export_id));
+                    },
+                    on_restore_bundle_change: move |restore_bundle| {
+                        sender.send(Message::SetRestoreBundle(restore_bundle));
+                    },
+                    on_split_per_window_change: move |split_per_window| {
+                        sender.send(Message::SetSplitPerWindow(split_per_window));
+                    },
+                    on_reveal_after_save_change: move |reveal_after_save| {
+                        sender.send(Message::SetRevealAfterSave(reveal_after_save));
+                    },
+                    on_save_preview_change: move |save_preview| {
+                        sender.send(Message::SetSavePreview(save_preview));
+                    },
+                    on_text_encoding_change: move |text_encoding| {
+                        sender.send(Message::SetTextEncoding(text_encoding));
+                    },
+                    on_page_size_change: move |page_size| {
+                        sender.send(Message::SetPageSize(page_size));
+                    },
+                    on_landscape_change: move |landscape| {
+                        sender.send(Message::SetLandscape(landscape));
+                    },
+                    on_margins_change: move |margins_mm| {
+                        sender.send(Message::SetMargins(margins_mm));
+                    },
+                    on_pdf_render_retries_change: move |retries| {
+                        sender.send(Message::SetPdfRenderRetries(retries));
+                    },
                     on_output_format_change: move |new_format| {
                         sender.send(Message::SetOutputFormat(new_format));
                     },
+                    on_markdown_style_change: move |markdown_style| {
+                        sender.send(Message::SetMarkdownStyle(markdown_style));
+                    },
+                    on_markdown_toc_links_change: move |markdown_toc_links| {
+                        sender.send(Message::SetMarkdownTocLinks(markdown_toc_links));
+                    },
+                    on_use_tst_change: move |use_tst| {
+                        sender.send(Message::SetUseTst(use_tst));
+                    },
+                    on_use_sidebery_change: move |use_sidebery| {
+                        sender.send(Message::SetUseSidebery(use_sidebery));
+                    },
+                    on_flat_links_change: move |flat_links| {
+                        sender.send(Message::SetFlatLinks(flat_links));
+                    },
+                    on_mark_collapsed_tabs_change: move |mark_collapsed_tabs| {
+                        sender.send(Message::SetMarkCollapsedTabs(mark_collapsed_tabs));
+                    },
+                    on_include_counts_change: move |include_counts| {
+                        sender.send(Message::SetIncludeCounts(include_counts));
+                    },
+                    on_mark_closed_windows_change: move |mark_closed_windows| {
+                        sender.send(Message::SetMarkClosedWindows(mark_closed_windows));
+                    },
+                    on_deduplicate_links_change: move |deduplicate_links| {
+                        sender.send(Message::SetDeduplicateLinks(deduplicate_links));
+                    },
+                    on_deduplicate_globally_change: move |deduplicate_globally| {
+                        sender.send(Message::SetDeduplicateGlobally(deduplicate_globally));
+                    },
+                    on_annotate_languages_change: move |annotate_languages| {
+                        sender.send(Message::SetAnnotateLanguages(annotate_languages));
+                    },
+                    on_include_timestamps_change: move |include_timestamps| {
+                        sender.send(Message::SetIncludeTimestamps(include_timestamps));
+                    },
+                    on_include_container_change: move |include_container| {
+                        sender.send(Message::SetIncludeContainer(include_container));
+                    },
+                    on_pinned_only_change: move |pinned_only| {
+                        sender.send(Message::SetPinnedOnly(pinned_only));
+                    },
+                    on_include_closed_tabs_change: move |include_closed_tabs| {
+                        sender.send(Message::SetIncludeClosedTabs(include_closed_tabs));
+                    },
+                    on_reproducible_change: move |reproducible| {
+                        sender.send(Message::SetReproducible(reproducible));
+                    },
+                    on_duplicate_names_change: move |duplicate_names| {
+                        sender.send(Message::SetDuplicateNames(duplicate_names));
+                    },
+                    on_tab_sort_change: move |tab_sort| {
+                        sender.send(Message::SetTabSort(tab_sort));
+                    },
+                    on_group_by_change: move |group_by| {
+                        sender.send(Message::SetGroupBy(group_by));
+                    },
+                    on_html_window_filter_change: move |html_window_filter| {
+                        sender.send(Message::SetHtmlWindowFilter(html_window_filter));
+                    },
+                    on_typst_font_change: move |font| {
+                        sender.send(Message::SetTypstFont(font));
+                    },
+                    on_pdf_outline_change: move |pdf_outline| {
+                        sender.send(Message::SetPdfOutline(pdf_outline));
+                    },
+                    on_typst_template_change: move |template| {
+                        sender.send(Message::SetTypstTemplate(template));
+                    },
+                    on_window_separator_change: move |window_separator| {
+                        sender.send(Message::SetWindowSeparator(window_separator));
+                    },
+                    on_custom_page_break_change: move |page_break| {
+                        sender.send(Message::SetCustomPageBreak(page_break));
+                    },
                     on_output_path_edit: move |path| {
                         sender.send(Message::SetOutputPath(path));
                     },
@@ -1317,6 +4975,41 @@ fn App() -> Element {
                     on_write_to_file: move |_| {
                         sender.send(Message::WriteLinksToFile);
                     },
+                    on_open_in_new_tab: move |_| {
+                        // Must happen while the click is handled:
+                        #[cfg(target_family = "wasm")]
+                        open_pending_tab();
+                        sender.send(Message::OpenInNewTab);
+                    },
+                    on_preview_pdf: move |_| {
+                        sender.send(Message::PreviewPdf);
+                    },
+                    on_export_combined_urls: move |_| {
+                        sender.send(Message::ExportCombinedUrls);
+                    },
+                    on_export_new_since_last: move |_| {
+                        sender.send(Message::ExportNewSinceLast);
+                    },
+                    theme: state.theme,
+                    on_theme_change: move |theme| {
+                        sender.send(Message::SetTheme(theme));
+                    },
+                    on_reset_options: move |_| {
+                        sender.send(Message::ResetOptions);
+                    },
+                }
+                DomainFilterEditor {
+                    allowlist: state.generate_options.domain_allowlist.clone().unwrap_or_default(),
+                    blocklist: state.generate_options.domain_blocklist.clone().unwrap_or_default(),
+                    on_change: move |filters: DomainFilters| {
+                        sender.send(Message::EditDomainFilters(filters));
+                    },
+                }
+                NotificationList {
+                    notifications: state.notifications.iter().cloned().collect::<Vec<_>>(),
+                    on_dismiss: move |id| {
+                        sender.send(Message::DismissNotification(id));
+                    },
                 }
                 // Status Bar:
                 div {
@@ -1327,6 +5020,22 @@ fn App() -> Element {
                         style: "margin: 8px;",
                         "Status: "
                     }
+                    if let Some(current) = state.preview_stage {
+                        div { class: "preview-stages contains-columns",
+                            for stage in PreviewStage::all().iter().copied() {
+                                span {
+                                    class: if stage < current {
+                                        "preview-stage done"
+                                    } else if stage == current {
+                                        "preview-stage active"
+                                    } else {
+                                        "preview-stage"
+                                    },
+                                    "{stage.as_str()}"
+                                }
+                            }
+                        }
+                    }
                     input {
                         r#type: "text",
                         style: "flex: 1 1 auto;",
@@ -1334,8 +5043,49 @@ fn App() -> Element {
                         disabled: true,
                         value: "{state.status}",
                     }
+                    if let Some(length) = &state.preview_length {
+                        label {
+                            class: "vertically-centered-text",
+                            style: "margin-left: 8px;",
+                            title: "Length of the preview. The number of pages is a rough estimate for PDF documents.",
+                            "{format_preview_length(length, state.output_options.format.is_pdf())}"
+                        }
+                    }
+                    if state.damaged_data.is_some() {
+                        button {
+                            title: "Keep the windows that are complete in data that was cut off, windows after the damage are lost.",
+                            style: "margin-left: 8px;",
+                            onclick: move |_| {
+                                sender.send(Message::RecoverWindows);
+                            },
+                            "Recover windows"
+                        }
+                    }
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The values of the `<option>`s in [`WindowSelect`] only contain the
+    /// window's index, not its name, so they can be parsed back whatever the
+    /// windows are called.
+    #[test]
+    fn window_option_values_round_trip() {
+        for is_closed in [false, true] {
+            for ix in [0, 1, 10, u32::MAX] {
+                let value = window_option_value(is_closed, ix as usize);
+                assert_eq!(parse_window_option_value(&value), Some((is_closed, ix)), "{value:?}");
+            }
+        }
+
+        // Values that aren't created by `window_option_value` aren't windows:
+        for value in ["Window 1", "Work, Personal", "o:1,c:2", "x:1", "o:", "c:-1", ",", ""] {
+            assert_eq!(parse_window_option_value(value), None, "{value:?}");
+        }
+    }
+}